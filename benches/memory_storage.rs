@@ -0,0 +1,124 @@
+//! MemoryStorage throughput at large reward counts
+//!
+//! Run with `cargo bench --bench memory_storage`. Set `POKE_BENCH_REWARDS`
+//! to change the number of pre-loaded rewards (default 1,000,000).
+
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pokemon_engine::amount::Amount;
+use pokemon_engine::models::{GameType, Reward};
+use pokemon_engine::storage::{MemoryStorage, Storage};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use uuid::Uuid;
+
+const REWARDS_PER_PLAYER: usize = 20;
+const WRITERS: usize = 8;
+const WRITES_PER_WRITER: usize = 1_000;
+
+fn reward_count() -> usize {
+    std::env::var("POKE_BENCH_REWARDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1_000_000)
+}
+
+fn player_id(index: usize) -> String {
+    format!("player-{index}")
+}
+
+fn reward(player: usize, game: GameType) -> Reward {
+    Reward {
+        id: Uuid::new_v4(),
+        player_id: player_id(player),
+        game,
+        amount: Amount::from_lamports(50_000_000_000), // 50 POKE
+        timestamp: Utc::now(),
+        claimed: false,
+        game_data: serde_json::Value::Null,
+        transaction_signature: None,
+        config_version: None,
+    }
+}
+
+fn populate(rt: &Runtime, rewards: usize) -> (Arc<MemoryStorage>, usize) {
+    let storage = Arc::new(MemoryStorage::new());
+    let players = (rewards / REWARDS_PER_PLAYER).max(1);
+    rt.block_on(async {
+        for i in 0..rewards {
+            let game = if i < players { GameType::Welcome } else { GameType::Battle };
+            storage.create_reward(&reward(i % players, game)).await.unwrap();
+        }
+    });
+    (storage, players)
+}
+
+fn lookups(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let rewards = reward_count();
+    let (storage, players) = populate(&rt, rewards);
+
+    let mut group = c.benchmark_group(format!("memory_storage/{rewards}_rewards"));
+    group.throughput(Throughput::Elements(1));
+
+    let mut next = 0usize;
+    group.bench_function("get_rewards", |b| {
+        b.iter(|| {
+            next = (next + 7919) % players;
+            rt.block_on(storage.get_rewards(&player_id(next))).unwrap()
+        })
+    });
+    group.bench_function("get_pending_rewards", |b| {
+        b.iter(|| {
+            next = (next + 7919) % players;
+            rt.block_on(storage.get_pending_rewards(&player_id(next))).unwrap()
+        })
+    });
+    group.bench_function("has_welcome_bonus", |b| {
+        b.iter(|| {
+            next = (next + 7919) % players;
+            rt.block_on(storage.has_welcome_bonus(&player_id(next))).unwrap()
+        })
+    });
+    group.bench_function("create_reward", |b| {
+        b.iter(|| {
+            next = (next + 7919) % players;
+            rt.block_on(storage.create_reward(&reward(next, GameType::Battle))).unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn concurrent_writes(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let rewards = reward_count();
+    let (storage, players) = populate(&rt, rewards);
+
+    let mut group = c.benchmark_group(format!("memory_storage/{rewards}_rewards"));
+    group.throughput(Throughput::Elements((WRITERS * WRITES_PER_WRITER) as u64));
+    group.sample_size(20);
+    group.bench_function(BenchmarkId::new("concurrent_create_reward", WRITERS), |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let tasks: Vec<_> = (0..WRITERS)
+                    .map(|writer| {
+                        let storage = storage.clone();
+                        tokio::spawn(async move {
+                            for i in 0..WRITES_PER_WRITER {
+                                let player = (writer * WRITES_PER_WRITER + i) % players;
+                                storage.create_reward(&reward(player, GameType::Battle)).await.unwrap();
+                            }
+                        })
+                    })
+                    .collect();
+                for task in tasks {
+                    task.await.unwrap();
+                }
+            })
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, lookups, concurrent_writes);
+criterion_main!(benches);
@@ -1,5 +1,4 @@
 use pokemon_engine::*;
-use pokemon_engine::models::*;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -18,13 +17,14 @@ async fn main() -> Result<()> {
             "is_new_high_score": false,
             "level": 2
         }),
+        locale: None,
     };
     
     let response = engine.process_game_event(&flypoke_event).await?;
     println!("Success: {}", response.success);
     if let Some(reward) = response.reward {
         println!("Reward ID: {}", reward.id);
-        println!("Amount: {} lamports", reward.amount);
+        println!("Amount: {}", reward.amount);
     }
     
     // Example 2: Process Battle event
@@ -37,6 +37,7 @@ async fn main() -> Result<()> {
             "streak": 2,
             "perfect_victory": false
         }),
+        locale: None,
     };
     
     let response = engine.process_game_event(&battle_event).await?;
@@ -48,6 +49,7 @@ async fn main() -> Result<()> {
         player_id: player_id.to_string(),
         game: GameType::Login,
         event_data: serde_json::json!({}),
+        locale: None,
     };
     
     let response = engine.process_game_event(&login_event).await?;
@@ -58,17 +60,17 @@ async fn main() -> Result<()> {
     let pending = engine.get_pending_rewards(player_id).await?;
     println!("Pending rewards: {}", pending.len());
     for reward in pending {
-        println!("  - {}: {} lamports", reward.game, reward.amount);
+        println!("  - {}: {}", reward.game, reward.amount);
     }
     
     // Example 5: Get daily stats
     println!("\n=== Getting Daily Stats ===");
     let today = chrono::Utc::now().date_naive();
     if let Some(stats) = engine.get_daily_stats(player_id, today).await? {
-        println!("FlyPoke: {} lamports", stats.flypoke);
-        println!("Battle: {} lamports", stats.battle);
-        println!("Login: {} lamports", stats.login);
-        println!("Total: {} lamports", stats.total);
+        println!("FlyPoke: {}", stats.flypoke);
+        println!("Battle: {}", stats.battle);
+        println!("Login: {}", stats.login);
+        println!("Total: {}", stats.total);
     }
     
     Ok(())
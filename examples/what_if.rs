@@ -0,0 +1,28 @@
+use pokemon_engine::config::Config;
+use pokemon_engine::event_log::FileEventLog;
+use pokemon_engine::simulation::{events_from_log, what_if};
+
+/// Compare a candidate config against the defaults on a recorded event log
+///
+/// The candidate is a TOML or JSON config file layered over the defaults, as
+/// loaded by `Config::from_file`.
+///
+/// Usage: cargo run --example what_if -- <events.jsonl> <candidate-config.toml|json>
+#[tokio::main]
+async fn main() -> pokemon_engine::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let (Some(log_path), Some(config_path)) = (args.get(1), args.get(2)) else {
+        eprintln!("usage: what_if <events.jsonl> <candidate-config.toml|json>");
+        std::process::exit(2);
+    };
+
+    let log = FileEventLog::open(log_path)?;
+    let candidate = Config::from_file(config_path)?;
+    candidate.ensure_valid()?;
+
+    let events = events_from_log(&log).await?;
+    let report = what_if(&events, Config::default(), candidate).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
@@ -0,0 +1,272 @@
+//! Exact token amounts
+//!
+//! `Amount` counts base units (lamports), so arithmetic never rounds. POKE
+//! values are converted with an explicit number of decimals, normally
+//! `Config::token_decimals`, by exact decimal parsing and formatting rather
+//! than floating point.
+
+use crate::error::{PokemonEngineError, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Decimals of the POKE mint, assumed where no config is at hand
+pub const DEFAULT_DECIMALS: u8 = 9;
+
+/// Most decimals representable: one whole token must fit in a `u64`
+pub const MAX_DECIMALS: u8 = 19;
+
+/// Token amount in base units
+///
+/// Serializes as a plain integer of base units. Deserializes from an integer
+/// or a string of base units; POKE strings need the token's decimals, so they
+/// only parse through `Amount::parse`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(u64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    /// Amount of `lamports` base units
+    pub const fn from_lamports(lamports: u64) -> Self {
+        Amount(lamports)
+    }
+
+    /// Amount in base units
+    pub const fn lamports(self) -> u64 {
+        self.0
+    }
+
+    /// Amount of whole tokens, or `None` if it doesn't fit
+    pub fn from_tokens(tokens: u64, decimals: u8) -> Option<Self> {
+        tokens.checked_mul(unit(decimals)?).map(Amount)
+    }
+
+    /// Parse an amount given in base units (`"50000000000"`, `"50000000000 lamports"`)
+    /// or in POKE (`"50 POKE"`, `"0.5 POKE"`)
+    ///
+    /// POKE amounts are converted exactly; more than `decimals` decimal places
+    /// is an error.
+    pub fn parse(text: &str, decimals: u8) -> Result<Self> {
+        let trimmed = text.trim();
+        if let Some(number) = strip_unit(trimmed, "POKE") {
+            return parse_decimal(text, number, decimals);
+        }
+        let number = strip_unit(trimmed, "lamports").unwrap_or(trimmed);
+        if number.contains('.') {
+            return Err(invalid(text, "base units must be a whole number"));
+        }
+        parse_decimal(text, number, 0)
+    }
+
+    /// Parse an amount given in base units only (`"50000000000"`, `"50000000000 lamports"`)
+    pub fn parse_lamports(text: &str) -> Result<Self> {
+        let trimmed = text.trim();
+        if strip_unit(trimmed, "POKE").is_some() {
+            return Err(invalid(text, "POKE amounts need the token decimals (use Amount::parse)"));
+        }
+        Amount::parse(text, 0)
+    }
+
+    /// Parse a plain decimal number of tokens, such as `"0.1"`
+    pub fn parse_tokens(text: &str, decimals: u8) -> Result<Self> {
+        parse_decimal(text, text.trim(), decimals)
+    }
+
+    /// Format as a decimal number of tokens, without trailing zeros
+    ///
+    /// The result round-trips through `parse_tokens` with the same decimals.
+    pub fn to_tokens_string(self, decimals: u8) -> String {
+        let digits = format!("{:0>width$}", self.0, width = decimals as usize + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            whole.to_string()
+        } else {
+            format!("{}.{}", whole, fraction)
+        }
+    }
+
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0.checked_sub(other.0).map(Amount)
+    }
+
+    pub fn checked_mul(self, factor: u64) -> Option<Amount> {
+        self.0.checked_mul(factor).map(Amount)
+    }
+
+    /// Sum of `amounts`, or `None` on overflow
+    pub fn checked_sum<I: IntoIterator<Item = Amount>>(amounts: I) -> Option<Amount> {
+        amounts.into_iter().try_fold(Amount::ZERO, Amount::checked_add)
+    }
+}
+
+/// Base units per whole token, or `None` if it doesn't fit
+fn unit(decimals: u8) -> Option<u64> {
+    10u64.checked_pow(decimals as u32)
+}
+
+fn invalid(text: &str, reason: &str) -> PokemonEngineError {
+    PokemonEngineError::InvalidAmount(format!("{:?}: {}", text, reason))
+}
+
+/// Parse `number` as a decimal with at most `decimals` places, in base units
+fn parse_decimal(text: &str, number: &str, decimals: u8) -> Result<Amount> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() || !whole.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid(text, "expected a number"));
+    }
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) || (number.contains('.') && fraction.is_empty()) {
+        return Err(invalid(text, "expected a number"));
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(invalid(text, &format!("more than {} decimal places", decimals)));
+    }
+
+    let unit = unit(decimals).ok_or_else(|| invalid(text, "too many decimals"))?;
+    let whole: u64 = whole.parse().map_err(|_| invalid(text, "too large"))?;
+    let fraction: u64 = if fraction.is_empty() {
+        0
+    } else {
+        format!("{:0<width$}", fraction, width = decimals as usize)
+            .parse()
+            .map_err(|_| invalid(text, "too large"))?
+    };
+    whole
+        .checked_mul(unit)
+        .and_then(|lamports| lamports.checked_add(fraction))
+        .map(Amount)
+        .ok_or_else(|| invalid(text, "too large"))
+}
+
+/// Strip a case-insensitive unit suffix, returning the number before it
+fn strip_unit<'a>(text: &'a str, unit: &str) -> Option<&'a str> {
+    let split = text.len().checked_sub(unit.len())?;
+    if !text.is_char_boundary(split) || !text[split..].eq_ignore_ascii_case(unit) {
+        return None;
+    }
+    Some(text[..split].trim_end())
+}
+
+impl fmt::Display for Amount {
+    /// Formats as base units, e.g. `"12500000000 lamports"`
+    ///
+    /// Use `to_tokens_string` with the token's decimals to show POKE.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} lamports", self.0)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = PokemonEngineError;
+
+    /// Parses with `Amount::parse_lamports`
+    fn from_str(text: &str) -> Result<Self> {
+        Amount::parse_lamports(text)
+    }
+}
+
+impl From<u64> for Amount {
+    fn from(lamports: u64) -> Self {
+        Amount(lamports)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Lamports(u64),
+            Text(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Lamports(lamports) => Ok(Amount(lamports)),
+            Repr::Text(text) => Amount::parse_lamports(&text).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tokens_exactly() {
+        assert_eq!(Amount::parse("0.1 POKE", 9).unwrap(), Amount(100_000_000));
+        assert_eq!(Amount::parse("50 poke", 9).unwrap(), Amount(50_000_000_000));
+        assert_eq!(Amount::parse("1.50 POKE", 2).unwrap(), Amount(150));
+        assert_eq!(Amount::parse_tokens("0.000000001", 9).unwrap(), Amount(1));
+    }
+
+    #[test]
+    fn parses_base_units() {
+        assert_eq!(Amount::parse("42", 9).unwrap(), Amount(42));
+        assert_eq!(Amount::parse("42 lamports", 9).unwrap(), Amount(42));
+        assert!(Amount::parse("4.2 lamports", 9).is_err());
+        assert!(Amount::parse_lamports("1 POKE").is_err());
+        assert_eq!("42".parse::<Amount>().unwrap(), Amount(42));
+    }
+
+    #[test]
+    fn rejects_excess_precision() {
+        assert!(Amount::parse("0.0000000001 POKE", 9).is_err());
+        assert!(Amount::parse("0.005 POKE", 2).is_err());
+        // Trailing zeros are not extra precision
+        assert_eq!(Amount::parse("0.0100 POKE", 2).unwrap(), Amount(1));
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert!(Amount::parse("18446744073709551616", 9).is_err());
+        assert!(Amount::parse("18446744074 POKE", 9).is_err());
+        assert_eq!(Amount::parse("18446744073709551615", 9).unwrap(), Amount(u64::MAX));
+        assert!(Amount::parse("1 POKE", MAX_DECIMALS + 1).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        for text in [".5 POKE", "5. POKE", "-1 POKE", "-1", "+1", "", "POKE", "1e3", "1,5 POKE"] {
+            assert!(Amount::parse(text, 9).is_err(), "{:?} should not parse", text);
+        }
+    }
+
+    #[test]
+    fn formats_tokens_without_rounding() {
+        assert_eq!(Amount(100_000_000).to_tokens_string(9), "0.1");
+        assert_eq!(Amount(1).to_tokens_string(9), "0.000000001");
+        assert_eq!(Amount(50_000_000_000).to_tokens_string(9), "50");
+        assert_eq!(Amount(150).to_tokens_string(2), "1.5");
+        assert_eq!(Amount(7).to_tokens_string(0), "7");
+        assert_eq!(Amount(u64::MAX).to_tokens_string(9), "18446744073.709551615");
+        assert_eq!(Amount(u64::MAX).to_tokens_string(MAX_DECIMALS), "1.8446744073709551615");
+    }
+
+    #[test]
+    fn token_strings_round_trip() {
+        for (lamports, decimals) in [(0, 9), (1, 9), (123_456_789, 6), (u64::MAX, 9), (10, 1)] {
+            let amount = Amount(lamports);
+            assert_eq!(Amount::parse_tokens(&amount.to_tokens_string(decimals), decimals).unwrap(), amount);
+        }
+    }
+
+    #[test]
+    fn deserializes_base_units_only() {
+        assert_eq!(serde_json::from_str::<Amount>("42").unwrap(), Amount(42));
+        assert_eq!(serde_json::from_str::<Amount>("\"42 lamports\"").unwrap(), Amount(42));
+        assert!(serde_json::from_str::<Amount>("\"1 POKE\"").is_err());
+        assert_eq!(Amount(42).to_string(), "42 lamports");
+    }
+}
@@ -1,4 +1,20 @@
+use crate::amount::{Amount, DEFAULT_DECIMALS, MAX_DECIMALS};
+use crate::engine::{MAX_FIXED_REWARD_POKE, MIN_FLYPOKE_REWARD_POKE, STREAK_TIERS};
+use crate::error::{PokemonEngineError, Result};
+use crate::solana::{parse_commitment, parse_pubkey};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// Prefix of environment variables that override configuration
+pub const ENV_PREFIX: &str = "POKE_";
+
+/// Separator between path segments in override variable names
+pub const ENV_SEPARATOR: &str = "__";
+
+/// Tables replaced as a whole by a config file rather than merged key by key
+const REPLACED_TABLES: &[&str] = &["rewards.login.streak_rewards"];
 
 /// Configuration for the reward engine
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -6,9 +22,17 @@ pub struct Config {
     pub token_decimals: u8,
     pub rewards: RewardConfig,
     pub solana: SolanaConfig,
+    #[serde(default)]
+    pub vault_monitor: VaultMonitorConfig,
+    #[serde(default)]
+    pub payout_worker: PayoutWorkerConfig,
 }
 
 /// Reward configuration per game type
+///
+/// Every game also takes an optional `max_reward`, the largest single reward
+/// it may pay; events whose computed reward is larger are rejected as out of
+/// range.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RewardConfig {
     pub flypoke: FlyPokeConfig,
@@ -17,108 +41,944 @@ pub struct RewardConfig {
     pub pokedex: PokedexConfig,
     pub login: LoginConfig,
     pub welcome: WelcomeConfig,
+    #[serde(default)]
+    pub daily_total_limit: Option<Amount>, // Cap on a player's daily total across games counted in daily stats
 }
 
 /// Solana configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SolanaConfig {
-    pub rpc_url: String,
+    pub rpc_url: String, // Used when no `endpoints` are configured
+    #[serde(default)]
+    pub endpoints: Vec<RpcEndpointConfig>,
+    #[serde(default)]
+    pub broadcast_sends: bool, // Also push sent transactions to every healthy endpoint
     pub token_mint: Option<String>, // POKE token mint address
     pub reward_vault: Option<String>, // Vault address for rewards
     pub commitment: String, // "confirmed", "finalized", etc.
+    #[serde(default)]
+    pub expected_mint_authority: Option<String>, // Required mint authority, or "none" for a fixed supply
+}
+
+impl SolanaConfig {
+    /// RPC endpoints to use, falling back to `rpc_url` if none are listed
+    pub fn rpc_endpoints(&self) -> Vec<RpcEndpointConfig> {
+        if self.endpoints.is_empty() {
+            vec![RpcEndpointConfig {
+                url: self.rpc_url.clone(),
+                weight: 1,
+            }]
+        } else {
+            self.endpoints.clone()
+        }
+    }
+}
+
+/// RPC endpoint with a routing weight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcEndpointConfig {
+    pub url: String,
+    pub weight: u32, // Relative preference, higher is preferred
+}
+
+/// Vault balance monitor configuration
+///
+/// Ratios compare the vault balance against the total of all unpaid rewards
+/// (pending, claimed or queued), so `1.0` means the vault holds exactly
+/// enough to pay every one of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultMonitorConfig {
+    pub check_interval_secs: u64,
+    pub warning_ratio: f64,  // Emit a warning below this coverage
+    pub critical_ratio: f64, // Emit a critical alert below this coverage
+    pub trip_ratio: f64,     // Trip the circuit breaker below this coverage
+    pub resume_ratio: f64,   // Reset the circuit breaker once coverage is back above this
+    pub pause_issuance: bool, // Also stop issuing new rewards while tripped
+    #[serde(default = "default_max_consecutive_failures")]
+    pub max_consecutive_failures: u32, // Trip after this many failed checks in a row; 0 never trips
+}
+
+fn default_max_consecutive_failures() -> u32 {
+    5
+}
+
+impl Default for VaultMonitorConfig {
+    fn default() -> Self {
+        VaultMonitorConfig {
+            check_interval_secs: 60,
+            warning_ratio: 2.0,
+            critical_ratio: 1.25,
+            trip_ratio: 1.0,
+            resume_ratio: 1.5,
+            pause_issuance: false,
+            max_consecutive_failures: default_max_consecutive_failures(),
+        }
+    }
+}
+
+/// Payout worker configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutWorkerConfig {
+    pub poll_interval_secs: u64,
+    pub batch_size: usize,      // Due payouts fetched per poll
+    pub max_concurrency: usize, // Payouts in flight at once
+    pub max_attempts: u32,      // Transactions submitted before a payout is marked failed
+    pub base_backoff_secs: u64, // Delay after the first failure, doubled on each retry
+    pub max_backoff_secs: u64,
+}
+
+impl Default for PayoutWorkerConfig {
+    fn default() -> Self {
+        PayoutWorkerConfig {
+            poll_interval_secs: 5,
+            batch_size: 32,
+            max_concurrency: 4,
+            max_attempts: 8,
+            base_backoff_secs: 5,
+            max_backoff_secs: 600,
+        }
+    }
 }
 
 /// FlyPoke game configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlyPokeConfig {
-    pub daily_limit: u64, // In lamports
+    pub daily_limit: Amount,
+    #[serde(default)]
+    pub max_reward: Option<Amount>,
 }
 
 /// Battle game configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BattleConfig {
-    pub daily_limit: u64,
-    pub base_reward: u64,
+    pub daily_limit: Amount,
+    pub base_reward: Amount,
+    #[serde(default)]
+    pub max_reward: Option<Amount>,
 }
 
 /// PokeMatch game configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PokeMatchConfig {
-    pub daily_limit: u64,
-    pub base_reward: u64,
-    pub perfect_bonus: u64,
+    pub daily_limit: Amount,
+    pub base_reward: Amount,
+    pub perfect_bonus: Amount,
+    #[serde(default)]
+    pub max_reward: Option<Amount>,
 }
 
 /// Pokedex game configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PokedexConfig {
-    pub base_reward: u64,
-    pub rare_bonus: u64,
+    pub base_reward: Amount,
+    pub rare_bonus: Amount,
+    #[serde(default)]
+    pub max_reward: Option<Amount>,
 }
 
 /// Login configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoginConfig {
-    pub daily_reward: u64,
-    pub streak_rewards: std::collections::HashMap<u32, u64>,
+    pub daily_reward: Amount,
+    pub streak_rewards: std::collections::HashMap<u32, Amount>,
+    #[serde(default)]
+    pub max_reward: Option<Amount>,
 }
 
 /// Welcome bonus configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WelcomeConfig {
-    pub reward: u64,
+    pub reward: Amount,
+    #[serde(default)]
+    pub max_reward: Option<Amount>,
 }
 
 impl Default for Config {
     fn default() -> Self {
+        Config::default_for_decimals(DEFAULT_DECIMALS).expect("default rewards fit at the default decimals")
+    }
+}
+
+impl Config {
+    /// Default configuration for a mint with `decimals` decimals
+    ///
+    /// Reward amounts are the same number of POKE whatever the decimals.
+    /// Returns `None` if they don't fit in base units.
+    pub fn default_for_decimals(decimals: u8) -> Option<Self> {
+        let poke = |tokens: u64| Amount::from_tokens(tokens, decimals);
+
         let mut streak_rewards = std::collections::HashMap::new();
-        streak_rewards.insert(3, 30_000_000_000); // 30 POKE
-        streak_rewards.insert(7, 50_000_000_000); // 50 POKE
+        streak_rewards.insert(3, poke(30)?);
+        streak_rewards.insert(7, poke(50)?);
 
-        Config {
-            token_decimals: 9, // Solana standard
+        Some(Config {
+            token_decimals: decimals,
             rewards: RewardConfig {
                 flypoke: FlyPokeConfig {
-                    daily_limit: 500_000_000_000, // 500 POKE
+                    daily_limit: poke(500)?,
+                    max_reward: Some(poke(120)?),
                 },
                 battle: BattleConfig {
-                    daily_limit: 300_000_000_000, // 300 POKE
-                    base_reward: 50_000_000_000,   // 50 POKE
+                    daily_limit: poke(300)?,
+                    base_reward: poke(50)?,
+                    max_reward: Some(poke(300)?),
                 },
                 pokematch: PokeMatchConfig {
-                    daily_limit: 200_000_000_000, // 200 POKE
-                    base_reward: 20_000_000_000,   // 20 POKE
-                    perfect_bonus: 100_000_000_000, // 100 POKE
+                    daily_limit: poke(200)?,
+                    base_reward: poke(20)?,
+                    perfect_bonus: poke(100)?,
+                    max_reward: Some(poke(120)?),
                 },
                 pokedex: PokedexConfig {
-                    base_reward: 10_000_000_000,  // 10 POKE
-                    rare_bonus: 100_000_000_000,    // 100 POKE
+                    base_reward: poke(10)?,
+                    rare_bonus: poke(100)?,
+                    max_reward: Some(poke(110)?),
                 },
                 login: LoginConfig {
-                    daily_reward: 20_000_000_000,  // 20 POKE
+                    daily_reward: poke(20)?,
                     streak_rewards,
+                    max_reward: Some(poke(50)?),
                 },
                 welcome: WelcomeConfig {
-                    reward: 100_000_000_000,       // 100 POKE
+                    reward: poke(100)?,
+                    max_reward: Some(poke(100)?),
                 },
+                daily_total_limit: None,
             },
             solana: SolanaConfig {
                 rpc_url: "https://api.mainnet-beta.solana.com".to_string(),
+                endpoints: Vec::new(),
+                broadcast_sends: false,
                 token_mint: None,
                 reward_vault: None,
                 commitment: "confirmed".to_string(),
+                expected_mint_authority: None,
             },
+            vault_monitor: VaultMonitorConfig::default(),
+            payout_worker: PayoutWorkerConfig::default(),
+        })
+    }
+
+    /// Load configuration from a TOML or JSON file, then apply environment overrides
+    ///
+    /// The format is picked from the extension (`.toml` or `.json`). Settings
+    /// missing from the file keep their defaults, and `POKE_`-prefixed
+    /// environment variables override both (see `with_env_overrides`).
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| PokemonEngineError::config("", format!("Failed to read {}: {}", path.display(), e)))?;
+
+        let config = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml_str(&text),
+            Some("json") => Self::from_json_str(&text),
+            _ => {
+                return Err(PokemonEngineError::config(
+                    "",
+                    format!("Unsupported config file {} (expected .toml or .json)", path.display()),
+                ))
+            }
+        }
+        .map_err(|e| match e {
+            PokemonEngineError::Config(mut issues) => {
+                for issue in &mut issues {
+                    issue.message = format!("{}: {}", path.display(), issue.message);
+                }
+                PokemonEngineError::Config(issues)
+            }
+            other => other,
+        })?;
+
+        config.with_env_overrides(std::env::vars())
+    }
+
+    /// Parse configuration from TOML, on top of the defaults
+    pub fn from_toml_str(text: &str) -> Result<Self> {
+        let table: toml::Table = toml::from_str(text).map_err(|e| PokemonEngineError::config("", e.to_string()))?;
+        Self::from_layer(serde_json::to_value(table)?)
+    }
+
+    /// Parse configuration from JSON, on top of the defaults
+    pub fn from_json_str(text: &str) -> Result<Self> {
+        let layer = serde_json::from_str(text).map_err(|e| PokemonEngineError::config("", e.to_string()))?;
+        Self::from_layer(layer)
+    }
+
+    /// Apply overrides from environment variables
+    ///
+    /// Variables named `POKE_<SECTION>__<FIELD>` set the field at that path,
+    /// e.g. `POKE_SOLANA__RPC_URL` sets `solana.rpc_url` and
+    /// `POKE_REWARDS__BATTLE__BASE_REWARD="75 POKE"` the battle base reward.
+    /// Values are read as JSON where that parses (numbers, booleans, arrays,
+    /// `null`) and as plain strings otherwise; string fields always take the
+    /// raw value. A `POKE_` variable whose path names no field is an error.
+    pub fn with_env_overrides<I>(self, vars: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut value = serde_json::to_value(&self)?;
+        let mut overrides: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+            .collect();
+        // Apply in a stable order, so nested overrides win over their parents
+        overrides.sort();
+
+        let mut paths = Vec::with_capacity(overrides.len());
+        for (name, raw) in &overrides {
+            let path: Vec<String> = name[ENV_PREFIX.len()..]
+                .split(ENV_SEPARATOR)
+                .map(|segment| segment.to_ascii_lowercase())
+                .collect();
+            if path.iter().any(|segment| segment.is_empty()) {
+                return Err(PokemonEngineError::config("", format!("Invalid override variable {}", name)));
+            }
+            let parent = &path[..path.len() - 1];
+            if !value_at(&value, parent).is_some_and(Value::is_object) {
+                return Err(unknown_override(name, &path));
+            }
+            set_path(&mut value, &path, raw);
+            paths.push((name, path));
+        }
+
+        resolve_amounts(&mut value)?;
+        let config: Config = serde_json::from_value(value)
+            .map_err(|e| PokemonEngineError::config("", format!("Invalid environment override: {}", e)))?;
+
+        // Fields that don't exist are dropped when the config is parsed
+        let parsed = serde_json::to_value(&config)?;
+        match paths.into_iter().find(|(_, path)| value_at(&parsed, path).is_none()) {
+            Some((name, path)) => Err(unknown_override(name, &path)),
+            None => Ok(config),
         }
     }
+
+    /// Merge one layer of settings over the defaults
+    ///
+    /// The defaults are built at the layer's `token_decimals`, so a file that
+    /// only changes the decimals keeps every default reward in POKE.
+    fn from_layer(layer: Value) -> Result<Self> {
+        if !layer.is_object() {
+            return Err(PokemonEngineError::config("", "Configuration must be a table"));
+        }
+        let defaults = match token_decimals(&layer) {
+            // Out of range decimals are reported by `validate`
+            Some(decimals) if decimals <= MAX_DECIMALS => Config::default_for_decimals(decimals).ok_or_else(|| {
+                PokemonEngineError::config(
+                    "token_decimals",
+                    format!("Default rewards don't fit in base units at {} decimals", decimals),
+                )
+            })?,
+            _ => Config::default(),
+        };
+        let mut value = serde_json::to_value(defaults)?;
+        merge(&mut value, layer, "");
+        resolve_amounts(&mut value)?;
+        serde_json::from_value(value).map_err(|e| PokemonEngineError::config("", e.to_string()))
+    }
 }
 
-/// Convert POKE amount to lamports (smallest unit)
-pub fn poke_to_lamports(poke: f64) -> u64 {
-    (poke * 1_000_000_000.0) as u64
+impl Config {
+    /// Check the configuration for mistakes, returning every problem found
+    ///
+    /// Issues with `Severity::Error` make the engine refuse the config (see
+    /// `RewardEngine::new`); warnings flag settings that are legal but almost
+    /// certainly not intended. Chain state (mint, vault) is checked separately
+    /// by `SolanaClient::connect`.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        if self.token_decimals > MAX_DECIMALS {
+            issues.push(ConfigIssue::new(
+                "token_decimals",
+                format!("At most {} decimals are supported", MAX_DECIMALS),
+            ));
+        } else if Amount::from_tokens(MAX_FIXED_REWARD_POKE, self.token_decimals).is_none() {
+            issues.push(ConfigIssue::new(
+                "token_decimals",
+                format!(
+                    "Fixed rewards up to {} POKE don't fit in base units at {} decimals",
+                    MAX_FIXED_REWARD_POKE, self.token_decimals
+                ),
+            ));
+        }
+        self.rewards.validate(self.token_decimals, &mut issues);
+        self.solana.validate(&mut issues);
+        self.vault_monitor.validate(&mut issues);
+        self.payout_worker.validate(&mut issues);
+        issues
+    }
+
+    /// Short fingerprint identifying this exact configuration
+    ///
+    /// Derived from the SHA-256 of the config's canonical JSON form, so equal
+    /// configs always share a fingerprint, across restarts too.
+    pub fn fingerprint(&self) -> String {
+        let canonical = serde_json::to_vec(&serde_json::to_value(self).expect("config serializes"))
+            .expect("config serializes");
+        let hash = solana_sdk::hash::hashv(&[&canonical]);
+        hash.to_bytes()[..8].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Validate, failing with `PokemonEngineError::Config` if any issue is an error
+    pub fn ensure_valid(&self) -> Result<()> {
+        let errors: Vec<ConfigIssue> = self
+            .validate()
+            .into_iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PokemonEngineError::Config(errors))
+        }
+    }
 }
 
-/// Convert lamports to POKE amount
-pub fn lamports_to_poke(lamports: u64) -> f64 {
-    lamports as f64 / 1_000_000_000.0
+impl RewardConfig {
+    fn validate(&self, decimals: u8, issues: &mut Vec<ConfigIssue>) {
+        let min_flypoke = Amount::from_tokens(MIN_FLYPOKE_REWARD_POKE, decimals);
+        if min_flypoke.is_some_and(|min| self.flypoke.daily_limit < min) {
+            issues.push(ConfigIssue::new(
+                "rewards.flypoke.daily_limit",
+                format!(
+                    "{} is below the smallest FlyPoke reward ({} POKE), so no FlyPoke game can pay",
+                    self.flypoke.daily_limit.lamports(),
+                    MIN_FLYPOKE_REWARD_POKE
+                ),
+            ));
+        }
+        check_base_within_limit(issues, "battle", self.battle.base_reward, self.battle.daily_limit);
+        check_base_within_limit(issues, "pokematch", self.pokematch.base_reward, self.pokematch.daily_limit);
+        if self.daily_total_limit == Some(Amount::ZERO) {
+            issues.push(ConfigIssue::warning(
+                "rewards.daily_total_limit",
+                "Zero, so only the welcome bonus can ever pay",
+            ));
+        }
+
+        let perfect = self.pokematch.base_reward.checked_add(self.pokematch.perfect_bonus);
+        if perfect.is_none_or(|perfect| perfect > self.pokematch.daily_limit) {
+            issues.push(ConfigIssue::warning(
+                "rewards.pokematch.perfect_bonus",
+                "base_reward + perfect_bonus exceeds daily_limit, so a perfect game can never pay",
+            ));
+        }
+        if self.pokedex.base_reward.checked_add(self.pokedex.rare_bonus).is_none() {
+            issues.push(ConfigIssue::new("rewards.pokedex.rare_bonus", "base_reward + rare_bonus overflows"));
+        }
+
+        for (path, amount) in [
+            ("rewards.battle.base_reward", self.battle.base_reward),
+            ("rewards.pokematch.base_reward", self.pokematch.base_reward),
+            ("rewards.pokedex.base_reward", self.pokedex.base_reward),
+            ("rewards.login.daily_reward", self.login.daily_reward),
+            ("rewards.welcome.reward", self.welcome.reward),
+        ] {
+            if amount == Amount::ZERO {
+                issues.push(ConfigIssue::warning(path, "Reward is zero"));
+            }
+        }
+
+        // Smallest reward each game pays, which its ceiling must allow
+        for (game, smallest, max_reward) in [
+            ("flypoke", min_flypoke, self.flypoke.max_reward),
+            ("battle", Some(self.battle.base_reward), self.battle.max_reward),
+            ("pokematch", Some(self.pokematch.base_reward), self.pokematch.max_reward),
+            ("pokedex", Some(self.pokedex.base_reward), self.pokedex.max_reward),
+            ("login", Some(self.login.daily_reward), self.login.max_reward),
+            ("welcome", Some(self.welcome.reward), self.welcome.max_reward),
+        ] {
+            if let (Some(smallest), Some(max_reward)) = (smallest, max_reward) {
+                if max_reward < smallest {
+                    issues.push(ConfigIssue::new(
+                        format!("rewards.{}.max_reward", game),
+                        format!(
+                            "{} is below the smallest {} reward ({}), so none can be paid",
+                            max_reward.lamports(),
+                            game,
+                            smallest.lamports()
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let mut tiers: Vec<(&u32, &Amount)> = self.login.streak_rewards.iter().collect();
+        tiers.sort();
+        for (days, amount) in tiers {
+            let path = format!("rewards.login.streak_rewards.{}", days);
+            if *days == 0 {
+                issues.push(ConfigIssue::new(path, "Streak length must be at least 1 day"));
+            } else if !STREAK_TIERS.contains(days) {
+                let paid: Vec<String> = STREAK_TIERS.iter().rev().map(u32::to_string).collect();
+                issues.push(ConfigIssue::warning(
+                    path,
+                    format!("Only {} day streak tiers are paid; this tier is ignored", paid.join(" and ")),
+                ));
+            } else if *amount < self.login.daily_reward {
+                issues.push(ConfigIssue::warning(path, "Streak reward is below the plain daily_reward"));
+            }
+        }
+    }
 }
 
+impl SolanaConfig {
+    fn validate(&self, issues: &mut Vec<ConfigIssue>) {
+        if self.endpoints.is_empty() {
+            check_rpc_url(issues, "solana.rpc_url", &self.rpc_url);
+        }
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            check_rpc_url(issues, &format!("solana.endpoints.{}.url", index), &endpoint.url);
+            if endpoint.weight == 0 {
+                issues.push(ConfigIssue::warning(
+                    format!("solana.endpoints.{}.weight", index),
+                    "Weight 0 is treated as 1",
+                ));
+            }
+        }
+
+        if let Err(issue) = parse_commitment(&self.commitment) {
+            issues.push(issue);
+        }
+        for (path, value) in [
+            ("solana.token_mint", &self.token_mint),
+            ("solana.reward_vault", &self.reward_vault),
+        ] {
+            if let Some(Err(issue)) = value.as_deref().map(|value| parse_pubkey(path, value)) {
+                issues.push(issue);
+            }
+        }
+        if let Some(authority) = self.expected_mint_authority.as_deref().filter(|a| *a != "none") {
+            if let Err(issue) = parse_pubkey("solana.expected_mint_authority", authority) {
+                issues.push(issue);
+            }
+        }
+    }
+}
+
+impl VaultMonitorConfig {
+    fn validate(&self, issues: &mut Vec<ConfigIssue>) {
+        let ratios = [
+            ("vault_monitor.warning_ratio", self.warning_ratio),
+            ("vault_monitor.critical_ratio", self.critical_ratio),
+            ("vault_monitor.trip_ratio", self.trip_ratio),
+            ("vault_monitor.resume_ratio", self.resume_ratio),
+        ];
+        let mut finite = true;
+        for (path, ratio) in ratios {
+            if !ratio.is_finite() || ratio < 0.0 {
+                issues.push(ConfigIssue::new(path, "Ratio must be a non-negative number"));
+                finite = false;
+            }
+        }
+        if finite {
+            if self.resume_ratio < self.trip_ratio {
+                issues.push(ConfigIssue::new(
+                    "vault_monitor.resume_ratio",
+                    "Must not be below trip_ratio, or the breaker resets while the vault is still short",
+                ));
+            }
+            if !(self.trip_ratio <= self.critical_ratio && self.critical_ratio <= self.warning_ratio) {
+                issues.push(ConfigIssue::warning(
+                    "vault_monitor",
+                    "Expected trip_ratio <= critical_ratio <= warning_ratio",
+                ));
+            }
+        }
+        if self.check_interval_secs == 0 {
+            issues.push(ConfigIssue::warning("vault_monitor.check_interval_secs", "0 is treated as 1 second"));
+        }
+    }
+}
+
+impl PayoutWorkerConfig {
+    fn validate(&self, issues: &mut Vec<ConfigIssue>) {
+        if self.batch_size == 0 {
+            issues.push(ConfigIssue::new("payout_worker.batch_size", "Must be at least 1, or nothing is sent"));
+        }
+        if self.max_attempts == 0 {
+            issues.push(ConfigIssue::new("payout_worker.max_attempts", "Must be at least 1, or nothing is sent"));
+        }
+        if self.max_concurrency == 0 {
+            issues.push(ConfigIssue::warning("payout_worker.max_concurrency", "0 is treated as 1"));
+        }
+        if self.poll_interval_secs == 0 {
+            issues.push(ConfigIssue::warning("payout_worker.poll_interval_secs", "0 is treated as 1 second"));
+        }
+        if self.max_backoff_secs < self.base_backoff_secs {
+            issues.push(ConfigIssue::warning(
+                "payout_worker.max_backoff_secs",
+                "Below base_backoff_secs, so every retry waits max_backoff_secs",
+            ));
+        }
+    }
+}
+
+fn check_base_within_limit(issues: &mut Vec<ConfigIssue>, game: &str, base_reward: Amount, daily_limit: Amount) {
+    if base_reward > daily_limit {
+        issues.push(ConfigIssue::new(
+            format!("rewards.{}.base_reward", game),
+            format!(
+                "{} exceeds daily_limit {}, so no {} game can pay",
+                base_reward.lamports(),
+                daily_limit.lamports(),
+                game
+            ),
+        ));
+    }
+}
+
+fn check_rpc_url(issues: &mut Vec<ConfigIssue>, path: &str, url: &str) {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        issues.push(ConfigIssue::new(path, format!("Expected an http(s) URL, got \"{}\"", url)));
+    }
+}
+
+/// Recursively merge `layer` into `base`; tables merge, everything else replaces
+fn merge(base: &mut Value, layer: Value, path: &str) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) if !REPLACED_TABLES.contains(&path) => {
+            for (key, value) in layer {
+                let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value, &child),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Convert amount strings such as `"50 POKE"` to base units
+///
+/// Every setting under `rewards` is an amount. POKE are converted with the
+/// config's own `token_decimals`, so they mean the same on any mint.
+fn resolve_amounts(value: &mut Value) -> Result<()> {
+    let decimals = token_decimals(value).unwrap_or(DEFAULT_DECIMALS);
+    match value.get_mut("rewards") {
+        Some(rewards) => resolve_amount_strings(rewards, "rewards", decimals),
+        None => Ok(()),
+    }
+}
+
+/// `token_decimals` of a config value, if set to a valid `u8`
+fn token_decimals(value: &Value) -> Option<u8> {
+    value
+        .get("token_decimals")
+        .and_then(Value::as_u64)
+        .and_then(|decimals| u8::try_from(decimals).ok())
+}
+
+fn resolve_amount_strings(value: &mut Value, path: &str, decimals: u8) -> Result<()> {
+    match value {
+        Value::String(text) => {
+            let amount = Amount::parse(text, decimals)
+                .map_err(|e| PokemonEngineError::config(path, e.to_string()))?;
+            *value = Value::from(amount.lamports());
+        }
+        Value::Object(table) => {
+            for (key, child) in table.iter_mut() {
+                resolve_amount_strings(child, &format!("{}.{}", path, key), decimals)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Value at `path` below `value`, if every segment exists
+fn value_at<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |current, segment| current.get(segment))
+}
+
+/// Error for an override variable whose path names no config field
+fn unknown_override(name: &str, path: &[String]) -> PokemonEngineError {
+    PokemonEngineError::config(
+        path.join("."),
+        format!("Override variable {} doesn't name a config field", name),
+    )
+}
+
+/// Set the value at `path`, creating tables along the way
+fn set_path(value: &mut Value, path: &[String], raw: &str) {
+    let Some((last, parents)) = path.split_last() else { return };
+    let mut current = value;
+    for segment in parents {
+        if !current.is_object() {
+            *current = Value::Object(Default::default());
+        }
+        current = current
+            .as_object_mut()
+            .expect("just made an object")
+            .entry(segment.clone())
+            .or_insert(Value::Null);
+    }
+    if !current.is_object() {
+        *current = Value::Object(Default::default());
+    }
+
+    let table = current.as_object_mut().expect("just made an object");
+    let parsed = match table.get(last) {
+        Some(Value::String(_)) => Value::String(raw.to_string()),
+        _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+    };
+    table.insert(last.clone(), parsed);
+}
+
+/// How serious a configuration issue is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Error, // The engine refuses the config
+    Warning, // Legal, but probably not intended
+}
+
+/// A single problem found while validating configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigIssue {
+    pub path: String, // Dotted path of the offending field, e.g. "solana.commitment"; empty for the whole config
+    pub message: String,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+impl ConfigIssue {
+    /// Issue with `Severity::Error`
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Issue with `Severity::Warning`
+    pub fn warning(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::new(path, message)
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        match self.severity {
+            Severity::Error => write!(f, "{}", self.message),
+            Severity::Warning => write!(f, "{} (warning)", self.message),
+        }
+    }
+}
+
+/// A config version and the time it takes effect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledConfig {
+    pub effective_from: DateTime<Utc>,
+    pub version: String, // `Config::fingerprint` of `config`
+    pub config: Config,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POKE: u64 = 1_000_000_000;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    fn config_issues(result: Result<Config>) -> Vec<ConfigIssue> {
+        match result {
+            Err(PokemonEngineError::Config(issues)) => issues,
+            other => panic!("expected a config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn toml_layers_over_the_defaults() {
+        let config = Config::from_toml_str(
+            r#"
+            [rewards.battle]
+            base_reward = "75 POKE"
+
+            [rewards.login.streak_rewards]
+            5 = "40 POKE"
+
+            [solana]
+            commitment = "finalized"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.rewards.battle.base_reward, Amount::from_lamports(75 * POKE));
+        assert_eq!(config.rewards.battle.daily_limit, Config::default().rewards.battle.daily_limit);
+        assert_eq!(config.solana.commitment, "finalized");
+        assert_eq!(config.solana.rpc_url, Config::default().solana.rpc_url);
+        // Streak tiers are replaced as a whole
+        assert_eq!(config.rewards.login.streak_rewards.len(), 1);
+        assert_eq!(config.rewards.login.streak_rewards[&5], Amount::from_lamports(40 * POKE));
+    }
+
+    #[test]
+    fn json_layers_over_the_defaults() {
+        let config = Config::from_json_str(
+            r#"{"token_decimals": 6, "rewards": {"pokematch": {"base_reward": "1.5 POKE"}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.token_decimals, 6);
+        assert_eq!(config.rewards.pokematch.base_reward, Amount::from_lamports(1_500_000));
+        assert_eq!(config.vault_monitor.trip_ratio, VaultMonitorConfig::default().trip_ratio);
+        // Defaults are rescaled to the file's decimals
+        assert_eq!(config.rewards.battle.base_reward, Amount::from_lamports(50_000_000));
+        assert_eq!(config.rewards.battle.daily_limit, Amount::from_lamports(300_000_000));
+        assert_eq!(config.rewards.login.streak_rewards[&7], Amount::from_lamports(50_000_000));
+        assert_eq!(config.rewards.welcome.max_reward, Some(Amount::from_lamports(100_000_000)));
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn defaults_that_dont_fit_the_decimals_are_config_errors() {
+        let issues = config_issues(Config::from_json_str(r#"{"token_decimals": 18}"#));
+        assert_eq!(issues[0].path, "token_decimals");
+    }
+
+    #[test]
+    fn malformed_files_are_config_errors() {
+        assert_eq!(config_issues(Config::from_toml_str("rewards = [")).len(), 1);
+        assert_eq!(config_issues(Config::from_json_str("[1, 2]"))[0].message, "Configuration must be a table");
+
+        let bad_amount = config_issues(Config::from_json_str(r#"{"rewards": {"battle": {"base_reward": "lots"}}}"#));
+        assert_eq!(bad_amount[0].path, "rewards.battle.base_reward");
+    }
+
+    #[test]
+    fn env_overrides_set_nested_fields() {
+        let config = Config::default()
+            .with_env_overrides(vars(&[
+                ("POKE_REWARDS__BATTLE__BASE_REWARD", "80 POKE"),
+                ("POKE_SOLANA__RPC_URL", "https://rpc.example.com"),
+                ("POKE_SOLANA__BROADCAST_SENDS", "true"),
+                ("POKE_VAULT_MONITOR__TRIP_RATIO", "0.5"),
+                ("POKE_REWARDS__LOGIN__STREAK_REWARDS__14", "\"90 POKE\""),
+                ("HOME", "/root"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.rewards.battle.base_reward, Amount::from_lamports(80 * POKE));
+        assert_eq!(config.solana.rpc_url, "https://rpc.example.com");
+        assert!(config.solana.broadcast_sends);
+        assert_eq!(config.vault_monitor.trip_ratio, 0.5);
+        assert_eq!(config.rewards.login.streak_rewards[&14], Amount::from_lamports(90 * POKE));
+    }
+
+    #[test]
+    fn env_overrides_naming_no_field_are_rejected() {
+        for name in [
+            "POKE_REWARDS__BATLE__BASE_REWARD",
+            "POKE_REWARDS__BATTLE__BASE_REWARDS",
+            "POKE_TOKEN_DECIMALS__EXTRA",
+            "POKE_UNKNOWN",
+        ] {
+            let issues = config_issues(Config::default().with_env_overrides(vars(&[(name, "1")])));
+            assert_eq!(issues.len(), 1, "{}", name);
+            assert!(issues[0].message.contains(name), "{}", issues[0].message);
+        }
+
+        let issues = config_issues(Config::default().with_env_overrides(vars(&[("POKE_REWARDS__BATLE__BASE_REWARD", "1")])));
+        assert_eq!(issues[0].path, "rewards.batle.base_reward");
+        assert_eq!(config_issues(Config::default().with_env_overrides(vars(&[("POKE_SOLANA____RPC_URL", "x")]))).len(), 1);
+    }
+
+    fn issue_at(issues: &[ConfigIssue], path: &str) -> ConfigIssue {
+        issues
+            .iter()
+            .find(|issue| issue.path == path)
+            .cloned()
+            .unwrap_or_else(|| panic!("no issue at {} in {:?}", path, issues))
+    }
+
+    #[test]
+    fn default_config_is_clean() {
+        assert_eq!(Config::default().validate(), Vec::new());
+        assert!(Config::default().ensure_valid().is_ok());
+    }
+
+    #[test]
+    fn base_reward_above_daily_limit_is_an_error() {
+        let mut config = Config::default();
+        config.rewards.battle.base_reward = Amount::from_lamports(301 * POKE);
+
+        let issue = issue_at(&config.validate(), "rewards.battle.base_reward");
+        assert_eq!(issue.severity, Severity::Error);
+        match config.ensure_valid() {
+            Err(PokemonEngineError::Config(errors)) => assert!(errors.contains(&issue)),
+            other => panic!("expected a config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn zero_limits_and_rewards_are_flagged() {
+        let mut config = Config::default();
+        config.rewards.flypoke.daily_limit = Amount::ZERO;
+        config.rewards.daily_total_limit = Some(Amount::ZERO);
+        config.rewards.welcome.reward = Amount::ZERO;
+        config.rewards.welcome.max_reward = None;
+        config.payout_worker.batch_size = 0;
+        let issues = config.validate();
+
+        assert_eq!(issue_at(&issues, "rewards.flypoke.daily_limit").severity, Severity::Error);
+        assert_eq!(issue_at(&issues, "rewards.daily_total_limit").severity, Severity::Warning);
+        assert_eq!(issue_at(&issues, "rewards.welcome.reward").severity, Severity::Warning);
+        assert_eq!(issue_at(&issues, "payout_worker.batch_size").severity, Severity::Error);
+    }
+
+    #[test]
+    fn other_mistakes_name_their_field() {
+        let mut config = Config::default();
+        config.solana.commitment = "eventually".to_string();
+        config.solana.token_mint = Some("not-a-key".to_string());
+        config.vault_monitor.resume_ratio = 0.5;
+        config.rewards.login.streak_rewards.insert(0, Amount::from_lamports(1));
+        config.rewards.battle.max_reward = Some(Amount::from_lamports(1));
+        config.token_decimals = MAX_DECIMALS + 1;
+        let issues = config.validate();
+
+        for path in [
+            "solana.commitment",
+            "solana.token_mint",
+            "vault_monitor.resume_ratio",
+            "rewards.login.streak_rewards.0",
+            "rewards.battle.max_reward",
+            "token_decimals",
+        ] {
+            assert_eq!(issue_at(&issues, path).severity, Severity::Error, "{}", path);
+        }
+    }
+
+    #[test]
+    fn warnings_do_not_block_construction() {
+        let mut config = Config::default();
+        config.rewards.pokematch.perfect_bonus = config.rewards.pokematch.daily_limit;
+        config.rewards.login.streak_rewards.insert(5, Amount::from_lamports(40 * POKE));
+        config.vault_monitor.check_interval_secs = 0;
+        config.payout_worker.max_concurrency = 0;
+        let issues = config.validate();
+
+        assert_eq!(issues.len(), 4, "{:?}", issues);
+        assert!(issues.iter().all(|issue| issue.severity == Severity::Warning));
+        assert_eq!(issue_at(&issues, "rewards.pokematch.perfect_bonus").severity, Severity::Warning);
+        assert_eq!(issue_at(&issues, "rewards.login.streak_rewards.5").severity, Severity::Warning);
+        assert!(config.ensure_valid().is_ok());
+        assert!(crate::engine::RewardEngine::new(config, Box::new(crate::storage::MemoryStorage::new())).is_ok());
+    }
+}
@@ -0,0 +1,95 @@
+//! Config file watching
+//!
+//! Polls a config file and hot-swaps it into a running engine through
+//! `RewardEngine::reload_config` whenever its contents change. A file that
+//! fails to load or validate is rejected and the active config stays in
+//! place until the file is fixed.
+
+use crate::config::Config;
+use crate::engine::RewardEngine;
+use crate::error::{PokemonEngineError, Result};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type ErrorHandler = Box<dyn Fn(&PokemonEngineError) + Send + Sync>;
+
+/// Watcher that reloads an engine's config when its file changes
+pub struct ConfigWatcher {
+    path: PathBuf,
+    poll_interval: Duration,
+    error_handler: Option<ErrorHandler>,
+    last_loaded: Mutex<Option<String>>, // Fingerprint of the file's last accepted contents
+}
+
+impl ConfigWatcher {
+    /// Create a watcher for a `.toml` or `.json` config file
+    ///
+    /// The file is loaded the same way as `Config::from_file`, environment
+    /// overrides included.
+    pub fn new(path: impl Into<PathBuf>, poll_interval: Duration) -> Self {
+        Self {
+            path: path.into(),
+            poll_interval,
+            error_handler: None,
+            last_loaded: Mutex::new(None),
+        }
+    }
+
+    /// Set a handler called whenever the file is rejected
+    pub fn with_error_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&PokemonEngineError) + Send + Sync + 'static,
+    {
+        self.error_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Load the file once and swap it in if it changed since the last check
+    ///
+    /// On the first check the file is compared with the engine's current
+    /// config. Versions scheduled on the engine are left alone until the file
+    /// itself changes. Returns the new config version, or `None` if nothing
+    /// changed.
+    pub async fn check(&self, engine: &RewardEngine) -> Result<Option<String>> {
+        let result = match Config::from_file(&self.path) {
+            Ok(config) => self.reload_if_changed(engine, config).await,
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = &result {
+            if let Some(handler) = &self.error_handler {
+                handler(err);
+            }
+        }
+        result
+    }
+
+    async fn reload_if_changed(&self, engine: &RewardEngine, config: Config) -> Result<Option<String>> {
+        let fingerprint = config.fingerprint();
+        let known = self
+            .last_loaded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_with(|| engine.config_version())
+            .clone();
+        if known == fingerprint {
+            return Ok(None);
+        }
+        let version = engine.reload_config(config).await?;
+        *self.last_loaded.lock().unwrap_or_else(|e| e.into_inner()) = Some(fingerprint);
+        Ok(Some(version))
+    }
+
+    /// Spawn a background task that checks the file on the poll interval
+    pub fn spawn(self, engine: Arc<RewardEngine>) -> tokio::task::JoinHandle<()> {
+        let period = self.poll_interval.max(Duration::from_millis(100));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let _ = self.check(&engine).await;
+            }
+        })
+    }
+}
@@ -1,6 +1,27 @@
+use crate::amount::Amount;
 use crate::config::Config;
+use crate::error::{PokemonEngineError, Result};
+use crate::models::GameType;
+
+/// FlyPoke reward tiers as (lowest score, reward in whole POKE), best first
+const FLYPOKE_TIERS: &[(u32, u64)] = &[(2000, 100), (1001, 50), (501, 25), (0, 10)];
+
+/// FlyPoke bonus for a new high score, in whole POKE
+const FLYPOKE_HIGH_SCORE_BONUS_POKE: u64 = 20;
+
+/// Smallest FlyPoke reward, in whole POKE
+pub(crate) const MIN_FLYPOKE_REWARD_POKE: u64 = FLYPOKE_TIERS[FLYPOKE_TIERS.len() - 1].1;
+
+/// Largest fixed amount paid (top FlyPoke tier plus the high score bonus), in whole POKE
+pub(crate) const MAX_FIXED_REWARD_POKE: u64 = FLYPOKE_TIERS[0].1 + FLYPOKE_HIGH_SCORE_BONUS_POKE;
+
+/// Login streak lengths paid from `streak_rewards`, longest first
+pub(crate) const STREAK_TIERS: &[u32] = &[7, 3];
 
 /// Reward calculator for different game types
+///
+/// Every computation is checked. Rewards that would overflow or exceed the
+/// game's `max_reward` fail with `EventOutOfRange` instead of being paid.
 pub struct RewardCalculator {
     config: Config,
 }
@@ -11,94 +32,181 @@ impl RewardCalculator {
     }
     
     /// Calculate FlyPoke reward based on score
-    pub fn calculate_flypoke_reward(&self, score: u32, is_new_high_score: bool) -> u64 {
-        let amount = match score {
-            s if s >= 2000 => 100_000_000_000,  // 100 POKE
-            s if s >= 1001 => 50_000_000_000,   // 50 POKE
-            s if s >= 501 => 25_000_000_000,    // 25 POKE
-            _ => 10_000_000_000,                 // 10 POKE
-        };
+    pub fn calculate_flypoke_reward(&self, score: u32, is_new_high_score: bool) -> Result<Amount> {
+        let tier = FLYPOKE_TIERS
+            .iter()
+            .find(|(lowest, _)| score >= *lowest)
+            .map_or(MIN_FLYPOKE_REWARD_POKE, |(_, poke)| *poke);
+        let amount = self.poke(tier)?;
         
         // High score bonus
-        if is_new_high_score {
-            amount + 20_000_000_000  // +20 POKE
+        let amount = if is_new_high_score {
+            amount
+                .checked_add(self.poke(FLYPOKE_HIGH_SCORE_BONUS_POKE)?)
+                .ok_or_else(|| overflow("FlyPoke high score bonus"))?
         } else {
             amount
-        }
+        };
+        
+        within_ceiling(GameType::FlyPoke, amount, self.config.rewards.flypoke.max_reward, Some("score"), || {
+            format!("score {}", score)
+        })
     }
     
     /// Calculate Battle reward
-    pub fn calculate_battle_reward(&self, level: u32, streak: u32) -> u64 {
+    pub fn calculate_battle_reward(&self, level: u32, streak: u32) -> Result<Amount> {
         let base = self.config.rewards.battle.base_reward;
+        let fields = || format!("level {} with streak {}", level, streak);
         
         // Level-based bonus
-        let level_bonus = level as u64 * 20_000_000_000;
+        let level_bonus = self
+            .poke(20)?
+            .checked_mul(level as u64)
+            .ok_or_else(|| out_of_range(GameType::Battle, "level", fields()))?;
         
         // Streak bonus
         let streak_bonus = match streak {
-            s if s >= 3 => 20_000_000_000,
-            s if s >= 2 => 10_000_000_000,
-            _ => 0,
+            s if s >= 3 => self.poke(20)?,
+            s if s >= 2 => self.poke(10)?,
+            _ => Amount::ZERO,
         };
         
-        base + level_bonus + streak_bonus
+        let amount = Amount::checked_sum([base, level_bonus, streak_bonus])
+            .ok_or_else(|| out_of_range(GameType::Battle, "level", fields()))?;
+        within_ceiling(GameType::Battle, amount, self.config.rewards.battle.max_reward, Some("level"), fields)
     }
     
     /// Calculate PokeMatch reward
-    pub fn calculate_pokematch_reward(&self, is_perfect: bool) -> u64 {
+    pub fn calculate_pokematch_reward(&self, is_perfect: bool) -> Result<Amount> {
         let base = self.config.rewards.pokematch.base_reward;
         
-        if is_perfect {
-            base + self.config.rewards.pokematch.perfect_bonus
+        let amount = if is_perfect {
+            base.checked_add(self.config.rewards.pokematch.perfect_bonus)
+                .ok_or_else(|| overflow("PokeMatch perfect bonus"))?
         } else {
             base
-        }
+        };
+        within_ceiling(GameType::PokeMatch, amount, self.config.rewards.pokematch.max_reward, Some("perfect"), || {
+            format!("perfect {}", is_perfect)
+        })
     }
     
     /// Calculate Pokedex reward
-    pub fn calculate_pokedex_reward(&self, is_rare: bool) -> u64 {
+    pub fn calculate_pokedex_reward(&self, is_rare: bool) -> Result<Amount> {
         let base = self.config.rewards.pokedex.base_reward;
         
-        if is_rare {
-            base + self.config.rewards.pokedex.rare_bonus
+        let amount = if is_rare {
+            base.checked_add(self.config.rewards.pokedex.rare_bonus)
+                .ok_or_else(|| overflow("Pokedex rare bonus"))?
         } else {
             base
-        }
+        };
+        within_ceiling(GameType::Pokedex, amount, self.config.rewards.pokedex.max_reward, Some("is_rare"), || {
+            format!("rare {}", is_rare)
+        })
     }
     
     /// Calculate login reward based on streak
-    pub fn calculate_login_reward(&self, streak: u32) -> u64 {
-        let base = self.config.rewards.login.daily_reward;
-        
-        // Check streak bonuses
-        if streak >= 7 {
-            if let Some(bonus) = self.config.rewards.login.streak_rewards.get(&7) {
-                return *bonus;
-            }
-        }
-        
-        if streak >= 3 {
-            if let Some(bonus) = self.config.rewards.login.streak_rewards.get(&3) {
-                return *bonus;
-            }
-        }
+    pub fn calculate_login_reward(&self, streak: u32) -> Result<Amount> {
+        let login = &self.config.rewards.login;
+        let tier = STREAK_TIERS
+            .iter()
+            .filter(|days| streak >= **days)
+            .find_map(|days| login.streak_rewards.get(days));
+        let amount = tier.copied().unwrap_or(login.daily_reward);
         
-        base
+        within_ceiling(GameType::Login, amount, login.max_reward, None, || format!("streak {}", streak))
     }
     
     /// Get welcome reward amount
-    pub fn get_welcome_reward(&self) -> u64 {
-        self.config.rewards.welcome.reward
+    pub fn get_welcome_reward(&self) -> Result<Amount> {
+        let welcome = &self.config.rewards.welcome;
+        within_ceiling(GameType::Welcome, welcome.reward, welcome.max_reward, None, || "welcome bonus".to_string())
     }
     
     /// Get daily limit for a game type
-    pub fn get_daily_limit(&self, game_type: crate::models::GameType) -> Option<u64> {
+    pub fn get_daily_limit(&self, game_type: GameType) -> Option<Amount> {
         match game_type {
-            crate::models::GameType::FlyPoke => Some(self.config.rewards.flypoke.daily_limit),
-            crate::models::GameType::Battle => Some(self.config.rewards.battle.daily_limit),
-            crate::models::GameType::PokeMatch => Some(self.config.rewards.pokematch.daily_limit),
+            GameType::FlyPoke => Some(self.config.rewards.flypoke.daily_limit),
+            GameType::Battle => Some(self.config.rewards.battle.daily_limit),
+            GameType::PokeMatch => Some(self.config.rewards.pokematch.daily_limit),
             _ => None,
         }
     }
+    
+    /// Whole POKE in base units at the configured decimals
+    fn poke(&self, tokens: u64) -> Result<Amount> {
+        Amount::from_tokens(tokens, self.config.token_decimals).ok_or_else(|| {
+            overflow(&format!("{} POKE at {} decimals", tokens, self.config.token_decimals))
+        })
+    }
+}
+
+/// Fail with `EventOutOfRange` if `amount` is above the game's ceiling
+///
+/// `field` names the event data field driving the amount, if any.
+fn within_ceiling(
+    game: GameType,
+    amount: Amount,
+    max_reward: Option<Amount>,
+    field: Option<&str>,
+    fields: impl FnOnce() -> String,
+) -> Result<Amount> {
+    match max_reward {
+        Some(max_reward) if amount > max_reward => Err(PokemonEngineError::EventOutOfRange {
+            field: field.map(str::to_string),
+            message: format!(
+                "{} {} would pay {}, above max_reward {}",
+                game,
+                fields(),
+                amount.lamports(),
+                max_reward.lamports()
+            ),
+        }),
+        _ => Ok(amount),
+    }
+}
+
+fn out_of_range(game: GameType, field: &str, fields: String) -> PokemonEngineError {
+    PokemonEngineError::EventOutOfRange {
+        field: Some(field.to_string()),
+        message: format!("{} {} overflows the reward", game, fields),
+    }
+}
+
+fn overflow(what: &str) -> PokemonEngineError {
+    PokemonEngineError::AmountOverflow(what.to_string())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POKE: u64 = 1_000_000_000;
+
+    #[test]
+    fn flypoke_tiers_span_the_validated_range() {
+        let calculator = RewardCalculator::new(Config::default());
+        let reward = |score, high| calculator.calculate_flypoke_reward(score, high).unwrap().lamports();
+
+        assert_eq!(reward(0, false), MIN_FLYPOKE_REWARD_POKE * POKE);
+        assert_eq!(reward(500, false), MIN_FLYPOKE_REWARD_POKE * POKE);
+        assert_eq!(reward(501, false), 25 * POKE);
+        assert_eq!(reward(1001, false), 50 * POKE);
+        assert_eq!(reward(1999, true), 70 * POKE);
+        assert_eq!(reward(u32::MAX, true), MAX_FIXED_REWARD_POKE * POKE);
+    }
+
+    #[test]
+    fn login_pays_the_longest_streak_tier_reached() {
+        let calculator = RewardCalculator::new(Config::default());
+        let login = Config::default().rewards.login;
+        let reward = |streak| calculator.calculate_login_reward(streak).unwrap();
+
+        assert_eq!(reward(2), login.daily_reward);
+        assert_eq!(reward(3), login.streak_rewards[&3]);
+        assert_eq!(reward(6), login.streak_rewards[&3]);
+        assert_eq!(reward(30), login.streak_rewards[&7]);
+        assert!(STREAK_TIERS.iter().all(|days| login.streak_rewards.contains_key(days)));
+    }
+}
@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+/// Why the circuit breaker was tripped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TripCause {
+    LowVaultFunds,
+    VaultUnreachable, // Vault monitor checks kept failing
+    Manual,
+}
+
+/// Details of an open (tripped) circuit breaker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TripInfo {
+    pub cause: TripCause,
+    pub reason: String,
+    pub tripped_at: DateTime<Utc>,
+}
+
+/// Circuit breaker that pauses payouts (and optionally issuance) while open
+///
+/// Whether issuance is paused too is `VaultMonitorConfig::pause_issuance` of
+/// the config in effect, so it follows config reloads.
+#[derive(Default)]
+pub struct CircuitBreaker {
+    state: RwLock<Option<TripInfo>>,
+}
+
+impl CircuitBreaker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trip the breaker, keeping the original trip info if already open
+    pub fn trip(&self, cause: TripCause, reason: impl Into<String>) {
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        if state.is_none() {
+            *state = Some(TripInfo {
+                cause,
+                reason: reason.into(),
+                tripped_at: Utc::now(),
+            });
+        }
+    }
+
+    /// Reset the breaker so payouts can resume
+    pub fn reset(&self) {
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        *state = None;
+    }
+
+    /// Current trip info, or `None` if the breaker is closed
+    pub fn trip_info(&self) -> Option<TripInfo> {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Check if the breaker is open
+    pub fn is_open(&self) -> bool {
+        self.trip_info().is_some()
+    }
+
+    /// Check if payouts are currently paused
+    pub fn payouts_paused(&self) -> bool {
+        self.is_open()
+    }
+
+    /// Check if new reward issuance is currently paused under `pause_issuance`
+    pub fn issuance_paused(&self, pause_issuance: bool) -> bool {
+        pause_issuance && self.is_open()
+    }
+}
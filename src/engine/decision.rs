@@ -0,0 +1,176 @@
+use crate::messages::{self, MessageArgs, Messages};
+use crate::models::{DailyStats, LoginStreak, Reward, RewardResponse};
+use crate::reason::ReasonCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Inputs to a decision that don't come from the event or storage
+///
+/// Live processing uses the current time and a fresh reward id; replay uses
+/// the values recorded in the event log so decisions come out identical.
+#[derive(Debug, Clone, Copy)]
+pub struct DecisionContext {
+    pub now: DateTime<Utc>,
+    pub reward_id: Uuid,
+}
+
+impl DecisionContext {
+    /// Context for processing an event right now
+    pub fn now() -> Self {
+        Self {
+            now: Utc::now(),
+            reward_id: Uuid::new_v4(),
+        }
+    }
+}
+
+/// Outcome of processing one game event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum Decision {
+    /// Reward issued, with the daily stats and login streak it updates
+    Accepted {
+        reward: Box<Reward>,
+        daily_stats: Option<DailyStats>,
+        login_streak: Option<LoginStreak>,
+    },
+    /// No reward issued
+    Rejected {
+        message: String,
+        daily_limit_reached: bool,
+        #[serde(default)]
+        issuance_paused: bool, // Rejected by the circuit breaker rather than the rules
+        #[serde(default)]
+        reason: Option<ReasonCode>, // Missing in entries logged before reason codes
+    },
+}
+
+impl Decision {
+    pub(crate) fn rejected(reason: ReasonCode, message: &str) -> Self {
+        Decision::Rejected {
+            message: message.to_string(),
+            daily_limit_reached: matches!(reason, ReasonCode::DailyLimitReached | ReasonCode::DailyTotalLimitReached),
+            issuance_paused: reason == ReasonCode::IssuancePaused,
+            reason: Some(reason),
+        }
+    }
+
+    /// Issued reward, if accepted
+    pub fn reward(&self) -> Option<&Reward> {
+        match self {
+            Decision::Accepted { reward, .. } => Some(reward),
+            Decision::Rejected { .. } => None,
+        }
+    }
+
+    /// Response returned to the caller for this decision
+    ///
+    /// The message comes from `messages` in `locale`. `args` supplies what the
+    /// decision doesn't record itself: the game of a rejected event, its daily
+    /// limit and the token decimals. Rejections without a template, or whose
+    /// template needs a value `args` lacks, keep their logged English message;
+    /// accepted rewards fall back to a plain English one. `remaining_allowance`
+    /// is left for the engine to fill in.
+    pub fn to_response(&self, messages: &Messages, locale: Option<&str>, args: &MessageArgs) -> RewardResponse {
+        match self {
+            Decision::Accepted { reward, login_streak, .. } => {
+                let args = MessageArgs {
+                    game: Some(reward.game),
+                    amount: Some(reward.amount),
+                    streak: login_streak.as_ref().map(|s| s.current_streak),
+                    ..*args
+                };
+                RewardResponse {
+                    reward: Some(reward.as_ref().clone()),
+                    success: true,
+                    message: messages
+                        .accepted(locale, &args)
+                        .filter(|m| messages::is_filled(m))
+                        .unwrap_or_else(|| {
+                            format!(
+                                "Reward processed successfully: {} POKE",
+                                reward.amount.to_tokens_string(args.token_decimals)
+                            )
+                        }),
+                    daily_limit_reached: false,
+                    reason: None,
+                    retryable: false,
+                    remaining_allowance: None,
+                }
+            }
+            Decision::Rejected {
+                message,
+                daily_limit_reached,
+                reason,
+                ..
+            } => RewardResponse {
+                reward: None,
+                success: false,
+                message: reason
+                    .and_then(|reason| messages.reason(locale, reason, args))
+                    .filter(|m| messages::is_filled(m))
+                    .unwrap_or_else(|| message.clone()),
+                daily_limit_reached: *daily_limit_reached,
+                reason: *reason,
+                retryable: reason.is_some_and(ReasonCode::is_retryable),
+                remaining_allowance: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::models::GameType;
+
+    fn login_reward() -> Reward {
+        Reward {
+            id: Uuid::new_v4(),
+            player_id: "player1".to_string(),
+            game: GameType::Login,
+            amount: Amount::from_lamports(20_000_000_000),
+            timestamp: Utc::now(),
+            claimed: false,
+            game_data: serde_json::Value::Null,
+            transaction_signature: None,
+            config_version: None,
+        }
+    }
+
+    #[test]
+    fn rejections_keep_the_logged_message_for_unfilled_placeholders() {
+        let messages = Messages::bundled();
+        let decision = Decision::rejected(ReasonCode::DailyLimitReached, "Daily limit reached for battle");
+        let without_limit = MessageArgs {
+            game: Some(GameType::Battle),
+            ..MessageArgs::default()
+        };
+        let response = decision.to_response(&messages, Some("en"), &without_limit);
+        assert_eq!(response.message, "Daily limit reached for battle");
+        assert_eq!(response.reason, Some(ReasonCode::DailyLimitReached));
+        assert!(response.daily_limit_reached);
+
+        let with_limit = MessageArgs {
+            limit: Some(Amount::from_lamports(300_000_000_000)),
+            ..without_limit
+        };
+        let response = decision.to_response(&messages, Some("en"), &with_limit);
+        assert_eq!(response.message, "Daily limit of 300 POKE reached for Battle");
+    }
+
+    #[test]
+    fn accepted_rewards_fall_back_to_a_plain_message_for_unfilled_placeholders() {
+        let messages = Messages::bundled();
+        let decision = Decision::Accepted {
+            reward: Box::new(login_reward()),
+            daily_stats: None,
+            login_streak: None,
+        };
+        let response = decision.to_response(&messages, Some("en"), &MessageArgs::default());
+        assert!(response.success);
+        assert_eq!(response.message, "Reward processed successfully: 20 POKE");
+    }
+}
@@ -1,6 +1,11 @@
 mod calculator;
+mod circuit_breaker;
+mod decision;
 mod reward_engine;
 
 pub use calculator::RewardCalculator;
+pub(crate) use calculator::{MAX_FIXED_REWARD_POKE, MIN_FLYPOKE_REWARD_POKE, STREAK_TIERS};
+pub use circuit_breaker::{CircuitBreaker, TripCause, TripInfo};
+pub use decision::{Decision, DecisionContext};
 pub use reward_engine::RewardEngine;
 
@@ -1,139 +1,655 @@
-use crate::config::Config;
+use crate::amount::Amount;
+use crate::config::{Config, ScheduledConfig};
 use crate::models::*;
-use crate::storage::Storage;
+use crate::storage::snapshot::{self, SnapshotSummary};
+use crate::storage::{RewardPage, RewardQuery, Storage};
 use crate::error::{Result, PokemonEngineError};
 use crate::engine::calculator;
-use chrono::{Utc, NaiveDate};
+use crate::engine::circuit_breaker::CircuitBreaker;
+use crate::engine::decision::{Decision, DecisionContext};
+use crate::event_log::{EventLog, LogRecord};
+use crate::ledger::Ledger;
+use crate::messages::{MessageArgs, Messages};
+use crate::reason::ReasonCode;
+use chrono::{DateTime, Utc, NaiveDate};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use tokio::sync::{Mutex, MutexGuard, OnceCell};
 use uuid::Uuid;
 
-/// Main reward engine for processing game events and distributing rewards
-pub struct RewardEngine {
-    #[allow(dead_code)]
+/// Lock shards serializing state changes per player
+const PLAYER_LOCK_SHARDS: usize = 64;
+
+/// Superseded config versions kept for lookup by fingerprint
+const CONFIG_HISTORY: usize = 32;
+
+/// Sharded per-player locks
+///
+/// Holding a player's lock across decide-and-save keeps concurrent events
+/// for the same player from both passing a daily limit check, and keeps
+/// claims from racing issuance.
+struct PlayerLocks {
+    shards: Vec<Mutex<()>>,
+    hasher: RandomState,
+}
+
+impl PlayerLocks {
+    fn new() -> Self {
+        Self {
+            shards: (0..PLAYER_LOCK_SHARDS).map(|_| Mutex::new(())).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    async fn lock(&self, player_id: &str) -> MutexGuard<'_, ()> {
+        let index = (self.hasher.hash_one(player_id) % self.shards.len() as u64) as usize;
+        self.shards[index].lock().await
+    }
+}
+
+/// A config version together with the calculator built from it
+struct ActiveConfig {
+    effective_from: DateTime<Utc>,
+    version: String,
     config: Config,
     calculator: calculator::RewardCalculator,
+}
+
+impl ActiveConfig {
+    fn new(config: Config, effective_from: DateTime<Utc>) -> Self {
+        Self {
+            effective_from,
+            version: config.fingerprint(),
+            calculator: calculator::RewardCalculator::new(config.clone()),
+            config,
+        }
+    }
+    
+    fn to_scheduled(&self) -> ScheduledConfig {
+        ScheduledConfig {
+            effective_from: self.effective_from,
+            version: self.version.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// Config versions ordered by effective time; never empty
+///
+/// The first version also covers any time before it took effect, so events
+/// replayed from before the engine started still have a config. Up to
+/// `CONFIG_HISTORY` superseded versions are kept ahead of the current one.
+struct ConfigVersions(Vec<Arc<ActiveConfig>>);
+
+impl ConfigVersions {
+    fn at(&self, at: DateTime<Utc>) -> Arc<ActiveConfig> {
+        let index = self.0.partition_point(|v| v.effective_from <= at).max(1);
+        Arc::clone(&self.0[index - 1])
+    }
+
+    /// Add a version in effective-time order, replacing one at the same time
+    fn insert(&mut self, version: Arc<ActiveConfig>) {
+        self.0.retain(|v| v.effective_from != version.effective_from);
+        let index = self.0.partition_point(|v| v.effective_from < version.effective_from);
+        self.0.insert(index, version);
+    }
+
+    /// Drop the oldest superseded versions beyond `CONFIG_HISTORY`
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let superseded = self.0.partition_point(|v| v.effective_from <= now).saturating_sub(1);
+        let excess = superseded.saturating_sub(CONFIG_HISTORY);
+        self.0.drain(..excess);
+    }
+
+    /// Remove the version at `effective_from` if it is later than `now`
+    fn cancel(&mut self, effective_from: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        let before = self.0.len();
+        self.0.retain(|v| v.effective_from <= now || v.effective_from != effective_from);
+        self.0.len() != before
+    }
+}
+
+/// Main reward engine for processing game events and distributing rewards
+pub struct RewardEngine {
+    config_versions: RwLock<ConfigVersions>,
     storage: Box<dyn Storage>,
+    circuit_breaker: Arc<CircuitBreaker>,
+    event_log: Option<Arc<dyn EventLog>>,
+    ledger: Option<Arc<Ledger>>,
+    ledger_rebuilt: OnceCell<()>, // Set once the ledger has been rebuilt from storage
+    messages: Messages,
+    player_locks: PlayerLocks,
 }
 
 impl RewardEngine {
     /// Create a new reward engine
-    pub fn new(config: Config, storage: Box<dyn Storage>) -> Self {
-        let calculator = calculator::RewardCalculator::new(config.clone());
-        Self {
-            config,
-            calculator,
+    ///
+    /// Fails with `PokemonEngineError::Config` if `Config::validate` reports any error.
+    pub fn new(config: Config, storage: Box<dyn Storage>) -> Result<Self> {
+        Self::new_from(config, storage, Utc::now())
+    }
+    
+    /// Create a reward engine whose first config version took effect at `effective_from`
+    pub(crate) fn new_from(config: Config, storage: Box<dyn Storage>, effective_from: DateTime<Utc>) -> Result<Self> {
+        config.ensure_valid()?;
+        Ok(Self {
+            config_versions: RwLock::new(ConfigVersions(vec![Arc::new(ActiveConfig::new(config, effective_from))])),
             storage,
+            circuit_breaker: Arc::new(CircuitBreaker::new()),
+            event_log: None,
+            ledger: None,
+            ledger_rebuilt: OnceCell::new(),
+            messages: Messages::bundled(),
+            player_locks: PlayerLocks::new(),
+        })
+    }
+    
+    /// Record every decision, config change, claim and payout in an append-only event log
+    ///
+    /// Entries are appended before storage is updated; if the storage write
+    /// fails an `Aborted` entry is appended so replay skips the change.
+    pub fn with_event_log(mut self, event_log: Arc<dyn EventLog>) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+    
+    /// Post issuances, claims and payouts to a double-entry ledger
+    ///
+    /// The ledger is rebuilt from storage (`Ledger::rebuild`) before the
+    /// engine first posts to it, so rewards saved before a restart can still
+    /// be claimed and paid. Call `rebuild_ledger` to do that up front.
+    pub fn with_ledger(mut self, ledger: Arc<Ledger>) -> Self {
+        self.ledger = Some(ledger);
+        self.ledger_rebuilt = OnceCell::new();
+        self
+    }
+    
+    /// Rebuild the attached ledger from storage now, if not done already
+    pub async fn rebuild_ledger(&self) -> Result<()> {
+        self.ledger().await.map(|_| ())
+    }
+    
+    /// The attached ledger, rebuilt from storage on first use
+    async fn ledger(&self) -> Result<Option<&Ledger>> {
+        let Some(ledger) = &self.ledger else {
+            return Ok(None);
+        };
+        self.ledger_rebuilt
+            .get_or_try_init(|| async { ledger.rebuild(self.storage.as_ref()).await.map(|_| ()) })
+            .await?;
+        Ok(Some(ledger.as_ref()))
+    }
+    
+    /// Use `messages` for player-facing response messages
+    ///
+    /// Defaults to the bundled `en` and `id` catalogs.
+    pub fn with_messages(mut self, messages: Messages) -> Self {
+        self.messages = messages;
+        self
+    }
+    
+    /// Message catalogs used for responses
+    pub fn messages(&self) -> &Messages {
+        &self.messages
+    }
+    
+    /// Error response localized for `locale`
+    ///
+    /// Like `Messages::error_response`, but also fills the game's daily limit
+    /// from the config in effect now.
+    pub fn error_response(&self, err: &PokemonEngineError, locale: Option<&str>) -> ErrorResponse {
+        let active = self.config_version_at(Utc::now());
+        let mut args = MessageArgs::from_error(err);
+        args.limit = args.game.and_then(|game| active.calculator.get_daily_limit(game));
+        args.token_decimals = active.config.token_decimals;
+        self.messages.error_response_with(err, locale, &args)
+    }
+    
+    /// Get a copy of the configuration in effect now
+    pub fn config(&self) -> Config {
+        self.config_version_at(Utc::now()).config.clone()
+    }
+    
+    /// Get the version of the configuration in effect now
+    ///
+    /// This is `Config::fingerprint` of the config, the same value stamped on
+    /// each reward as `config_version`.
+    pub fn config_version(&self) -> String {
+        self.config_version_at(Utc::now()).version.clone()
+    }
+    
+    /// Preview the config version in effect at `at`
+    ///
+    /// Events are decided under the version returned here for their time,
+    /// so this also shows which version a scheduled change will hand over to.
+    pub fn config_at(&self, at: DateTime<Utc>) -> ScheduledConfig {
+        self.config_version_at(at).to_scheduled()
+    }
+    
+    /// Look up a config version by its fingerprint
+    ///
+    /// Covers the current and scheduled versions and the last
+    /// `CONFIG_HISTORY` superseded ones, so the `config_version` of a recent
+    /// reward resolves to the config that produced it.
+    pub fn config_by_version(&self, version: &str) -> Option<ScheduledConfig> {
+        let versions = self.config_versions.read().unwrap_or_else(|e| e.into_inner());
+        versions.0.iter().rev().find(|v| v.version == version).map(|v| v.to_scheduled())
+    }
+    
+    /// Get the config in effect now followed by every scheduled version
+    pub fn config_schedule(&self) -> Vec<ScheduledConfig> {
+        let now = Utc::now();
+        let versions = self.config_versions.read().unwrap_or_else(|e| e.into_inner());
+        let current = versions.at(now);
+        std::iter::once(current.to_scheduled())
+            .chain(versions.0.iter().filter(|v| v.effective_from > now).map(|v| v.to_scheduled()))
+            .collect()
+    }
+    
+    /// Swap in a new configuration without restarting the engine
+    ///
+    /// The config is validated first and, if it has errors, the active one is
+    /// kept. The swap is atomic: each event is decided entirely under the
+    /// config that was in effect when it started. The replaced version stays
+    /// available to `config_by_version`, versions scheduled for later stay
+    /// scheduled, and the circuit breaker keeps its current
+    /// state. The change is logged before it is applied. Returns the new
+    /// config version.
+    pub async fn reload_config(&self, config: Config) -> Result<String> {
+        config.ensure_valid()?;
+        let now = Utc::now();
+        let active = Arc::new(ActiveConfig::new(config, now));
+        let version = active.version.clone();
+        self.log_config_change(now, &active).await?;
+        
+        let mut versions = self.config_versions.write().unwrap_or_else(|e| e.into_inner());
+        versions.insert(active);
+        versions.prune(now);
+        Ok(version)
+    }
+    
+    /// Schedule a config version to take effect at `effective_from`
+    ///
+    /// Events are decided under the version in effect at their own time, so
+    /// the change applies to every event from `effective_from` on. The config
+    /// is validated now and the change logged; a version already scheduled
+    /// for the same time is replaced. Returns the scheduled config version.
+    pub async fn schedule_config(&self, effective_from: DateTime<Utc>, config: Config) -> Result<String> {
+        if effective_from <= Utc::now() {
+            return Err(PokemonEngineError::config(
+                "",
+                format!(
+                    "Cannot schedule a config at {}: not in the future (use reload_config)",
+                    effective_from.to_rfc3339()
+                ),
+            ));
+        }
+        config.ensure_valid()?;
+        let scheduled = Arc::new(ActiveConfig::new(config, effective_from));
+        let version = scheduled.version.clone();
+        self.log_config_change(Utc::now(), &scheduled).await?;
+        
+        let mut versions = self.config_versions.write().unwrap_or_else(|e| e.into_inner());
+        versions.insert(scheduled);
+        versions.prune(Utc::now());
+        Ok(version)
+    }
+    
+    /// Cancel the config version scheduled at `effective_from`
+    ///
+    /// Returns whether a version was cancelled. Versions already in effect
+    /// can't be cancelled; replace them with `reload_config`.
+    pub async fn cancel_scheduled_config(&self, effective_from: DateTime<Utc>) -> Result<bool> {
+        let now = Utc::now();
+        let scheduled = self
+            .config_versions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .0
+            .iter()
+            .any(|v| v.effective_from > now && v.effective_from == effective_from);
+        if !scheduled {
+            return Ok(false);
         }
+        self.append_to_log(now, LogRecord::ConfigCancelled { effective_from }).await?;
+        Ok(self.config_versions.write().unwrap_or_else(|e| e.into_inner()).cancel(effective_from, now))
+    }
+    
+    /// Apply a logged config change during replay
+    pub(crate) fn replay_config_change(&self, effective_from: DateTime<Utc>, config: Config, recorded_at: DateTime<Utc>) {
+        let version = Arc::new(ActiveConfig::new(config, effective_from));
+        let mut versions = self.config_versions.write().unwrap_or_else(|e| e.into_inner());
+        versions.insert(version);
+        versions.prune(recorded_at);
+    }
+    
+    /// Apply a logged config cancellation during replay
+    pub(crate) fn replay_config_cancel(&self, effective_from: DateTime<Utc>, recorded_at: DateTime<Utc>) {
+        self.config_versions
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .cancel(effective_from, recorded_at);
+    }
+    
+    async fn log_config_change(&self, recorded_at: DateTime<Utc>, version: &ActiveConfig) -> Result<()> {
+        let record = LogRecord::ConfigChanged {
+            fingerprint: version.version.clone(),
+            effective_from: version.effective_from,
+            config: Box::new(version.config.clone()),
+        };
+        self.append_to_log(recorded_at, record).await?;
+        Ok(())
+    }
+    
+    fn config_version_at(&self, at: DateTime<Utc>) -> Arc<ActiveConfig> {
+        self.config_versions.read().unwrap_or_else(|e| e.into_inner()).at(at)
     }
     
     /// Process FlyPoke game event
-    pub async fn process_flypoke_event(
+    pub async fn process_flypoke_event(&self, player_id: &str, event_data: &FlyPokeEventData) -> Result<RewardResponse> {
+        self.process_flypoke_event_with_locale(player_id, event_data, None).await
+    }
+    
+    /// Process FlyPoke game event, with the response message in `locale`
+    pub async fn process_flypoke_event_with_locale(
         &self,
         player_id: &str,
         event_data: &FlyPokeEventData,
+        locale: Option<&str>,
     ) -> Result<RewardResponse> {
+        self.process_game_event(&GameEvent {
+            player_id: player_id.to_string(),
+            game: GameType::FlyPoke,
+            event_data: serde_json::to_value(event_data)?,
+            locale: locale.map(str::to_string),
+        })
+        .await
+    }
+    
+    /// Process Battle game event
+    pub async fn process_battle_event(&self, player_id: &str, event_data: &BattleEventData) -> Result<RewardResponse> {
+        self.process_battle_event_with_locale(player_id, event_data, None).await
+    }
+    
+    /// Process Battle game event, with the response message in `locale`
+    pub async fn process_battle_event_with_locale(
+        &self,
+        player_id: &str,
+        event_data: &BattleEventData,
+        locale: Option<&str>,
+    ) -> Result<RewardResponse> {
+        self.process_game_event(&GameEvent {
+            player_id: player_id.to_string(),
+            game: GameType::Battle,
+            event_data: serde_json::to_value(event_data)?,
+            locale: locale.map(str::to_string),
+        })
+        .await
+    }
+    
+    /// Process login event
+    pub async fn process_login_event(&self, player_id: &str) -> Result<RewardResponse> {
+        self.process_login_event_with_locale(player_id, None).await
+    }
+    
+    /// Process login event, with the response message in `locale`
+    pub async fn process_login_event_with_locale(&self, player_id: &str, locale: Option<&str>) -> Result<RewardResponse> {
+        self.process_game_event(&GameEvent {
+            player_id: player_id.to_string(),
+            game: GameType::Login,
+            event_data: serde_json::Value::Null,
+            locale: locale.map(str::to_string),
+        })
+        .await
+    }
+    
+    /// Process welcome event
+    pub async fn process_welcome_event(&self, player_id: &str) -> Result<RewardResponse> {
+        self.process_welcome_event_with_locale(player_id, None).await
+    }
+    
+    /// Process welcome event, with the response message in `locale`
+    pub async fn process_welcome_event_with_locale(&self, player_id: &str, locale: Option<&str>) -> Result<RewardResponse> {
+        self.process_game_event(&GameEvent {
+            player_id: player_id.to_string(),
+            game: GameType::Welcome,
+            event_data: serde_json::Value::Null,
+            locale: locale.map(str::to_string),
+        })
+        .await
+    }
+    
+    /// Process generic game event
+    pub async fn process_game_event(&self, event: &GameEvent) -> Result<RewardResponse> {
+        let _player = self.player_locks.lock(&event.player_id).await;
+        let context = DecisionContext::now();
+        let decision = match self.decide(event, &context).await {
+            Ok(decision) => decision,
+            Err(err) => {
+                let record = LogRecord::FailedEvent {
+                    event: event.clone(),
+                    reason: err.reason_code(),
+                    error: err.to_string(),
+                };
+                // Best effort: the decision error is the one worth reporting
+                let _ = self.append_to_log(context.now, record).await;
+                return Err(err);
+            }
+        };
+        
+        let ledger = match decision {
+            Decision::Accepted { .. } => self.ledger().await?,
+            Decision::Rejected { .. } => None,
+        };
+        let record = LogRecord::GameEvent {
+            event: event.clone(),
+            decision: Box::new(decision.clone()),
+        };
+        let sequence = self.append_to_log(context.now, record).await?;
+        
+        if let Decision::Accepted { reward, daily_stats, login_streak } = &decision {
+            // Ledger first: a reward it refuses is never saved
+            if let Some(ledger) = ledger {
+                let issued = ledger.issue(reward).map(|_| ());
+                self.abort_on_error(sequence, issued).await?;
+            }
+            
+            let saved = self
+                .storage
+                .record_reward(reward, daily_stats.as_ref(), login_streak.as_ref())
+                .await;
+            if let (Err(err), Some(ledger)) = (&saved, ledger) {
+                // Balance the issuance already posted
+                let _ = ledger.revoke(&reward.id, &format!("Not saved: {}", err));
+            }
+            self.abort_on_error(sequence, saved).await?;
+        }
+        
+        self.respond(event, &decision, context.now).await
+    }
+    
+    /// Preview the response to a game event without saving anything
+    ///
+    /// Runs the same decision as `process_game_event` (event data parsing,
+    /// reward calculation, ceilings, daily limits, streaks and the circuit
+    /// breaker) against current state, but nothing is logged or stored. The
+    /// player's lock is held as for processing, so the preview never sees a
+    /// concurrent event half applied. The previewed reward's id is never
+    /// used, and the actual outcome can differ if state changes before the
+    /// event is submitted.
+    pub async fn preview_game_event(&self, event: &GameEvent) -> Result<RewardResponse> {
+        let _player = self.player_locks.lock(&event.player_id).await;
+        let context = DecisionContext::now();
+        let decision = self.decide(event, &context).await?;
+        self.respond(event, &decision, context.now).await
+    }
+    
+    /// Response for a decision, in the event's locale, with the allowance left after it
+    async fn respond(&self, event: &GameEvent, decision: &Decision, now: DateTime<Utc>) -> Result<RewardResponse> {
+        let active = self.config_version_at(now);
+        let limit = match decision {
+            Decision::Rejected { reason: Some(ReasonCode::DailyTotalLimitReached), .. } => {
+                active.config.rewards.daily_total_limit
+            }
+            _ => active.calculator.get_daily_limit(event.game),
+        };
+        let args = MessageArgs {
+            game: Some(event.game),
+            limit,
+            token_decimals: active.config.token_decimals,
+            ..MessageArgs::default()
+        };
+        let mut response = decision.to_response(&self.messages, event.locale.as_deref(), &args);
+        
+        let stats = match decision {
+            Decision::Accepted { daily_stats: Some(stats), .. } => Some(stats.clone()),
+            _ => self.storage.get_daily_stats(&event.player_id, now.date_naive()).await?,
+        };
+        response.remaining_allowance = game_allowance(&active, event.game, stats.as_ref()).remaining;
+        Ok(response)
+    }
+    
+    /// Decide the outcome of a game event without saving anything
+    ///
+    /// Reads current daily stats and streaks from storage; the time and
+    /// reward id come from `context`.
+    pub(crate) async fn decide(&self, event: &GameEvent, context: &DecisionContext) -> Result<Decision> {
+        // One snapshot per event, so a reload can't change the rules mid-decision
+        let active = self.config_version_at(context.now);
+        match event.game {
+            GameType::FlyPoke => {
+                let event_data: FlyPokeEventData = decode_event_data(&event.event_data)?;
+                self.decide_flypoke(&active, &event.player_id, &event_data, context).await
+            }
+            GameType::Battle => {
+                let event_data: BattleEventData = decode_event_data(&event.event_data)?;
+                self.decide_battle(&active, &event.player_id, &event_data, context).await
+            }
+            GameType::Login => {
+                self.decide_login(&active, &event.player_id, context).await
+            }
+            GameType::Welcome => {
+                self.decide_welcome(&active, &event.player_id, context).await
+            }
+            _ => Err(PokemonEngineError::InvalidGameType(format!("{:?}", event.game))),
+        }
+    }
+    
+    async fn decide_flypoke(
+        &self,
+        active: &ActiveConfig,
+        player_id: &str,
+        event_data: &FlyPokeEventData,
+        context: &DecisionContext,
+    ) -> Result<Decision> {
+        if let Some(decision) = self.issuance_paused_decision(active) {
+            return Ok(decision);
+        }
+        
         // Calculate reward
-        let amount = self.calculator.calculate_flypoke_reward(
+        let amount = active.calculator.calculate_flypoke_reward(
             event_data.score,
             event_data.is_new_high_score,
-        );
+        )?;
         
         // Check daily limit
-        if let Some(daily_limit) = self.calculator.get_daily_limit(GameType::FlyPoke) {
-            let today = Utc::now().date_naive();
-            if let Some(stats) = self.storage.get_daily_stats(player_id, today).await? {
-                if stats.flypoke + amount > daily_limit {
-                    return Ok(RewardResponse {
-                        reward: None,
-                        success: false,
-                        message: "Daily limit reached for FlyPoke".to_string(),
-                        daily_limit_reached: true,
-                    });
-                }
+        let today = context.now.date_naive();
+        if let Some(daily_limit) = active.calculator.get_daily_limit(GameType::FlyPoke) {
+            let stats = self.storage.get_daily_stats(player_id, today).await?;
+            let earned = stats.map(|s| s.flypoke).unwrap_or_default();
+            if earned.checked_add(amount).is_none_or(|total| total > daily_limit) {
+                return Ok(Decision::rejected(ReasonCode::DailyLimitReached, "Daily limit reached for FlyPoke"));
             }
         }
+        if let Some(decision) = self.daily_total_decision(active, player_id, amount, today).await? {
+            return Ok(decision);
+        }
         
         // Create reward
         let reward = Reward {
-            id: Uuid::new_v4(),
+            id: context.reward_id,
             player_id: player_id.to_string(),
             game: GameType::FlyPoke,
             amount,
-            timestamp: Utc::now(),
+            timestamp: context.now,
             claimed: false,
             game_data: serde_json::to_value(event_data)?,
             transaction_signature: None,
+            config_version: Some(active.version.clone()),
         };
         
-        // Save reward
-        self.storage.create_reward(&reward).await?;
-        
-        // Update daily stats
-        self.update_daily_stats(player_id, GameType::FlyPoke, amount).await?;
-        
-        Ok(RewardResponse {
-            reward: Some(reward),
-            success: true,
-            message: "Reward processed successfully".to_string(),
-            daily_limit_reached: false,
+        let stats = self.next_daily_stats(player_id, GameType::FlyPoke, amount, today).await?;
+        Ok(Decision::Accepted {
+            reward: Box::new(reward),
+            daily_stats: Some(stats),
+            login_streak: None,
         })
     }
     
-    /// Process Battle game event
-    pub async fn process_battle_event(
+    async fn decide_battle(
         &self,
+        active: &ActiveConfig,
         player_id: &str,
         event_data: &BattleEventData,
-    ) -> Result<RewardResponse> {
+        context: &DecisionContext,
+    ) -> Result<Decision> {
+        if let Some(decision) = self.issuance_paused_decision(active) {
+            return Ok(decision);
+        }
+        
         // Calculate reward
-        let amount = self.calculator.calculate_battle_reward(
+        let amount = active.calculator.calculate_battle_reward(
             event_data.level,
             event_data.streak,
-        );
+        )?;
         
         // Check daily limit
-        if let Some(daily_limit) = self.calculator.get_daily_limit(GameType::Battle) {
-            let today = Utc::now().date_naive();
-            if let Some(stats) = self.storage.get_daily_stats(player_id, today).await? {
-                if stats.battle + amount > daily_limit {
-                    return Ok(RewardResponse {
-                        reward: None,
-                        success: false,
-                        message: "Daily limit reached for Battle".to_string(),
-                        daily_limit_reached: true,
-                    });
-                }
+        let today = context.now.date_naive();
+        if let Some(daily_limit) = active.calculator.get_daily_limit(GameType::Battle) {
+            let stats = self.storage.get_daily_stats(player_id, today).await?;
+            let earned = stats.map(|s| s.battle).unwrap_or_default();
+            if earned.checked_add(amount).is_none_or(|total| total > daily_limit) {
+                return Ok(Decision::rejected(ReasonCode::DailyLimitReached, "Daily limit reached for Battle"));
             }
         }
+        if let Some(decision) = self.daily_total_decision(active, player_id, amount, today).await? {
+            return Ok(decision);
+        }
         
         // Create reward
         let reward = Reward {
-            id: Uuid::new_v4(),
+            id: context.reward_id,
             player_id: player_id.to_string(),
             game: GameType::Battle,
             amount,
-            timestamp: Utc::now(),
+            timestamp: context.now,
             claimed: false,
             game_data: serde_json::to_value(event_data)?,
             transaction_signature: None,
+            config_version: Some(active.version.clone()),
         };
         
-        // Save reward
-        self.storage.create_reward(&reward).await?;
-        
-        // Update daily stats
-        self.update_daily_stats(player_id, GameType::Battle, amount).await?;
-        
-        Ok(RewardResponse {
-            reward: Some(reward),
-            success: true,
-            message: "Reward processed successfully".to_string(),
-            daily_limit_reached: false,
+        let stats = self.next_daily_stats(player_id, GameType::Battle, amount, today).await?;
+        Ok(Decision::Accepted {
+            reward: Box::new(reward),
+            daily_stats: Some(stats),
+            login_streak: None,
         })
     }
     
-    /// Process login event
-    pub async fn process_login_event(&self, player_id: &str) -> Result<RewardResponse> {
-        let today = Utc::now().date_naive();
+    async fn decide_login(
+        &self,
+        active: &ActiveConfig,
+        player_id: &str,
+        context: &DecisionContext,
+    ) -> Result<Decision> {
+        if let Some(decision) = self.issuance_paused_decision(active) {
+            return Ok(decision);
+        }
+        
+        let today = context.now.date_naive();
         
         // Get or create login streak
         let streak = match self.storage.get_login_streak(player_id).await? {
@@ -142,12 +658,7 @@ impl RewardEngine {
                 
                 if streak_data.last_login_date == today {
                     // Already logged in today
-                    return Ok(RewardResponse {
-                        reward: None,
-                        success: false,
-                        message: "Already logged in today".to_string(),
-                        daily_limit_reached: false,
-                    });
+                    return Ok(Decision::rejected(ReasonCode::AlreadyLoggedInToday, "Already logged in today"));
                 } else if streak_data.last_login_date == yesterday {
                     // Consecutive day
                     streak_data.current_streak += 1;
@@ -157,114 +668,96 @@ impl RewardEngine {
                 }
                 
                 streak_data.last_login_date = today;
-                self.storage.update_login_streak(&streak_data).await?;
-                streak_data.current_streak
+                streak_data
             }
             None => {
                 // First login
-                let streak_data = LoginStreak {
+                LoginStreak {
                     player_id: player_id.to_string(),
                     current_streak: 1,
                     last_login_date: today,
-                };
-                self.storage.update_login_streak(&streak_data).await?;
-                1
+                }
             }
         };
         
         // Calculate reward
-        let amount = self.calculator.calculate_login_reward(streak);
+        let amount = active.calculator.calculate_login_reward(streak.current_streak)?;
+        if let Some(decision) = self.daily_total_decision(active, player_id, amount, today).await? {
+            return Ok(decision);
+        }
         
         // Create reward
         let reward = Reward {
-            id: Uuid::new_v4(),
+            id: context.reward_id,
             player_id: player_id.to_string(),
             game: GameType::Login,
             amount,
-            timestamp: Utc::now(),
+            timestamp: context.now,
             claimed: false,
-            game_data: serde_json::json!({ "streak": streak }),
+            game_data: serde_json::json!({ "streak": streak.current_streak }),
             transaction_signature: None,
+            config_version: Some(active.version.clone()),
         };
         
-        // Save reward
-        self.storage.create_reward(&reward).await?;
-        
-        // Update daily stats
-        self.update_daily_stats(player_id, GameType::Login, amount).await?;
-        
-        Ok(RewardResponse {
-            reward: Some(reward),
-            success: true,
-            message: "Login reward processed successfully".to_string(),
-            daily_limit_reached: false,
+        let stats = self.next_daily_stats(player_id, GameType::Login, amount, today).await?;
+        Ok(Decision::Accepted {
+            reward: Box::new(reward),
+            daily_stats: Some(stats),
+            login_streak: Some(streak),
         })
     }
     
-    /// Process welcome event
-    pub async fn process_welcome_event(&self, player_id: &str) -> Result<RewardResponse> {
+    async fn decide_welcome(
+        &self,
+        active: &ActiveConfig,
+        player_id: &str,
+        context: &DecisionContext,
+    ) -> Result<Decision> {
+        if let Some(decision) = self.issuance_paused_decision(active) {
+            return Ok(decision);
+        }
+        
         // Check if welcome bonus already given
         if self.storage.has_welcome_bonus(player_id).await? {
-            return Ok(RewardResponse {
-                reward: None,
-                success: false,
-                message: "Welcome bonus already claimed".to_string(),
-                daily_limit_reached: false,
-            });
+            return Ok(Decision::rejected(ReasonCode::WelcomeBonusAlreadyClaimed, "Welcome bonus already claimed"));
         }
         
         // Get welcome reward
-        let amount = self.calculator.get_welcome_reward();
+        let amount = active.calculator.get_welcome_reward()?;
         
         // Create reward
         let reward = Reward {
-            id: Uuid::new_v4(),
+            id: context.reward_id,
             player_id: player_id.to_string(),
             game: GameType::Welcome,
             amount,
-            timestamp: Utc::now(),
+            timestamp: context.now,
             claimed: false,
             game_data: serde_json::json!({ "type": "welcome_bonus" }),
             transaction_signature: None,
+            config_version: Some(active.version.clone()),
         };
         
-        // Save reward
-        self.storage.create_reward(&reward).await?;
-        
-        Ok(RewardResponse {
-            reward: Some(reward),
-            success: true,
-            message: "Welcome bonus processed successfully".to_string(),
-            daily_limit_reached: false,
+        Ok(Decision::Accepted {
+            reward: Box::new(reward),
+            daily_stats: None,
+            login_streak: None,
         })
     }
     
-    /// Process generic game event
-    pub async fn process_game_event(&self, event: &GameEvent) -> Result<RewardResponse> {
-        match event.game {
-            GameType::FlyPoke => {
-                let event_data: FlyPokeEventData = serde_json::from_value(event.event_data.clone())?;
-                self.process_flypoke_event(&event.player_id, &event_data).await
-            }
-            GameType::Battle => {
-                let event_data: BattleEventData = serde_json::from_value(event.event_data.clone())?;
-                self.process_battle_event(&event.player_id, &event_data).await
-            }
-            GameType::Login => {
-                self.process_login_event(&event.player_id).await
-            }
-            GameType::Welcome => {
-                self.process_welcome_event(&event.player_id).await
-            }
-            _ => Err(PokemonEngineError::InvalidGameType(format!("{:?}", event.game))),
-        }
-    }
-    
     /// Get all rewards for a player
     pub async fn get_rewards(&self, player_id: &str) -> Result<Vec<Reward>> {
         self.storage.get_rewards(player_id).await
     }
     
+    /// Get one page of a player's rewards, filtered and sorted by `query`
+    ///
+    /// Pass the returned `next_cursor` in the next query to fetch the
+    /// following page.
+    pub async fn query_rewards(&self, player_id: &str, query: &RewardQuery) -> Result<RewardPage> {
+        self.storage.query_rewards(player_id, query).await
+    }
+    
     /// Get pending rewards for a player
     pub async fn get_pending_rewards(&self, player_id: &str) -> Result<Vec<Reward>> {
         self.storage.get_pending_rewards(player_id).await
@@ -275,37 +768,635 @@ impl RewardEngine {
         self.storage.get_daily_stats(player_id, date).await
     }
     
+    /// What a player can still earn on `date`, per game, under the config in effect on `date`
+    ///
+    /// That is the config in effect now for today, at the start of the day
+    /// for a future date and at its end for a past one. Covers the games
+    /// counted in daily stats, with each game's remaining amount also capped
+    /// by the global daily limit. Remaining amounts don't account for the
+    /// circuit breaker; check `issuance_paused` for that.
+    pub async fn get_daily_allowance(&self, player_id: &str, date: NaiveDate) -> Result<DailyAllowance> {
+        let now = Utc::now();
+        let starts_at = date
+            .and_hms_opt(0, 0, 0)
+            .map(|midnight| midnight.and_utc())
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let resets_at = date
+            .succ_opt()
+            .and_then(|next| next.and_hms_opt(0, 0, 0))
+            .map(|midnight| midnight.and_utc())
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let active = self.config_version_at(now.clamp(starts_at, resets_at - chrono::Duration::nanoseconds(1)));
+        
+        let stats = self.storage.get_daily_stats(player_id, date).await?;
+        let games = DailyStats::TRACKED_GAMES
+            .into_iter()
+            .map(|game| game_allowance(&active, game, stats.as_ref()))
+            .collect();
+        Ok(DailyAllowance {
+            player_id: player_id.to_string(),
+            date,
+            games,
+            total_earned: stats.as_ref().map(|s| s.total).unwrap_or_default(),
+            global_limit: active.config.rewards.daily_total_limit,
+            global_remaining: global_remaining(&active, stats.as_ref()),
+            issuance_paused: self.circuit_breaker.issuance_paused(active.config.vault_monitor.pause_issuance),
+            resets_at,
+            resets_in_secs: (resets_at - now).num_seconds().max(0) as u64,
+        })
+    }
+    
+    /// Get all rewards that have a payout transaction signature recorded
+    pub async fn get_paid_rewards(&self) -> Result<Vec<Reward>> {
+        self.storage.get_paid_rewards().await
+    }
+    
+    /// Record the payout transaction signature for a reward
+    pub async fn record_payout(&self, reward_id: &Uuid, signature: &str) -> Result<()> {
+        self.record_payout_with_fee(reward_id, signature, 0).await
+    }
+    
+    /// Record the payout transaction signature for a reward, withholding `fee`
+    ///
+    /// An attached ledger posts the fee from the vault to its fees account;
+    /// storage only keeps the signature.
+    pub async fn record_payout_with_fee(&self, reward_id: &Uuid, signature: &str, fee: u64) -> Result<()> {
+        let record = LogRecord::Payout {
+            reward_id: *reward_id,
+            signature: signature.to_string(),
+        };
+        let ledger = self.ledger().await?;
+        let sequence = self.append_to_log(Utc::now(), record).await?;
+        
+        // Ledger first, as for issuance: a payout it refuses is never saved
+        if let Some(ledger) = ledger {
+            let paid = ledger.pay(reward_id, signature, fee).map(|_| ());
+            self.abort_on_error(sequence, paid).await?;
+        }
+        
+        let saved = self.storage.set_transaction_signature(reward_id, signature).await;
+        if let (Err(err), Some(ledger)) = (&saved, ledger) {
+            let _ = ledger.reverse_payout(reward_id, &format!("Not saved: {}", err));
+        }
+        self.abort_on_error(sequence, saved).await
+    }
+    
+    /// Get the total amount of pending rewards across all players
+    pub async fn get_total_pending_amount(&self) -> Result<u64> {
+        self.storage.get_total_pending_amount().await
+    }
+    
+    /// Get the total amount the vault still owes: pending, claimed and queued rewards not yet paid
+    pub async fn get_total_unpaid_amount(&self) -> Result<u64> {
+        self.storage.get_total_unpaid_amount().await
+    }
+    
+    /// Write a JSON Lines snapshot of the engine's storage
+    pub async fn export_snapshot<W: std::io::Write + Send>(&self, writer: W) -> Result<SnapshotSummary> {
+        snapshot::export_snapshot(self.storage.as_ref(), writer).await
+    }
+    
+    /// Get the circuit breaker guarding payouts and issuance
+    pub fn circuit_breaker(&self) -> Arc<CircuitBreaker> {
+        Arc::clone(&self.circuit_breaker)
+    }
+    
     /// Claim all pending rewards for a player
     pub async fn claim_rewards(&self, player_id: &str) -> Result<()> {
-        self.storage.mark_all_rewards_claimed(player_id).await
+        if let Some(trip) = self.circuit_breaker.trip_info() {
+            return Err(PokemonEngineError::PayoutsPaused(trip.reason));
+        }
+        
+        let _player = self.player_locks.lock(player_id).await;
+        let ledger = self.ledger().await?;
+        let claimed = match ledger {
+            Some(_) => self.storage.get_pending_rewards(player_id).await?,
+            None => Vec::new(),
+        };
+        
+        let record = LogRecord::Claim {
+            player_id: player_id.to_string(),
+        };
+        let sequence = self.append_to_log(Utc::now(), record).await?;
+        if let Some(ledger) = ledger {
+            let posted = claim_in_ledger(ledger, &claimed);
+            self.abort_on_error(sequence, posted).await?;
+        }
+        
+        let saved = self.storage.mark_all_rewards_claimed(player_id).await;
+        if let (Err(err), Some(ledger)) = (&saved, ledger) {
+            reverse_claims_in_ledger(ledger, &claimed, &format!("Not saved: {}", err));
+        }
+        self.abort_on_error(sequence, saved).await
     }
     
-    /// Update daily stats helper
-    async fn update_daily_stats(&self, player_id: &str, game_type: GameType, amount: u64) -> Result<()> {
-        let today = Utc::now().date_naive();
+    /// Claim all pending rewards for a player and queue their payout
+    ///
+    /// The rewards are marked claimed and the payout is written to the
+    /// storage's outbox in one step; a `PayoutWorker` sends it later. Returns
+    /// `None` if the player has nothing pending.
+    pub async fn request_payout(&self, player_id: &str, wallet: &str) -> Result<Option<PayoutRequest>> {
+        if let Some(trip) = self.circuit_breaker.trip_info() {
+            return Err(PokemonEngineError::PayoutsPaused(trip.reason));
+        }
+        solana_sdk::pubkey::Pubkey::from_str(wallet)
+            .map_err(|e| PokemonEngineError::InvalidWallet(format!("{}: {}", wallet, e)))?;
+        
+        let _player = self.player_locks.lock(player_id).await;
+        let pending = self.storage.get_pending_rewards(player_id).await?;
+        if pending.is_empty() {
+            return Ok(None);
+        }
+        
+        let ledger = self.ledger().await?;
+        let now = Utc::now();
+        let payout = PayoutRequest::new(player_id, wallet, &pending, now)?;
+        let record = LogRecord::PayoutRequested { payout: payout.clone() };
+        let sequence = self.append_to_log(now, record).await?;
+        if let Some(ledger) = ledger {
+            let posted = claim_in_ledger(ledger, &pending);
+            self.abort_on_error(sequence, posted).await?;
+        }
         
+        let saved = self.storage.enqueue_payout(&payout).await;
+        if let (Err(err), Some(ledger)) = (&saved, ledger) {
+            reverse_claims_in_ledger(ledger, &pending, &format!("Not saved: {}", err));
+        }
+        self.abort_on_error(sequence, saved).await?;
+        Ok(Some(payout))
+    }
+    
+    /// Save a payout's delivery state to the outbox, logging it first
+    pub(crate) async fn update_payout(&self, payout: &PayoutRequest) -> Result<()> {
+        let record = LogRecord::PayoutUpdated { payout: payout.clone() };
+        let sequence = self.append_to_log(Utc::now(), record).await?;
+        let saved = self.storage.update_payout(payout).await;
+        self.abort_on_error(sequence, saved).await
+    }
+    
+    /// Get a queued payout and its delivery status
+    pub async fn get_payout(&self, payout_id: &Uuid) -> Result<Option<PayoutRequest>> {
+        self.storage.get_payout(payout_id).await
+    }
+    
+    /// Storage the engine reads and writes
+    pub(crate) fn storage(&self) -> &dyn Storage {
+        self.storage.as_ref()
+    }
+    
+    /// Rejection if `amount` would take the player past `rewards.daily_total_limit`
+    async fn daily_total_decision(
+        &self,
+        active: &ActiveConfig,
+        player_id: &str,
+        amount: Amount,
+        today: NaiveDate,
+    ) -> Result<Option<Decision>> {
+        let Some(limit) = active.config.rewards.daily_total_limit else {
+            return Ok(None);
+        };
+        let stats = self.storage.get_daily_stats(player_id, today).await?;
+        let earned = stats.map(|s| s.total).unwrap_or_default();
+        if earned.checked_add(amount).is_none_or(|total| total > limit) {
+            return Ok(Some(Decision::rejected(ReasonCode::DailyTotalLimitReached, "Daily total limit reached")));
+        }
+        Ok(None)
+    }
+    
+    /// Rejection while the circuit breaker pauses issuance under `active`
+    fn issuance_paused_decision(&self, active: &ActiveConfig) -> Option<Decision> {
+        if !self.circuit_breaker.issuance_paused(active.config.vault_monitor.pause_issuance) {
+            return None;
+        }
+        
+        Some(Decision::rejected(ReasonCode::IssuancePaused, "Reward issuance is temporarily paused"))
+    }
+    
+    /// Append a record to the event log, if one is attached
+    async fn append_to_log(&self, recorded_at: DateTime<Utc>, record: LogRecord) -> Result<Option<u64>> {
+        match &self.event_log {
+            Some(log) => Ok(Some(log.append(recorded_at, record).await?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Mark a logged change as aborted if applying it to storage failed
+    async fn abort_on_error(&self, sequence: Option<u64>, result: Result<()>) -> Result<()> {
+        if let (Err(_), Some(log), Some(sequence)) = (&result, &self.event_log, sequence) {
+            // Best effort: the storage error is the one worth reporting
+            let _ = log.append(Utc::now(), LogRecord::Aborted { sequence }).await;
+        }
+        result
+    }
+    
+    /// Compute today's daily stats after adding a reward, without saving them
+    async fn next_daily_stats(
+        &self,
+        player_id: &str,
+        game_type: GameType,
+        amount: Amount,
+        today: NaiveDate,
+    ) -> Result<DailyStats> {
         let mut stats = match self.storage.get_daily_stats(player_id, today).await? {
             Some(s) => s,
             None => DailyStats {
                 player_id: player_id.to_string(),
                 date: today,
-                flypoke: 0,
-                battle: 0,
-                login: 0,
-                total: 0,
+                flypoke: Amount::ZERO,
+                battle: Amount::ZERO,
+                login: Amount::ZERO,
+                total: Amount::ZERO,
             },
         };
         
+        let overflow = || {
+            PokemonEngineError::AmountOverflow(format!("Daily stats of {} for {}", player_id, today))
+        };
         match game_type {
-            GameType::FlyPoke => stats.flypoke += amount,
-            GameType::Battle => stats.battle += amount,
-            GameType::Login => stats.login += amount,
+            GameType::FlyPoke => stats.flypoke = stats.flypoke.checked_add(amount).ok_or_else(overflow)?,
+            GameType::Battle => stats.battle = stats.battle.checked_add(amount).ok_or_else(overflow)?,
+            GameType::Login => stats.login = stats.login.checked_add(amount).ok_or_else(overflow)?,
             _ => {}
         }
         
-        stats.total = stats.flypoke + stats.battle + stats.login;
+        stats.total = Amount::checked_sum([stats.flypoke, stats.battle, stats.login]).ok_or_else(overflow)?;
         
-        self.storage.update_daily_stats(&stats).await
+        Ok(stats)
     }
 }
 
+/// Post the claim of every reward, reversing those posted if one is refused
+fn claim_in_ledger(ledger: &Ledger, rewards: &[Reward]) -> Result<()> {
+    for (index, reward) in rewards.iter().enumerate() {
+        if let Err(err) = ledger.claim(&reward.id) {
+            reverse_claims_in_ledger(ledger, &rewards[..index], &format!("Claim refused: {}", err));
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Reverse the claims posted for `rewards`, best effort
+fn reverse_claims_in_ledger(ledger: &Ledger, rewards: &[Reward], reason: &str) {
+    for reward in rewards {
+        let _ = ledger.reverse_claim(&reward.id, reason);
+    }
+}
+
+/// Decode a game's event data, naming the offending field where serde does
+fn decode_event_data<T: serde::de::DeserializeOwned>(data: &serde_json::Value) -> Result<T> {
+    serde_json::from_value(data.clone()).map_err(|e| {
+        let message = e.to_string();
+        let field = ["missing field `", "unknown field `"]
+            .iter()
+            .find_map(|prefix| message.strip_prefix(prefix))
+            .and_then(|rest| rest.split_once('`'))
+            .map(|(field, _)| field.to_string());
+        PokemonEngineError::InvalidEventData { field, message }
+    })
+}
+
+/// Limit, earnings and remaining allowance of one game given a day's stats
+fn game_allowance(active: &ActiveConfig, game: GameType, stats: Option<&DailyStats>) -> GameAllowance {
+    let limit = active.calculator.get_daily_limit(game);
+    let earned = stats.and_then(|s| s.earned(game)).unwrap_or_default();
+    let remaining = if DailyStats::TRACKED_GAMES.contains(&game) {
+        let game_remaining = limit.map(|limit| limit.checked_sub(earned).unwrap_or(Amount::ZERO));
+        match (game_remaining, global_remaining(active, stats)) {
+            (Some(game), Some(global)) => Some(game.min(global)),
+            (game, global) => game.or(global),
+        }
+    } else {
+        // Earnings aren't counted, so nothing can be said about what's left
+        None
+    };
+    GameAllowance {
+        game,
+        limit,
+        earned,
+        remaining,
+    }
+}
+
+/// What's left under `rewards.daily_total_limit`, if one is set
+fn global_remaining(active: &ActiveConfig, stats: Option<&DailyStats>) -> Option<Amount> {
+    let earned = stats.map(|s| s.total).unwrap_or_default();
+    active
+        .config
+        .rewards
+        .daily_total_limit
+        .map(|limit| limit.checked_sub(earned).unwrap_or(Amount::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(flypoke: u64, battle: u64, login: u64) -> DailyStats {
+        DailyStats {
+            player_id: "player1".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 10, 18).unwrap(),
+            flypoke: Amount::from_lamports(flypoke),
+            battle: Amount::from_lamports(battle),
+            login: Amount::from_lamports(login),
+            total: Amount::from_lamports(flypoke + battle + login),
+        }
+    }
+
+    #[test]
+    fn allowance_is_capped_by_the_global_limit() {
+        let mut config = Config::default();
+        config.rewards.battle.daily_limit = Amount::from_lamports(300);
+        config.rewards.daily_total_limit = Some(Amount::from_lamports(400));
+        let active = ActiveConfig::new(config, Utc::now());
+        let stats = stats(150, 100, 50);
+
+        let battle = game_allowance(&active, GameType::Battle, Some(&stats));
+        assert_eq!(battle.earned, Amount::from_lamports(100));
+        assert_eq!(battle.remaining, Some(Amount::from_lamports(100)));
+        let login = game_allowance(&active, GameType::Login, Some(&stats));
+        assert_eq!(login.remaining, Some(Amount::from_lamports(100)));
+        assert_eq!(global_remaining(&active, Some(&stats)), Some(Amount::from_lamports(100)));
+    }
+
+    #[test]
+    fn allowance_of_untracked_games_is_unknown() {
+        let active = ActiveConfig::new(Config::default(), Utc::now());
+        let stats = stats(10, 20, 30);
+        for game in [GameType::PokeMatch, GameType::Pokedex] {
+            let allowance = game_allowance(&active, game, Some(&stats));
+            assert_eq!(allowance.earned, Amount::ZERO, "{}", game);
+            assert_eq!(allowance.remaining, None, "{}", game);
+        }
+        let pokematch = game_allowance(&active, GameType::PokeMatch, Some(&stats));
+        assert_eq!(pokematch.limit, Some(active.config.rewards.pokematch.daily_limit));
+    }
+
+    #[tokio::test]
+    async fn events_past_the_global_limit_are_rejected() {
+        let mut config = Config::default();
+        config.rewards.daily_total_limit = Some(Amount::from_lamports(100_000_000_000)); // 100 POKE
+        let engine = RewardEngine::new(config, Box::new(crate::storage::MemoryStorage::new())).unwrap();
+        let data = BattleEventData { level: 1, streak: 0, perfect_victory: None };
+
+        let first = engine.process_battle_event("player1", &data).await.unwrap();
+        assert!(first.success);
+        let second = engine.process_battle_event("player1", &data).await.unwrap();
+        assert!(!second.success);
+        assert!(second.daily_limit_reached);
+        assert_eq!(second.reason, Some(ReasonCode::DailyTotalLimitReached));
+        assert_eq!(second.message, "Daily limit of 100 POKE across all games reached");
+        assert_eq!(second.remaining_allowance, Some(Amount::from_lamports(30_000_000_000)));
+    }
+
+    #[tokio::test]
+    async fn default_rewards_are_in_poke_whatever_the_decimals() {
+        let config = Config::default_for_decimals(6).unwrap();
+        let engine = RewardEngine::new(config, Box::new(crate::storage::MemoryStorage::new())).unwrap();
+
+        let base = BattleEventData { level: 0, streak: 0, perfect_victory: None };
+        let response = engine.process_battle_event("player1", &base).await.unwrap();
+        assert_eq!(response.reward.unwrap().amount, Amount::from_lamports(50_000_000)); // 50 POKE
+
+        // The configured base and the calculator's level bonus use the same scale
+        let levelled = BattleEventData { level: 1, ..base };
+        let response = engine.process_battle_event("player2", &levelled).await.unwrap();
+        assert_eq!(response.reward.unwrap().amount, Amount::from_lamports(70_000_000)); // 70 POKE
+    }
+
+    #[tokio::test]
+    async fn ledger_is_rebuilt_for_rewards_saved_before_it_was_attached() {
+        let engine = RewardEngine::new(Config::default(), Box::new(crate::storage::MemoryStorage::new())).unwrap();
+        let data = BattleEventData { level: 1, streak: 0, perfect_victory: None };
+        let first = engine.process_battle_event("player1", &data).await.unwrap();
+        let amount = first.reward.unwrap().amount.lamports() as i128;
+
+        // As after a restart: the new ledger never saw the issuance
+        let ledger = Arc::new(Ledger::new());
+        let engine = engine.with_ledger(Arc::clone(&ledger));
+        engine.process_battle_event("player1", &data).await.unwrap();
+        assert_eq!(ledger.player_balance("player1").pending, 2 * amount);
+
+        engine.claim_rewards("player1").await.unwrap();
+        assert_eq!(ledger.balance(&crate::ledger::Account::Vault), 2 * amount);
+        assert!(ledger.check_invariants().is_sound());
+    }
+
+    #[tokio::test]
+    async fn payout_fees_are_posted_to_the_fees_account() {
+        use crate::ledger::Account;
+
+        let ledger = Arc::new(Ledger::new());
+        let engine = RewardEngine::new(Config::default(), Box::new(crate::storage::MemoryStorage::new()))
+            .unwrap()
+            .with_ledger(Arc::clone(&ledger));
+        let data = BattleEventData { level: 1, streak: 0, perfect_victory: None };
+        let reward = engine.process_battle_event("player1", &data).await.unwrap().reward.unwrap();
+        let amount = reward.amount.lamports() as i128;
+
+        let wallet = solana_sdk::pubkey::Pubkey::new_unique().to_string();
+        engine.request_payout("player1", &wallet).await.unwrap().unwrap();
+        engine.record_payout_with_fee(&reward.id, "sig1", 5000).await.unwrap();
+
+        assert_eq!(ledger.balance(&Account::Fees), 5000);
+        assert_eq!(ledger.balance(&Account::Vault), 0);
+        assert_eq!(ledger.player_balance("player1").paid, amount - 5000);
+        let report = ledger.check_invariants();
+        assert_eq!(report.total, 0);
+        assert!(report.is_sound(), "{:?}", report.violations);
+    }
+
+    #[tokio::test]
+    async fn issuance_pause_follows_the_active_config() {
+        let engine = RewardEngine::new(Config::default(), Box::new(crate::storage::MemoryStorage::new())).unwrap();
+        let data = BattleEventData { level: 1, streak: 0, perfect_victory: None };
+        engine.circuit_breaker().trip(crate::engine::TripCause::Manual, "maintenance");
+
+        let response = engine.process_battle_event("player1", &data).await.unwrap();
+        assert!(response.success);
+
+        let mut config = Config::default();
+        config.vault_monitor.pause_issuance = true;
+        engine.reload_config(config).await.unwrap();
+        let response = engine.process_battle_event("player1", &data).await.unwrap();
+        assert_eq!(response.reason, Some(ReasonCode::IssuancePaused));
+        let allowance = engine.get_daily_allowance("player1", Utc::now().date_naive()).await.unwrap();
+        assert!(allowance.issuance_paused);
+
+        engine.circuit_breaker().reset();
+        let response = engine.process_battle_event("player1", &data).await.unwrap();
+        assert!(response.success);
+    }
+
+    #[tokio::test]
+    async fn reload_swaps_the_config_for_new_events_only() {
+        let engine = RewardEngine::new(Config::default(), Box::new(crate::storage::MemoryStorage::new())).unwrap();
+        let data = BattleEventData { level: 1, streak: 0, perfect_victory: None };
+        let original = engine.config_version();
+        let before = engine.process_battle_event("player1", &data).await.unwrap().reward.unwrap();
+
+        let mut invalid = Config::default();
+        invalid.solana.commitment = "eventually".to_string();
+        assert!(matches!(engine.reload_config(invalid).await, Err(PokemonEngineError::Config(_))));
+        assert_eq!(engine.config_version(), original);
+
+        let mut config = Config::default();
+        config.rewards.battle.base_reward = Amount::from_lamports(60_000_000_000);
+        let version = engine.reload_config(config.clone()).await.unwrap();
+        assert_eq!(version, config.fingerprint());
+        assert_eq!(engine.config_version(), version);
+        assert_eq!(engine.config().rewards.battle.base_reward, config.rewards.battle.base_reward);
+
+        let after = engine.process_battle_event("player1", &data).await.unwrap().reward.unwrap();
+        assert_eq!(after.config_version.as_deref(), Some(version.as_str()));
+        assert_eq!(after.amount.lamports(), before.amount.lamports() + 10_000_000_000);
+        let stored = engine.storage().get_rewards("player1").await.unwrap();
+        let stored = stored.iter().find(|r| r.id == before.id).unwrap();
+        assert_eq!(stored.config_version.as_deref(), Some(original.as_str()));
+    }
+
+    #[tokio::test]
+    async fn superseded_versions_resolve_by_fingerprint() {
+        let engine = RewardEngine::new(Config::default(), Box::new(crate::storage::MemoryStorage::new())).unwrap();
+        let data = BattleEventData { level: 1, streak: 0, perfect_victory: None };
+        let reward = engine.process_battle_event("player1", &data).await.unwrap().reward.unwrap();
+
+        let mut config = Config::default();
+        config.rewards.battle.base_reward = Amount::from_lamports(60_000_000_000);
+        engine.reload_config(config).await.unwrap();
+        assert_ne!(engine.config_version(), reward.config_version.clone().unwrap());
+
+        let resolved = engine.config_by_version(reward.config_version.as_deref().unwrap()).unwrap();
+        assert_eq!(resolved.config.rewards.battle.base_reward, Config::default().rewards.battle.base_reward);
+        assert!(resolved.effective_from < Utc::now());
+        assert!(engine.config_by_version("0000000000000000").is_none());
+    }
+
+    #[tokio::test]
+    async fn events_are_decided_under_the_version_in_effect_at_their_time() {
+        let engine = RewardEngine::new(Config::default(), Box::new(crate::storage::MemoryStorage::new())).unwrap();
+        let original = engine.config_version();
+        let mut config = Config::default();
+        config.rewards.battle.base_reward = Amount::from_lamports(60_000_000_000);
+        let effective_from = Utc::now() + chrono::Duration::hours(1);
+        let scheduled = engine.schedule_config(effective_from, config).await.unwrap();
+
+        let just_before = effective_from - chrono::Duration::nanoseconds(1);
+        assert_eq!(engine.config_at(just_before).version, original);
+        assert_eq!(engine.config_at(effective_from).version, scheduled);
+        assert_eq!(engine.config_version(), original);
+
+        let event = GameEvent {
+            player_id: "player1".to_string(),
+            game: GameType::Battle,
+            event_data: serde_json::json!({ "level": 1, "streak": 0 }),
+            locale: None,
+        };
+        for (at, version) in [(just_before, &original), (effective_from, &scheduled)] {
+            let context = DecisionContext { now: at, reward_id: Uuid::new_v4() };
+            let decision = engine.decide(&event, &context).await.unwrap();
+            assert_eq!(decision.reward().unwrap().config_version.as_ref(), Some(version));
+        }
+    }
+
+    #[test]
+    fn only_the_configured_history_of_superseded_versions_is_kept() {
+        let start = Utc::now();
+        let version = |minutes: i64| Arc::new(ActiveConfig::new(Config::default(), start + chrono::Duration::minutes(minutes)));
+        let mut versions = ConfigVersions(vec![version(0)]);
+        for minutes in 1..=(CONFIG_HISTORY as i64 + 2) {
+            versions.insert(version(minutes));
+        }
+        // One version scheduled after `now`
+        let now = start + chrono::Duration::minutes(CONFIG_HISTORY as i64 + 1);
+        versions.prune(now);
+
+        assert_eq!(versions.0.len(), CONFIG_HISTORY + 2);
+        assert_eq!(versions.at(now).effective_from, now);
+        assert_eq!(versions.0[0].effective_from, start + chrono::Duration::minutes(1));
+    }
+
+    #[tokio::test]
+    async fn typed_entry_points_localize_with_locale() {
+        let engine = RewardEngine::new(Config::default(), Box::new(crate::storage::MemoryStorage::new())).unwrap();
+
+        let response = engine.process_login_event("player1").await.unwrap();
+        assert_eq!(response.message, "Login reward processed successfully: 20 POKE for a 1-day streak");
+        let response = engine.process_welcome_event_with_locale("player1", Some("id")).await.unwrap();
+        assert_eq!(response.message, "Bonus selamat datang berhasil diproses: 100 POKE");
+    }
+
+    #[tokio::test]
+    async fn allowance_uses_the_config_in_effect_on_its_date() {
+        let mut config = Config::default();
+        config.rewards.daily_total_limit = Some(Amount::from_lamports(100_000_000_000)); // 100 POKE
+        let engine = RewardEngine::new(config, Box::new(crate::storage::MemoryStorage::new())).unwrap();
+        let data = BattleEventData { level: 1, streak: 0, perfect_victory: None };
+        engine.process_battle_event("player1", &data).await.unwrap();
+
+        let today = Utc::now().date_naive();
+        let tomorrow = today.succ_opt().unwrap();
+        let mut scheduled = Config::default();
+        scheduled.rewards.battle.daily_limit = Amount::from_lamports(400_000_000_000);
+        let midnight = tomorrow.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        engine.schedule_config(midnight, scheduled).await.unwrap();
+
+        let allowance = engine.get_daily_allowance("player1", today).await.unwrap();
+        let battle = allowance.games.iter().find(|g| g.game == GameType::Battle).unwrap();
+        assert_eq!(battle.limit, Some(Amount::from_lamports(300_000_000_000)));
+        assert_eq!(battle.earned, Amount::from_lamports(70_000_000_000));
+        assert_eq!(battle.remaining, Some(Amount::from_lamports(30_000_000_000)));
+        assert_eq!(allowance.total_earned, Amount::from_lamports(70_000_000_000));
+        assert_eq!(allowance.global_limit, Some(Amount::from_lamports(100_000_000_000)));
+        assert_eq!(allowance.global_remaining, Some(Amount::from_lamports(30_000_000_000)));
+        assert_eq!(allowance.resets_at, midnight);
+
+        let allowance = engine.get_daily_allowance("player1", tomorrow).await.unwrap();
+        let battle = allowance.games.iter().find(|g| g.game == GameType::Battle).unwrap();
+        assert_eq!(battle.limit, Some(Amount::from_lamports(400_000_000_000)));
+        assert_eq!(battle.remaining, battle.limit);
+        assert_eq!(allowance.global_limit, None);
+        assert_eq!(allowance.global_remaining, None);
+
+        // Past days are reported under the config that ended them
+        let yesterday = today.pred_opt().unwrap();
+        let allowance = engine.get_daily_allowance("player1", yesterday).await.unwrap();
+        assert_eq!(allowance.global_remaining, Some(Amount::from_lamports(100_000_000_000)));
+        assert_eq!(allowance.resets_in_secs, 0);
+    }
+
+    #[tokio::test]
+    async fn preview_matches_processing_and_changes_nothing() {
+        let log = Arc::new(crate::event_log::MemoryEventLog::new());
+        let ledger = Arc::new(Ledger::new());
+        let engine = RewardEngine::new(Config::default(), Box::new(crate::storage::MemoryStorage::new()))
+            .unwrap()
+            .with_event_log(Arc::clone(&log) as Arc<dyn EventLog>)
+            .with_ledger(Arc::clone(&ledger));
+        let data = BattleEventData { level: 2, streak: 3, perfect_victory: None };
+        engine.process_battle_event("player1", &data).await.unwrap();
+
+        let state = || async {
+            let rewards = engine.storage().get_rewards("player1").await.unwrap();
+            let stats = engine.get_daily_stats("player1", Utc::now().date_naive()).await.unwrap();
+            (serde_json::to_value(rewards).unwrap(), serde_json::to_value(stats).unwrap())
+        };
+        let ledger_state = || (ledger.balances(), ledger.transactions(1, usize::MAX).len());
+        let before = (state().await, ledger_state(), log.last_sequence().await.unwrap());
+
+        let event = GameEvent {
+            player_id: "player1".to_string(),
+            game: GameType::Battle,
+            event_data: serde_json::to_value(&data).unwrap(),
+            locale: None,
+        };
+        let preview = engine.preview_game_event(&event).await.unwrap();
+        assert_eq!(before, (state().await, ledger_state(), log.last_sequence().await.unwrap()));
+
+        let processed = engine.process_game_event(&event).await.unwrap();
+        assert!(preview.success && processed.success);
+        assert_eq!(preview.reward.unwrap().amount, processed.reward.unwrap().amount);
+        assert_eq!(preview.remaining_allowance, processed.remaining_allowance);
+        assert_ne!(before.0, state().await);
+    }
+}
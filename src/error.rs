@@ -1,9 +1,15 @@
+use crate::config::ConfigIssue;
+use crate::models::ErrorResponse;
+use crate::reason::ReasonCode;
 use thiserror::Error;
 
 /// Result type alias for Pokemon Engine operations
 pub type Result<T> = std::result::Result<T, PokemonEngineError>;
 
 /// Error types for Pokemon Engine
+///
+/// `Storage` and `Solana` are for failures to reach the backend or the RPC,
+/// which may clear on retry; permanent outcomes have their own variants.
 #[derive(Error, Debug)]
 pub enum PokemonEngineError {
     #[error("Storage error: {0}")]
@@ -24,13 +30,119 @@ pub enum PokemonEngineError {
     #[error("Invalid player ID: {0}")]
     InvalidPlayerId(String),
     
-    #[error("Configuration error: {0}")]
-    Config(String),
+    #[error("Invalid wallet: {0}")]
+    InvalidWallet(String),
+    
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    
+    #[error("Payouts paused: {0}")]
+    PayoutsPaused(String),
+    
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+    
+    #[error("Invalid event data: {message}")]
+    InvalidEventData { field: Option<String>, message: String },
+    
+    #[error("Event out of range: {message}")]
+    EventOutOfRange { field: Option<String>, message: String },
+    
+    #[error("Amount overflow: {0}")]
+    AmountOverflow(String),
+    
+    #[error("Configuration error: {}", format_issues(.0))]
+    Config(Vec<ConfigIssue>),
+    
+    #[error("Not found: {0}")]
+    NotFound(String),
+    
+    #[error("Already exists: {0}")]
+    AlreadyExists(String),
+    
+    #[error("Transaction failed: {0}")]
+    TransactionFailed(String),
+    
+    #[error("Corrupt data: {0}")]
+    Corrupt(String),
+    
+    #[error("Ledger error: {0}")]
+    Ledger(String),
+    
+    #[error("Invalid snapshot: {0}")]
+    InvalidSnapshot(String),
     
     #[error("Serialization error: {0}")]
     Serialization(String),
 }
 
+impl PokemonEngineError {
+    /// Configuration error with a single issue at `path`
+    pub fn config(path: impl Into<String>, message: impl Into<String>) -> Self {
+        PokemonEngineError::Config(vec![ConfigIssue::new(path, message)])
+    }
+    
+    /// Stable code for this error
+    pub fn reason_code(&self) -> ReasonCode {
+        match self {
+            PokemonEngineError::Storage(_) => ReasonCode::StorageUnavailable,
+            PokemonEngineError::Solana(_) => ReasonCode::SolanaUnavailable,
+            PokemonEngineError::InvalidGameType(_) => ReasonCode::InvalidGameType,
+            PokemonEngineError::DailyLimitReached(_) => ReasonCode::DailyLimitReached,
+            PokemonEngineError::RewardAlreadyClaimed(_) => ReasonCode::Duplicate,
+            PokemonEngineError::InvalidPlayerId(_) => ReasonCode::InvalidPlayerId,
+            PokemonEngineError::InvalidWallet(_) => ReasonCode::InvalidWallet,
+            PokemonEngineError::InvalidQuery(_) => ReasonCode::InvalidQuery,
+            PokemonEngineError::PayoutsPaused(_) => ReasonCode::PayoutsPaused,
+            PokemonEngineError::InvalidAmount(_) => ReasonCode::InvalidAmount,
+            PokemonEngineError::InvalidEventData { .. } => ReasonCode::InvalidPayload,
+            PokemonEngineError::EventOutOfRange { .. } => ReasonCode::InvalidField,
+            PokemonEngineError::AmountOverflow(_) => ReasonCode::AmountOverflow,
+            PokemonEngineError::Config(_) => ReasonCode::InvalidConfig,
+            PokemonEngineError::NotFound(_) => ReasonCode::NotFound,
+            PokemonEngineError::AlreadyExists(_) => ReasonCode::Duplicate,
+            PokemonEngineError::TransactionFailed(_) => ReasonCode::TransactionFailed,
+            PokemonEngineError::Corrupt(_) => ReasonCode::Internal,
+            PokemonEngineError::Ledger(_) => ReasonCode::LedgerError,
+            PokemonEngineError::InvalidSnapshot(_) => ReasonCode::InvalidSnapshot,
+            PokemonEngineError::Serialization(_) => ReasonCode::Internal,
+        }
+    }
+    
+    /// Whether the failed call may succeed if retried unchanged
+    pub fn is_retryable(&self) -> bool {
+        self.reason_code().is_retryable()
+    }
+    
+    /// Event data field the error is about, when known
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            PokemonEngineError::InvalidEventData { field, .. } | PokemonEngineError::EventOutOfRange { field, .. } => {
+                field.as_deref()
+            }
+            _ => None,
+        }
+    }
+    
+    /// Serializable form for returning to clients
+    pub fn to_response(&self) -> ErrorResponse {
+        ErrorResponse {
+            reason: self.reason_code(),
+            retryable: self.is_retryable(),
+            field: self.field().map(str::to_string),
+            message: self.to_string(),
+        }
+    }
+}
+
+fn format_issues(issues: &[ConfigIssue]) -> String {
+    issues
+        .iter()
+        .map(|issue| issue.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 impl From<serde_json::Error> for PokemonEngineError {
     fn from(err: serde_json::Error) -> Self {
         PokemonEngineError::Serialization(err.to_string())
@@ -43,3 +155,15 @@ impl From<std::io::Error> for PokemonEngineError {
     }
 }
 
+impl From<sled::Error> for PokemonEngineError {
+    fn from(err: sled::Error) -> Self {
+        PokemonEngineError::Storage(err.to_string())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for PokemonEngineError {
+    fn from(err: rusqlite::Error) -> Self {
+        PokemonEngineError::Storage(err.to_string())
+    }
+}
@@ -0,0 +1,613 @@
+//! Append-only event log and deterministic replay
+//!
+//! The engine appends every game event together with its decision (or the
+//! error it failed with), every config change, every claim and every payout
+//! signature. Replaying the log into an empty
+//! `Storage` rebuilds rewards, daily stats and login streaks, and re-runs
+//! each event through the engine to prove the logged decision still follows
+//! from the rules and the state before it.
+
+use crate::config::Config;
+use crate::engine::{Decision, DecisionContext, RewardEngine};
+use crate::error::{PokemonEngineError, Result};
+use crate::models::{GameEvent, PayoutRequest};
+use crate::reason::ReasonCode;
+use crate::storage::Storage;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Entries read from the log per batch during replay
+const REPLAY_BATCH: usize = 1_000;
+
+/// State change recorded in the event log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LogRecord {
+    /// Game event and the engine's decision, accepted or rejected
+    GameEvent { event: GameEvent, decision: Box<Decision> },
+    /// Game event that failed before a decision was reached
+    FailedEvent {
+        event: GameEvent,
+        reason: ReasonCode,
+        error: String,
+    },
+    /// All pending rewards of a player were claimed
+    Claim { player_id: String },
+    /// Pending rewards were claimed into a payout in the outbox
+    PayoutRequested { payout: PayoutRequest },
+    /// Payout in the outbox was updated by the payout worker
+    PayoutUpdated { payout: PayoutRequest },
+    /// Payout signature recorded for a reward
+    Payout { reward_id: Uuid, signature: String },
+    /// Config version reloaded or scheduled to take effect at `effective_from`
+    ConfigChanged {
+        fingerprint: String,
+        effective_from: DateTime<Utc>,
+        config: Box<Config>,
+    },
+    /// Scheduled config version cancelled before it took effect
+    ConfigCancelled { effective_from: DateTime<Utc> },
+    /// Storage rejected the change logged under `sequence`
+    Aborted { sequence: u64 },
+}
+
+/// One entry of the event log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub sequence: u64, // Starts at 1, no gaps
+    pub recorded_at: DateTime<Utc>,
+    pub record: LogRecord,
+}
+
+/// Append-only event log
+#[async_trait]
+pub trait EventLog: Send + Sync {
+    /// Append a record, returning its sequence number
+    async fn append(&self, recorded_at: DateTime<Utc>, record: LogRecord) -> Result<u64>;
+
+    /// Read up to `limit` entries starting at sequence `from`
+    async fn read(&self, from: u64, limit: usize) -> Result<Vec<LogEntry>>;
+
+    /// Sequence number of the last entry, 0 if the log is empty
+    async fn last_sequence(&self) -> Result<u64>;
+}
+
+/// In-memory event log (for testing or simple use cases)
+#[derive(Default)]
+pub struct MemoryEventLog {
+    entries: Mutex<Vec<LogEntry>>,
+}
+
+impl MemoryEventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl EventLog for MemoryEventLog {
+    async fn append(&self, recorded_at: DateTime<Utc>, record: LogRecord) -> Result<u64> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let sequence = entries.len() as u64 + 1;
+        entries.push(LogEntry {
+            sequence,
+            recorded_at,
+            record,
+        });
+        Ok(sequence)
+    }
+
+    async fn read(&self, from: u64, limit: usize) -> Result<Vec<LogEntry>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let start = (from.max(1) - 1) as usize;
+        Ok(entries.iter().skip(start).take(limit).cloned().collect())
+    }
+
+    async fn last_sequence(&self) -> Result<u64> {
+        Ok(self.entries.lock().unwrap_or_else(|e| e.into_inner()).len() as u64)
+    }
+}
+
+struct FileLogState {
+    file: File,
+    offsets: Vec<u64>, // Byte offset of each entry, indexed by sequence - 1
+    len: u64,
+}
+
+/// Event log stored as one JSON entry per line
+///
+/// Each append is synced to disk before it returns. A partially written last
+/// line (from a crash mid-append) is truncated when the log is opened.
+pub struct FileEventLog {
+    path: PathBuf,
+    state: Mutex<FileLogState>,
+}
+
+impl FileEventLog {
+    /// Open (or create) a log file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+
+        let mut offsets = Vec::new();
+        let mut len = 0u64;
+        let mut reader = BufReader::new(&mut file);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)? as u64;
+            if read == 0 {
+                break;
+            }
+            // A line without its newline is a torn write at the end of the file
+            if !line.ends_with('\n') {
+                break;
+            }
+            let entry: LogEntry = serde_json::from_str(&line).map_err(|err| {
+                PokemonEngineError::Corrupt(format!("Event log {} at byte {}: {}", path.display(), len, err))
+            })?;
+            if entry.sequence != offsets.len() as u64 + 1 {
+                return Err(PokemonEngineError::Corrupt(format!(
+                    "Event log {} out of order at byte {}: expected sequence {}, found {}",
+                    path.display(),
+                    len,
+                    offsets.len() + 1,
+                    entry.sequence
+                )));
+            }
+            offsets.push(len);
+            len += read;
+        }
+        drop(reader);
+        file.set_len(len)?;
+
+        Ok(Self {
+            path,
+            state: Mutex::new(FileLogState { file, offsets, len }),
+        })
+    }
+
+    /// Path of the log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, FileLogState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl EventLog for FileEventLog {
+    async fn append(&self, recorded_at: DateTime<Utc>, record: LogRecord) -> Result<u64> {
+        let mut state = self.state();
+        let entry = LogEntry {
+            sequence: state.offsets.len() as u64 + 1,
+            recorded_at,
+            record,
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+
+        if let Err(err) = state.file.write_all(&line).and_then(|_| state.file.sync_data()) {
+            // Drop whatever part of the line made it to disk
+            let len = state.len;
+            let _ = state.file.set_len(len);
+            return Err(err.into());
+        }
+
+        let offset = state.len;
+        state.offsets.push(offset);
+        state.len += line.len() as u64;
+        Ok(entry.sequence)
+    }
+
+    async fn read(&self, from: u64, limit: usize) -> Result<Vec<LogEntry>> {
+        let (offset, available) = {
+            let state = self.state();
+            let start = (from.max(1) - 1) as usize;
+            match state.offsets.get(start) {
+                Some(&offset) => (offset, (state.offsets.len() - start).min(limit)),
+                None => return Ok(Vec::new()),
+            }
+        };
+
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut entries = Vec::with_capacity(available);
+        let mut line = String::new();
+        while entries.len() < available {
+            line.clear();
+            reader.read_line(&mut line)?;
+            entries.push(serde_json::from_str(&line)?);
+        }
+        Ok(entries)
+    }
+
+    async fn last_sequence(&self) -> Result<u64> {
+        Ok(self.state().offsets.len() as u64)
+    }
+}
+
+/// Logged decision that re-running the event no longer produces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayMismatch {
+    pub sequence: u64,
+    pub event: GameEvent,
+    pub logged: Decision,
+    pub replayed: Decision,
+}
+
+/// Summary of a replay run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub entries_read: u64,
+    pub rewards_restored: u64,
+    pub rejections_verified: u64,
+    pub claims_applied: u64,
+    #[serde(default)]
+    pub payouts_requested: u64,
+    #[serde(default)]
+    pub payout_updates_applied: u64,
+    pub payouts_applied: u64,
+    pub aborted_skipped: u64,
+    #[serde(default)]
+    pub failed_events: u64, // Logged as failed; nothing to restore
+    #[serde(default)]
+    pub config_changes: u64,
+    pub mismatches: Vec<ReplayMismatch>,
+}
+
+impl ReplayReport {
+    /// Check if every logged decision was reproduced
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Rebuild engine state from an event log into a fresh storage
+///
+/// Entries are applied in sequence order: logged rewards, stats and streaks
+/// are written as recorded, and claims and payouts are re-applied. Each game
+/// event is also decided again against the state rebuilt so far, using the
+/// logged time and reward id; any difference is reported as a mismatch.
+/// Payouts are restored to the outbox in their last logged state.
+/// Rejections caused by the circuit breaker and events that failed are not
+/// re-checked.
+///
+/// `config` is the config the log started under. Logged reloads and
+/// schedule changes are applied as they come, so each event is re-decided
+/// under the version that was in effect at its time.
+///
+/// Returns an engine over the rebuilt storage. It has no event log attached;
+/// use `with_event_log` to keep appending to the same log.
+pub async fn replay(
+    log: &dyn EventLog,
+    config: Config,
+    storage: Box<dyn Storage>,
+) -> Result<(RewardEngine, ReplayReport)> {
+    let engine = RewardEngine::new_from(config, storage, DateTime::<Utc>::MIN_UTC)?;
+    let mut report = ReplayReport::default();
+    let aborted = aborted_sequences(log).await?;
+
+    let mut next = 1;
+    loop {
+        let entries = log.read(next, REPLAY_BATCH).await?;
+        let Some(last) = entries.last() else { break };
+        next = last.sequence + 1;
+
+        for entry in entries {
+            report.entries_read += 1;
+            if aborted.contains(&entry.sequence) {
+                report.aborted_skipped += 1;
+                continue;
+            }
+            replay_entry(&engine, entry, &mut report).await?;
+        }
+    }
+
+    Ok((engine, report))
+}
+
+async fn replay_entry(engine: &RewardEngine, entry: LogEntry, report: &mut ReplayReport) -> Result<()> {
+    match entry.record {
+        LogRecord::GameEvent { event, decision } => {
+            let paused = matches!(*decision, Decision::Rejected { issuance_paused: true, .. });
+            if !paused {
+                let context = DecisionContext {
+                    now: entry.recorded_at,
+                    reward_id: decision.reward().map(|r| r.id).unwrap_or_default(),
+                };
+                let replayed = engine.decide(&event, &context).await?;
+                if !same_decision(&decision, &replayed)? {
+                    report.mismatches.push(ReplayMismatch {
+                        sequence: entry.sequence,
+                        event,
+                        logged: (*decision).clone(),
+                        replayed,
+                    });
+                }
+            }
+
+            match decision.as_ref() {
+                Decision::Accepted {
+                    reward,
+                    daily_stats,
+                    login_streak,
+                } => {
+                    engine
+                        .storage()
+                        .record_reward(reward, daily_stats.as_ref(), login_streak.as_ref())
+                        .await?;
+                    report.rewards_restored += 1;
+                }
+                Decision::Rejected { .. } if !paused => report.rejections_verified += 1,
+                Decision::Rejected { .. } => {}
+            }
+        }
+        LogRecord::FailedEvent { .. } => report.failed_events += 1,
+        LogRecord::Claim { player_id } => {
+            engine.storage().mark_all_rewards_claimed(&player_id).await?;
+            report.claims_applied += 1;
+        }
+        LogRecord::PayoutRequested { payout } => {
+            engine.storage().enqueue_payout(&payout).await?;
+            report.payouts_requested += 1;
+        }
+        LogRecord::PayoutUpdated { payout } => {
+            engine.storage().update_payout(&payout).await?;
+            report.payout_updates_applied += 1;
+        }
+        LogRecord::Payout {
+            reward_id,
+            signature,
+        } => {
+            engine.storage().set_transaction_signature(&reward_id, &signature).await?;
+            report.payouts_applied += 1;
+        }
+        LogRecord::ConfigChanged {
+            effective_from, config, ..
+        } => {
+            engine.replay_config_change(effective_from, *config, entry.recorded_at);
+            report.config_changes += 1;
+        }
+        LogRecord::ConfigCancelled { effective_from } => {
+            engine.replay_config_cancel(effective_from, entry.recorded_at);
+            report.config_changes += 1;
+        }
+        LogRecord::Aborted { .. } => {}
+    }
+    Ok(())
+}
+
+/// Sequences whose changes never reached storage
+async fn aborted_sequences(log: &dyn EventLog) -> Result<HashSet<u64>> {
+    let mut aborted = HashSet::new();
+    let mut next = 1;
+    loop {
+        let entries = log.read(next, REPLAY_BATCH).await?;
+        let Some(last) = entries.last() else { break };
+        next = last.sequence + 1;
+        for entry in entries {
+            if let LogRecord::Aborted { sequence } = entry.record {
+                aborted.insert(sequence);
+            }
+        }
+    }
+    Ok(aborted)
+}
+
+/// Compare decisions by their serialized form
+fn same_decision(logged: &Decision, replayed: &Decision) -> Result<bool> {
+    // Fields added since the entry was logged aren't compared
+    let mut replayed = replayed.clone();
+    match (logged, &mut replayed) {
+        (Decision::Accepted { reward: logged, .. }, Decision::Accepted { reward, .. })
+            if logged.config_version.is_none() =>
+        {
+            reward.config_version = None
+        }
+        (Decision::Rejected { reason: None, .. }, Decision::Rejected { reason, .. }) => *reason = None,
+        _ => {}
+    }
+    Ok(serde_json::to_value(logged)? == serde_json::to_value(&replayed)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::models::{BattleEventData, DailyStats, FlyPokeEventData, LoginStreak, Reward};
+    use crate::storage::{MemoryStorage, StorageVisitor};
+    use std::collections::BTreeSet;
+    use std::sync::Arc;
+
+    fn claim(player_id: &str) -> LogRecord {
+        LogRecord::Claim {
+            player_id: player_id.to_string(),
+        }
+    }
+
+    fn claimed_player(entry: &LogEntry) -> &str {
+        match &entry.record {
+            LogRecord::Claim { player_id } => player_id,
+            other => panic!("expected a claim, got {:?}", other),
+        }
+    }
+
+    /// Every record in a storage, serialized, in a stable order
+    #[derive(Default)]
+    struct Records(BTreeSet<String>);
+
+    impl Records {
+        fn add(&mut self, kind: &str, record: &impl Serialize) -> Result<()> {
+            self.0.insert(format!("{} {}", kind, serde_json::to_string(record)?));
+            Ok(())
+        }
+    }
+
+    impl StorageVisitor for Records {
+        fn visit_reward(&mut self, reward: &Reward) -> Result<()> {
+            self.add("reward", reward)
+        }
+
+        fn visit_daily_stats(&mut self, stats: &DailyStats) -> Result<()> {
+            self.add("daily_stats", stats)
+        }
+
+        fn visit_login_streak(&mut self, streak: &LoginStreak) -> Result<()> {
+            self.add("login_streak", streak)
+        }
+
+        fn visit_payout(&mut self, payout: &PayoutRequest) -> Result<()> {
+            self.add("payout", payout)
+        }
+    }
+
+    async fn records(storage: &dyn Storage) -> BTreeSet<String> {
+        let mut records = Records::default();
+        storage.export(&mut records).await.unwrap();
+        records.0
+    }
+
+    fn temp_log_path() -> PathBuf {
+        std::env::temp_dir().join(format!("pokemon-engine-log-{}.jsonl", Uuid::new_v4()))
+    }
+
+    async fn append_and_read_in_order(log: &dyn EventLog) {
+        assert_eq!(log.last_sequence().await.unwrap(), 0);
+        for (i, player_id) in ["player1", "player2", "player3"].into_iter().enumerate() {
+            assert_eq!(log.append(Utc::now(), claim(player_id)).await.unwrap(), i as u64 + 1);
+        }
+        assert_eq!(log.last_sequence().await.unwrap(), 3);
+
+        let entries = log.read(1, 10).await.unwrap();
+        let sequences: Vec<u64> = entries.iter().map(|e| e.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3]);
+        let players: Vec<&str> = entries.iter().map(claimed_player).collect();
+        assert_eq!(players, vec!["player1", "player2", "player3"]);
+
+        let middle = log.read(2, 1).await.unwrap();
+        assert_eq!(middle.len(), 1);
+        assert_eq!(claimed_player(&middle[0]), "player2");
+        assert!(log.read(4, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn memory_log_reads_back_in_append_order() {
+        append_and_read_in_order(&MemoryEventLog::new()).await;
+    }
+
+    #[tokio::test]
+    async fn file_log_reads_back_in_append_order() {
+        let path = temp_log_path();
+        append_and_read_in_order(&FileEventLog::open(&path).unwrap()).await;
+
+        // Reopening finds the same entries
+        let log = FileEventLog::open(&path).unwrap();
+        assert_eq!(log.last_sequence().await.unwrap(), 3);
+        assert_eq!(claimed_player(&log.read(3, 1).await.unwrap()[0]), "player3");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_log_drops_a_torn_last_line_on_open() {
+        let path = temp_log_path();
+        {
+            let log = FileEventLog::open(&path).unwrap();
+            log.append(Utc::now(), claim("player1")).await.unwrap();
+            log.append(Utc::now(), claim("player2")).await.unwrap();
+        }
+        let intact = std::fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"sequence":3,"recorded_at":"#).unwrap();
+        drop(file);
+
+        let log = FileEventLog::open(&path).unwrap();
+        assert_eq!(log.last_sequence().await.unwrap(), 2);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), intact);
+
+        assert_eq!(log.append(Utc::now(), claim("player3")).await.unwrap(), 3);
+        drop(log);
+        let log = FileEventLog::open(&path).unwrap();
+        let players: Vec<String> = log
+            .read(1, 10)
+            .await
+            .unwrap()
+            .iter()
+            .map(|e| claimed_player(e).to_string())
+            .collect();
+        assert_eq!(players, vec!["player1", "player2", "player3"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replay_skips_aborted_sequences() {
+        let log = Arc::new(MemoryEventLog::new());
+        let engine = RewardEngine::new(Config::default(), Box::new(MemoryStorage::new()))
+            .unwrap()
+            .with_event_log(Arc::clone(&log) as Arc<dyn EventLog>);
+        let data = BattleEventData { level: 1, streak: 0, perfect_victory: None };
+        engine.process_battle_event("player1", &data).await.unwrap();
+
+        // A claim that storage rejected
+        let sequence = log.append(Utc::now(), claim("player1")).await.unwrap();
+        log.append(Utc::now(), LogRecord::Aborted { sequence }).await.unwrap();
+
+        let (replayed, report) = replay(log.as_ref(), Config::default(), Box::new(MemoryStorage::new()))
+            .await
+            .unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(report.entries_read, 3);
+        assert_eq!(report.rewards_restored, 1);
+        assert_eq!(report.aborted_skipped, 1);
+        assert_eq!(report.claims_applied, 0);
+        assert_eq!(replayed.get_pending_rewards("player1").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn replay_into_fresh_storage_reproduces_the_original() {
+        let log = Arc::new(MemoryEventLog::new());
+        let engine = RewardEngine::new(Config::default(), Box::new(MemoryStorage::new()))
+            .unwrap()
+            .with_event_log(Arc::clone(&log) as Arc<dyn EventLog>);
+        let battle = BattleEventData { level: 3, streak: 2, perfect_victory: Some(true) };
+        let flypoke = FlyPokeEventData { score: 1_200, is_new_high_score: true, level: None };
+
+        engine.process_welcome_event("player1").await.unwrap();
+        engine.process_login_event("player1").await.unwrap();
+        engine.process_login_event("player1").await.unwrap();
+        engine.process_battle_event("player1", &battle).await.unwrap();
+        engine.claim_rewards("player1").await.unwrap();
+        let paid = engine.process_flypoke_event("player2", &flypoke).await.unwrap();
+        engine.record_payout(&paid.reward.unwrap().id, "sig1").await.unwrap();
+
+        let mut config = Config::default();
+        config.rewards.battle.base_reward = Amount::from_lamports(25_000_000_000);
+        engine.reload_config(config).await.unwrap();
+        engine.process_battle_event("player2", &battle).await.unwrap();
+        let wallet = solana_sdk::pubkey::Pubkey::new_unique().to_string();
+        engine.request_payout("player2", &wallet).await.unwrap().unwrap();
+
+        let (replayed, report) = replay(log.as_ref(), Config::default(), Box::new(MemoryStorage::new()))
+            .await
+            .unwrap();
+        assert!(report.is_consistent(), "{:?}", report.mismatches);
+        assert_eq!(report.entries_read, log.last_sequence().await.unwrap());
+        assert_eq!(report.rewards_restored, 5);
+        assert_eq!(report.rejections_verified, 1);
+        assert_eq!(report.claims_applied, 1);
+        assert_eq!(report.payouts_applied, 1);
+        assert_eq!(report.payouts_requested, 1);
+        assert_eq!(report.config_changes, 1);
+        assert_eq!(replayed.config_version(), engine.config_version());
+        assert_eq!(records(replayed.storage()).await, records(engine.storage()).await);
+    }
+}
@@ -0,0 +1,678 @@
+//! Double-entry POKE ledger
+//!
+//! Every movement of POKE is a transaction of postings that sum to zero.
+//! A positive posting adds to an account, a negative one takes from it:
+//!
+//! | Transaction | From               | To                  |
+//! |-------------|--------------------|---------------------|
+//! | Issuance    | `EmissionPool`     | `PlayerPending(p)`  |
+//! | Claim       | `PlayerPending(p)` | `Vault`             |
+//! | Payout      | `Vault`            | `PlayerPaid(p)`     |
+//! | Fee         | `Vault`            | `Fees`              |
+//! | Revocation  | `PlayerPending(p)` or `Vault` | `EmissionPool` |
+//! | Reversal    | the postings of a claim or payout, negated |  |
+//!
+//! `EmissionPool` is the only account that goes negative: its balance is
+//! minus the POKE currently issued. `Vault` holds claimed rewards awaiting
+//! payout. A fee is withheld from a payout, so the player receives the
+//! reward amount minus the fee. A reversal undoes a claim or payout that
+//! storage then refused, returning the reward to its earlier state.
+//!
+//! The ledger lives in memory; `Ledger::rebuild` restores it from storage
+//! after a restart.
+
+use crate::error::{PokemonEngineError, Result};
+use crate::models::{DailyStats, LoginStreak, PayoutRequest, Reward};
+use crate::storage::{Storage, StorageVisitor};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use uuid::Uuid;
+
+/// Ledger account
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Account {
+    EmissionPool,
+    PlayerPending(String),
+    PlayerPaid(String),
+    Vault,
+    Fees,
+}
+
+impl std::fmt::Display for Account {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Account::EmissionPool => write!(f, "emission_pool"),
+            Account::PlayerPending(player_id) => write!(f, "player_pending:{}", player_id),
+            Account::PlayerPaid(player_id) => write!(f, "player_paid:{}", player_id),
+            Account::Vault => write!(f, "vault"),
+            Account::Fees => write!(f, "fees"),
+        }
+    }
+}
+
+/// Kind of ledger transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    Issuance,
+    Claim,
+    Payout,
+    Fee,
+    Revocation,
+    Reversal,
+}
+
+/// One side of a transaction (lamports; positive adds to the account)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Posting {
+    pub account: Account,
+    pub amount: i128,
+}
+
+/// Balanced set of postings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerTransaction {
+    pub id: u64, // Starts at 1, in posting order
+    pub kind: TransactionKind,
+    pub reward_id: Uuid,
+    pub reference: Option<String>, // Payout signature or revocation reason
+    pub posted_at: DateTime<Utc>,
+    pub postings: Vec<Posting>,
+}
+
+/// Where a reward is in its lifecycle, as far as the ledger knows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardState {
+    Pending,
+    Claimed,
+    Paid,
+    Revoked,
+}
+
+/// Ledger view of one reward
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerReward {
+    pub player_id: String,
+    pub amount: u64,
+    pub state: RewardState,
+}
+
+/// Balances of one player
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerBalance {
+    pub pending: i128,
+    pub paid: i128,
+}
+
+/// Result of `Ledger::check_invariants`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvariantReport {
+    pub transactions_checked: u64,
+    pub total: i128, // Sum of all balances; zero in a sound ledger
+    pub violations: Vec<String>,
+}
+
+impl InvariantReport {
+    /// Check if the ledger sums to zero and every invariant holds
+    pub fn is_sound(&self) -> bool {
+        self.total == 0 && self.violations.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct LedgerState {
+    transactions: Vec<LedgerTransaction>,
+    balances: HashMap<Account, i128>,
+    rewards: HashMap<Uuid, LedgerReward>,
+}
+
+impl LedgerState {
+    fn post(
+        &mut self,
+        kind: TransactionKind,
+        reward_id: Uuid,
+        reference: Option<String>,
+        postings: Vec<Posting>,
+    ) -> Result<u64> {
+        let sum: i128 = postings.iter().map(|p| p.amount).sum();
+        if sum != 0 {
+            return Err(PokemonEngineError::Ledger(format!(
+                "Unbalanced {:?} transaction for reward {}: postings sum to {}",
+                kind, reward_id, sum
+            )));
+        }
+
+        for posting in &postings {
+            *self.balances.entry(posting.account.clone()).or_default() += posting.amount;
+        }
+        let id = self.transactions.len() as u64 + 1;
+        self.transactions.push(LedgerTransaction {
+            id,
+            kind,
+            reward_id,
+            reference,
+            posted_at: Utc::now(),
+            postings,
+        });
+        Ok(id)
+    }
+
+    fn reward(&self, reward_id: &Uuid) -> Result<&LedgerReward> {
+        self.rewards
+            .get(reward_id)
+            .ok_or_else(|| PokemonEngineError::Ledger(format!("Reward not in ledger: {}", reward_id)))
+    }
+
+    fn issue(&mut self, reward: &Reward) -> Result<u64> {
+        if self.rewards.contains_key(&reward.id) {
+            return Err(PokemonEngineError::Ledger(format!("Reward already issued: {}", reward.id)));
+        }
+
+        let amount = reward.amount.lamports() as i128;
+        let id = self.post(
+            TransactionKind::Issuance,
+            reward.id,
+            None,
+            vec![
+                posting(Account::EmissionPool, -amount),
+                posting(Account::PlayerPending(reward.player_id.clone()), amount),
+            ],
+        )?;
+        self.rewards.insert(
+            reward.id,
+            LedgerReward {
+                player_id: reward.player_id.clone(),
+                amount: reward.amount.lamports(),
+                state: RewardState::Pending,
+            },
+        );
+        Ok(id)
+    }
+
+    fn claim(&mut self, reward_id: &Uuid) -> Result<u64> {
+        let reward = self.transition(reward_id, &[RewardState::Pending], RewardState::Claimed)?;
+        let amount = reward.amount as i128;
+        self.post(
+            TransactionKind::Claim,
+            *reward_id,
+            None,
+            vec![
+                posting(Account::PlayerPending(reward.player_id), -amount),
+                posting(Account::Vault, amount),
+            ],
+        )
+    }
+
+    fn pay(&mut self, reward_id: &Uuid, signature: &str, fee: u64) -> Result<Vec<u64>> {
+        let reward = self.reward(reward_id)?;
+        if fee > reward.amount {
+            return Err(PokemonEngineError::Ledger(format!(
+                "Fee {} exceeds reward {} amount {}",
+                fee, reward_id, reward.amount
+            )));
+        }
+        let reward = self.transition(reward_id, &[RewardState::Claimed], RewardState::Paid)?;
+
+        let paid = (reward.amount - fee) as i128;
+        let mut ids = vec![self.post(
+            TransactionKind::Payout,
+            *reward_id,
+            Some(signature.to_string()),
+            vec![
+                posting(Account::Vault, -paid),
+                posting(Account::PlayerPaid(reward.player_id), paid),
+            ],
+        )?];
+        if fee > 0 {
+            ids.push(self.post(
+                TransactionKind::Fee,
+                *reward_id,
+                Some(signature.to_string()),
+                vec![posting(Account::Vault, -(fee as i128)), posting(Account::Fees, fee as i128)],
+            )?);
+        }
+        Ok(ids)
+    }
+
+    /// Negate the reward's latest transactions of `kinds` in one reversal
+    ///
+    /// `kinds` lists the transactions posted together for the step being
+    /// undone, the first of them mandatory (a payout and its optional fee).
+    fn reverse(
+        &mut self,
+        reward_id: &Uuid,
+        kinds: &[TransactionKind],
+        from: RewardState,
+        to: RewardState,
+        reason: &str,
+    ) -> Result<u64> {
+        let state = self.reward(reward_id)?.state;
+        if state != from {
+            return Err(PokemonEngineError::Ledger(format!(
+                "Reward {} is {:?}, expected {:?}",
+                reward_id, state, from
+            )));
+        }
+        // Only look back as far as the reward's previous reversal
+        let mut postings = Vec::new();
+        for (index, kind) in kinds.iter().enumerate() {
+            let last = self
+                .transactions
+                .iter()
+                .rev()
+                .filter(|tx| tx.reward_id == *reward_id)
+                .take_while(|tx| tx.kind != TransactionKind::Reversal)
+                .find(|tx| tx.kind == *kind);
+            match last {
+                Some(tx) => postings.extend(tx.postings.iter().map(|p| posting(p.account.clone(), -p.amount))),
+                None if index == 0 => {
+                    return Err(PokemonEngineError::Ledger(format!(
+                        "No {:?} transaction to reverse for reward {}",
+                        kind, reward_id
+                    )));
+                }
+                None => {}
+            }
+        }
+        self.transition(reward_id, &[from], to)?;
+        self.post(TransactionKind::Reversal, *reward_id, Some(reason.to_string()), postings)
+    }
+
+    /// Move a reward to a new state, checking it is in one of `from`
+    fn transition(&mut self, reward_id: &Uuid, from: &[RewardState], to: RewardState) -> Result<LedgerReward> {
+        let reward = self.reward(reward_id)?;
+        if !from.contains(&reward.state) {
+            return Err(PokemonEngineError::Ledger(format!(
+                "Reward {} is {:?}, expected one of {:?}",
+                reward_id, reward.state, from
+            )));
+        }
+        let before = reward.clone();
+        if let Some(reward) = self.rewards.get_mut(reward_id) {
+            reward.state = to;
+        }
+        Ok(before)
+    }
+}
+
+/// In-memory double-entry ledger
+#[derive(Default)]
+pub struct Ledger {
+    state: RwLock<LedgerState>,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, LedgerState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, LedgerState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replace the ledger's contents with the state recorded in `storage`
+    ///
+    /// The ledger is kept in memory only, so call this after a restart:
+    /// every stored reward is issued again, then claimed if it is claimed
+    /// and paid (without a fee) if it has a transaction signature. Earlier
+    /// transaction history, revocations included, is not restored. Returns
+    /// the number of rewards rebuilt.
+    pub async fn rebuild(&self, storage: &dyn Storage) -> Result<u64> {
+        let mut rebuilder = Rebuilder {
+            state: LedgerState::default(),
+            rewards: 0,
+        };
+        storage.export(&mut rebuilder).await?;
+        *self.write() = rebuilder.state;
+        Ok(rebuilder.rewards)
+    }
+
+    /// Post the issuance of a new reward
+    pub fn issue(&self, reward: &Reward) -> Result<u64> {
+        self.write().issue(reward)
+    }
+
+    /// Post the claim of a pending reward
+    pub fn claim(&self, reward_id: &Uuid) -> Result<u64> {
+        self.write().claim(reward_id)
+    }
+
+    /// Post the payout of a claimed reward, withholding `fee`
+    ///
+    /// Returns the payout transaction id, followed by the fee transaction id
+    /// if the fee is non-zero.
+    pub fn pay(&self, reward_id: &Uuid, signature: &str, fee: u64) -> Result<Vec<u64>> {
+        self.write().pay(reward_id, signature, fee)
+    }
+
+    /// Reverse the claim of a reward, making it pending again
+    pub fn reverse_claim(&self, reward_id: &Uuid, reason: &str) -> Result<u64> {
+        self.write().reverse(
+            reward_id,
+            &[TransactionKind::Claim],
+            RewardState::Claimed,
+            RewardState::Pending,
+            reason,
+        )
+    }
+
+    /// Reverse the payout of a reward and its fee, making it claimed again
+    pub fn reverse_payout(&self, reward_id: &Uuid, reason: &str) -> Result<u64> {
+        self.write().reverse(
+            reward_id,
+            &[TransactionKind::Payout, TransactionKind::Fee],
+            RewardState::Paid,
+            RewardState::Claimed,
+            reason,
+        )
+    }
+
+    /// Post the revocation of a pending or claimed (not yet paid) reward
+    pub fn revoke(&self, reward_id: &Uuid, reason: &str) -> Result<u64> {
+        let mut state = self.write();
+        let reward = state.transition(
+            reward_id,
+            &[RewardState::Pending, RewardState::Claimed],
+            RewardState::Revoked,
+        )?;
+        let amount = reward.amount as i128;
+        let source = match reward.state {
+            RewardState::Pending => Account::PlayerPending(reward.player_id),
+            _ => Account::Vault,
+        };
+        state.post(
+            TransactionKind::Revocation,
+            *reward_id,
+            Some(reason.to_string()),
+            vec![posting(source, -amount), posting(Account::EmissionPool, amount)],
+        )
+    }
+
+    /// Current balance of an account
+    pub fn balance(&self, account: &Account) -> i128 {
+        self.read().balances.get(account).copied().unwrap_or(0)
+    }
+
+    /// Every account with a posting, in account order
+    pub fn balances(&self) -> BTreeMap<Account, i128> {
+        self.read().balances.iter().map(|(a, b)| (a.clone(), *b)).collect()
+    }
+
+    /// Pending and paid balances of a player
+    pub fn player_balance(&self, player_id: &str) -> PlayerBalance {
+        let state = self.read();
+        let balance = |account: Account| state.balances.get(&account).copied().unwrap_or(0);
+        PlayerBalance {
+            pending: balance(Account::PlayerPending(player_id.to_string())),
+            paid: balance(Account::PlayerPaid(player_id.to_string())),
+        }
+    }
+
+    /// Ledger view of a reward
+    pub fn reward(&self, reward_id: &Uuid) -> Option<LedgerReward> {
+        self.read().rewards.get(reward_id).cloned()
+    }
+
+    /// Up to `limit` transactions starting at id `from`
+    pub fn transactions(&self, from: u64, limit: usize) -> Vec<LedgerTransaction> {
+        let state = self.read();
+        let start = (from.max(1) - 1) as usize;
+        state.transactions.iter().skip(start).take(limit).cloned().collect()
+    }
+
+    /// Recompute every balance from the transactions and check invariants
+    ///
+    /// Checks that each transaction balances, the recomputed balances match
+    /// the running ones and sum to zero, only `EmissionPool` is negative, and
+    /// each player's pending balance equals their pending rewards (and the
+    /// vault equals all claimed rewards).
+    pub fn check_invariants(&self) -> InvariantReport {
+        let state = self.read();
+        let mut violations = Vec::new();
+        let mut recomputed: HashMap<&Account, i128> = HashMap::new();
+
+        for tx in &state.transactions {
+            let sum: i128 = tx.postings.iter().map(|p| p.amount).sum();
+            if sum != 0 {
+                violations.push(format!("transaction {} sums to {}", tx.id, sum));
+            }
+            for posting in &tx.postings {
+                *recomputed.entry(&posting.account).or_default() += posting.amount;
+            }
+        }
+
+        for (account, balance) in &state.balances {
+            let expected = recomputed.get(account).copied().unwrap_or(0);
+            if *balance != expected {
+                violations.push(format!("{} balance {} but transactions sum to {}", account, balance, expected));
+            }
+            let negative_allowed = *account == Account::EmissionPool;
+            if *balance < 0 && !negative_allowed {
+                violations.push(format!("{} is negative: {}", account, balance));
+            }
+        }
+        if let Some(pool) = state.balances.get(&Account::EmissionPool) {
+            if *pool > 0 {
+                violations.push(format!("emission_pool is positive: {}", pool));
+            }
+        }
+
+        let mut expected: HashMap<Account, i128> = HashMap::new();
+        for reward in state.rewards.values() {
+            let account = match reward.state {
+                RewardState::Pending => Account::PlayerPending(reward.player_id.clone()),
+                RewardState::Claimed => Account::Vault,
+                RewardState::Paid | RewardState::Revoked => continue,
+            };
+            *expected.entry(account).or_default() += reward.amount as i128;
+        }
+        for (account, balance) in &state.balances {
+            if matches!(account, Account::PlayerPending(_) | Account::Vault) {
+                let from_rewards = expected.remove(account).unwrap_or(0);
+                if *balance != from_rewards {
+                    violations.push(format!(
+                        "{} balance {} but its rewards total {}",
+                        account, balance, from_rewards
+                    ));
+                }
+            }
+        }
+        for (account, from_rewards) in expected {
+            violations.push(format!("{} has no postings but its rewards total {}", account, from_rewards));
+        }
+
+        violations.sort();
+        InvariantReport {
+            transactions_checked: state.transactions.len() as u64,
+            total: recomputed.values().sum(),
+            violations,
+        }
+    }
+}
+
+fn posting(account: Account, amount: i128) -> Posting {
+    Posting { account, amount }
+}
+
+/// Posts each stored reward into a fresh ledger state during `Ledger::rebuild`
+struct Rebuilder {
+    state: LedgerState,
+    rewards: u64,
+}
+
+impl StorageVisitor for Rebuilder {
+    fn visit_reward(&mut self, reward: &Reward) -> Result<()> {
+        self.state.issue(reward)?;
+        if reward.claimed || reward.transaction_signature.is_some() {
+            self.state.claim(&reward.id)?;
+        }
+        if let Some(signature) = &reward.transaction_signature {
+            self.state.pay(&reward.id, signature, 0)?;
+        }
+        self.rewards += 1;
+        Ok(())
+    }
+
+    fn visit_daily_stats(&mut self, _stats: &DailyStats) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_login_streak(&mut self, _streak: &LoginStreak) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_payout(&mut self, _payout: &PayoutRequest) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::models::GameType;
+    use crate::storage::MemoryStorage;
+
+    fn reward(player_id: &str, lamports: u64) -> Reward {
+        Reward {
+            id: Uuid::new_v4(),
+            player_id: player_id.to_string(),
+            game: GameType::Battle,
+            amount: Amount::from_lamports(lamports),
+            timestamp: Utc::now(),
+            claimed: false,
+            game_data: serde_json::Value::Null,
+            transaction_signature: None,
+            config_version: None,
+        }
+    }
+
+    #[test]
+    fn issue_claim_pay_moves_balances() {
+        let ledger = Ledger::new();
+        let reward = reward("player1", 100);
+
+        ledger.issue(&reward).unwrap();
+        assert_eq!(ledger.player_balance("player1"), PlayerBalance { pending: 100, paid: 0 });
+        assert_eq!(ledger.balance(&Account::EmissionPool), -100);
+
+        ledger.claim(&reward.id).unwrap();
+        assert_eq!(ledger.player_balance("player1").pending, 0);
+        assert_eq!(ledger.balance(&Account::Vault), 100);
+
+        let ids = ledger.pay(&reward.id, "sig", 10).unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ledger.player_balance("player1"), PlayerBalance { pending: 0, paid: 90 });
+        assert_eq!(ledger.balance(&Account::Fees), 10);
+        assert_eq!(ledger.balance(&Account::Vault), 0);
+        assert_eq!(ledger.reward(&reward.id).unwrap().state, RewardState::Paid);
+
+        let report = ledger.check_invariants();
+        assert!(report.is_sound(), "{:?}", report.violations);
+        assert_eq!(report.transactions_checked, 4);
+    }
+
+    #[test]
+    fn steps_out_of_order_are_refused() {
+        let ledger = Ledger::new();
+        let reward = reward("player1", 100);
+        assert!(ledger.claim(&reward.id).is_err());
+
+        ledger.issue(&reward).unwrap();
+        assert!(ledger.issue(&reward).is_err());
+        assert!(ledger.pay(&reward.id, "sig", 0).is_err());
+        ledger.claim(&reward.id).unwrap();
+        assert!(ledger.claim(&reward.id).is_err());
+        assert!(ledger.pay(&reward.id, "sig", 101).is_err());
+        assert!(ledger.check_invariants().is_sound());
+    }
+
+    #[test]
+    fn revoke_returns_pending_and_claimed_rewards_to_the_pool() {
+        let ledger = Ledger::new();
+        let pending = reward("player1", 100);
+        let claimed = reward("player1", 50);
+        ledger.issue(&pending).unwrap();
+        ledger.issue(&claimed).unwrap();
+        ledger.claim(&claimed.id).unwrap();
+
+        ledger.revoke(&pending.id, "fraud").unwrap();
+        ledger.revoke(&claimed.id, "fraud").unwrap();
+        assert_eq!(ledger.balance(&Account::EmissionPool), 0);
+        assert_eq!(ledger.balance(&Account::Vault), 0);
+        assert_eq!(ledger.player_balance("player1").pending, 0);
+        assert!(ledger.revoke(&pending.id, "again").is_err());
+        assert!(ledger.check_invariants().is_sound());
+    }
+
+    #[test]
+    fn reversals_restore_the_previous_state() {
+        let ledger = Ledger::new();
+        let reward = reward("player1", 100);
+        ledger.issue(&reward).unwrap();
+        ledger.claim(&reward.id).unwrap();
+        ledger.reverse_claim(&reward.id, "not saved").unwrap();
+        assert_eq!(ledger.reward(&reward.id).unwrap().state, RewardState::Pending);
+        assert_eq!(ledger.player_balance("player1").pending, 100);
+        assert!(ledger.reverse_claim(&reward.id, "twice").is_err());
+
+        ledger.claim(&reward.id).unwrap();
+        ledger.pay(&reward.id, "sig", 10).unwrap();
+        ledger.reverse_payout(&reward.id, "not saved").unwrap();
+        assert_eq!(ledger.reward(&reward.id).unwrap().state, RewardState::Claimed);
+        assert_eq!(ledger.balance(&Account::Vault), 100);
+        assert_eq!(ledger.balance(&Account::Fees), 0);
+        assert_eq!(ledger.player_balance("player1").paid, 0);
+
+        ledger.pay(&reward.id, "sig2", 0).unwrap();
+        assert_eq!(ledger.player_balance("player1").paid, 100);
+        assert!(ledger.check_invariants().is_sound());
+    }
+
+    #[test]
+    fn invariant_violations_are_reported() {
+        let ledger = Ledger::new();
+        let reward = reward("player1", 100);
+        ledger.issue(&reward).unwrap();
+        ledger.write().balances.insert(Account::Vault, -5);
+
+        let report = ledger.check_invariants();
+        assert!(!report.is_sound());
+        assert_eq!(report.total, 0);
+        assert!(report.violations.iter().any(|v| v == "vault is negative: -5"), "{:?}", report.violations);
+        assert!(report.violations.iter().any(|v| v.starts_with("vault balance -5 but transactions sum to 0")));
+    }
+
+    #[tokio::test]
+    async fn rebuild_restores_states_from_storage() {
+        let storage = MemoryStorage::new();
+        let pending = reward("player1", 100);
+        let mut claimed = reward("player1", 50);
+        claimed.claimed = true;
+        let mut paid = reward("player2", 25);
+        paid.claimed = true;
+        paid.transaction_signature = Some("sig".to_string());
+        for reward in [&pending, &claimed, &paid] {
+            storage.create_reward(reward).await.unwrap();
+        }
+
+        let ledger = Ledger::new();
+        ledger.issue(&reward("stale", 1)).unwrap();
+        assert_eq!(ledger.rebuild(&storage).await.unwrap(), 3);
+        assert_eq!(ledger.reward(&pending.id).unwrap().state, RewardState::Pending);
+        assert_eq!(ledger.reward(&claimed.id).unwrap().state, RewardState::Claimed);
+        assert_eq!(ledger.reward(&paid.id).unwrap().state, RewardState::Paid);
+        assert_eq!(ledger.player_balance("stale"), PlayerBalance::default());
+        assert_eq!(ledger.balance(&Account::Vault), 50);
+        assert_eq!(ledger.balance(&Account::EmissionPool), -175);
+        assert!(ledger.check_invariants().is_sound());
+    }
+}
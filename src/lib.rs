@@ -19,6 +19,7 @@
 //!         "score": 1500,
 //!         "is_new_high_score": false
 //!     }),
+//!     locale: None,
 //! };
 //!
 //! let response = engine.process_game_event(&event).await?;
@@ -26,14 +27,27 @@
 //! # }
 //! ```
 
+pub mod amount;
 pub mod config;
+pub mod config_watcher;
 pub mod engine;
+pub mod event_log;
+pub mod ledger;
+pub mod messages;
 pub mod models;
+pub mod monitor;
+pub mod payout_worker;
+pub mod reason;
+pub mod reconciliation;
+pub mod rpc_pool;
+pub mod simulation;
 pub mod solana;
 pub mod storage;
 pub mod error;
 
+pub use amount::Amount;
 pub use engine::RewardEngine;
+pub use reason::ReasonCode;
 pub use models::*;
 pub use error::{PokemonEngineError, Result};
 
@@ -41,11 +55,25 @@ pub use error::{PokemonEngineError, Result};
 pub fn create_reward_engine() -> Result<RewardEngine> {
     let config = config::Config::default();
     let storage = storage::MemoryStorage::new();
-    Ok(engine::RewardEngine::new(config, Box::new(storage)))
+    engine::RewardEngine::new(config, Box::new(storage))
 }
 
 /// Initialize the reward engine with custom configuration
 pub fn create_reward_engine_with_config(config: config::Config) -> Result<RewardEngine> {
     let storage = storage::MemoryStorage::new();
-    Ok(engine::RewardEngine::new(config, Box::new(storage)))
+    engine::RewardEngine::new(config, Box::new(storage))
+}
+
+/// Initialize the reward engine with default configuration and persistent sled storage
+pub fn create_reward_engine_with_sled(path: impl AsRef<std::path::Path>) -> Result<RewardEngine> {
+    let config = config::Config::default();
+    let storage = storage::SledStorage::open(path)?;
+    engine::RewardEngine::new(config, Box::new(storage))
+}
+
+/// Initialize the reward engine from a TOML or JSON config file, with environment overrides
+pub fn create_reward_engine_from_file(path: impl AsRef<std::path::Path>) -> Result<RewardEngine> {
+    let config = config::Config::from_file(path)?;
+    let storage = storage::MemoryStorage::new();
+    engine::RewardEngine::new(config, Box::new(storage))
 }
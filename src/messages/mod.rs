@@ -0,0 +1,284 @@
+//! Localized player-facing messages
+//!
+//! Messages come from per-locale catalogs of templates keyed by reason code
+//! (for rejections and errors) or by game (for accepted rewards). Templates
+//! interpolate `{game}`, `{amount}`, `{limit}` and `{streak}`. Catalogs for
+//! `en` and `id` are bundled; more can be loaded from TOML in the same format.
+//!
+//! Lookups fall back from the requested locale to its language (`id-ID` to
+//! `id`), then to the default locale, then to `en`.
+
+use crate::amount::{Amount, DEFAULT_DECIMALS};
+use crate::error::{PokemonEngineError, Result};
+use crate::models::{ErrorResponse, GameType};
+use crate::reason::ReasonCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Locale every lookup falls back to last
+pub const FALLBACK_LOCALE: &str = "en";
+
+const BUNDLED: [(&str, &str); 2] = [("en", include_str!("en.toml")), ("id", include_str!("id.toml"))];
+
+const PLACEHOLDERS: [&str; 4] = ["{game}", "{amount}", "{limit}", "{streak}"];
+
+/// Templates for one locale
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessageCatalog {
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: String,
+    #[serde(default)]
+    pub games: HashMap<String, String>, // Display names by game, e.g. `flypoke`
+    #[serde(default)]
+    pub accepted: HashMap<String, String>, // By game, with `default` for the rest
+    #[serde(default)]
+    pub reasons: HashMap<String, String>, // By reason code
+}
+
+fn default_decimal_separator() -> String {
+    ".".to_string()
+}
+
+/// Values interpolated into a template
+#[derive(Debug, Clone, Copy)]
+pub struct MessageArgs {
+    pub game: Option<GameType>,
+    pub amount: Option<Amount>,
+    pub limit: Option<Amount>,
+    pub streak: Option<u32>,
+    pub token_decimals: u8, // Used to format `amount` and `limit`
+}
+
+impl Default for MessageArgs {
+    fn default() -> Self {
+        Self {
+            game: None,
+            amount: None,
+            limit: None,
+            streak: None,
+            token_decimals: DEFAULT_DECIMALS,
+        }
+    }
+}
+
+impl MessageArgs {
+    /// Values an error carries itself, such as the game of a daily limit
+    pub fn from_error(err: &PokemonEngineError) -> Self {
+        let game = match err {
+            PokemonEngineError::DailyLimitReached(game) | PokemonEngineError::InvalidGameType(game) => {
+                serde_json::from_value(serde_json::Value::String(game.trim().to_ascii_lowercase())).ok()
+            }
+            _ => None,
+        };
+        Self { game, ..Self::default() }
+    }
+}
+
+impl MessageCatalog {
+    /// Parse a catalog from TOML
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text).map_err(|e| PokemonEngineError::config("", format!("Invalid message catalog: {}", e)))
+    }
+
+    /// Template for an accepted reward in `game`
+    pub fn accepted_template(&self, game: GameType) -> Option<&str> {
+        self.accepted
+            .get(&game.to_string())
+            .or_else(|| self.accepted.get("default"))
+            .map(String::as_str)
+    }
+
+    /// Template for a rejection or error
+    pub fn reason_template(&self, reason: ReasonCode) -> Option<&str> {
+        self.reasons.get(reason.as_str()).map(String::as_str)
+    }
+
+    /// Fill a template's placeholders from `args`
+    ///
+    /// Game names come from this catalog's `games` table. Placeholders
+    /// without a value are left as they are.
+    pub fn render(&self, template: &str, args: &MessageArgs) -> String {
+        let mut text = template.to_string();
+        if let Some(game) = args.game {
+            let key = game.to_string();
+            let name = self.games.get(&key).cloned().unwrap_or(key);
+            text = text.replace("{game}", &name);
+        }
+        if let Some(amount) = args.amount {
+            text = text.replace("{amount}", &self.format_amount(amount, args.token_decimals));
+        }
+        if let Some(limit) = args.limit {
+            text = text.replace("{limit}", &self.format_amount(limit, args.token_decimals));
+        }
+        if let Some(streak) = args.streak {
+            text = text.replace("{streak}", &streak.to_string());
+        }
+        text
+    }
+
+    /// Format an amount as POKE with this locale's decimal separator
+    pub fn format_amount(&self, amount: Amount, decimals: u8) -> String {
+        let tokens = amount.to_tokens_string(decimals).replace('.', &self.decimal_separator);
+        format!("{} POKE", tokens)
+    }
+}
+
+/// Message catalogs by locale
+#[derive(Debug, Clone)]
+pub struct Messages {
+    catalogs: HashMap<String, MessageCatalog>,
+    default_locale: String,
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self::bundled()
+    }
+}
+
+impl Messages {
+    /// The bundled `en` and `id` catalogs, defaulting to `en`
+    pub fn bundled() -> Self {
+        let catalogs = BUNDLED
+            .iter()
+            .map(|(locale, text)| {
+                let catalog = MessageCatalog::from_toml(text).expect("bundled message catalogs are valid");
+                (locale.to_string(), catalog)
+            })
+            .collect();
+        Self {
+            catalogs,
+            default_locale: FALLBACK_LOCALE.to_string(),
+        }
+    }
+
+    /// Add or replace the catalog for `locale`
+    pub fn with_catalog(mut self, locale: &str, catalog: MessageCatalog) -> Self {
+        self.catalogs.insert(normalize(locale), catalog);
+        self
+    }
+
+    /// Set the locale used when a request names none, or one without a catalog
+    pub fn with_default_locale(mut self, locale: &str) -> Self {
+        self.default_locale = normalize(locale);
+        self
+    }
+
+    /// Locales with a catalog, sorted
+    pub fn locales(&self) -> Vec<&str> {
+        let mut locales: Vec<&str> = self.catalogs.keys().map(String::as_str).collect();
+        locales.sort_unstable();
+        locales
+    }
+
+    /// Message for an accepted reward, if any catalog has a template
+    pub fn accepted(&self, locale: Option<&str>, args: &MessageArgs) -> Option<String> {
+        let game = args.game?;
+        self.lookup(locale, |catalog| catalog.accepted_template(game).map(|t| catalog.render(t, args)))
+    }
+
+    /// Message for a rejection or error, if any catalog has a template
+    pub fn reason(&self, locale: Option<&str>, reason: ReasonCode, args: &MessageArgs) -> Option<String> {
+        self.lookup(locale, |catalog| catalog.reason_template(reason).map(|t| catalog.render(t, args)))
+    }
+
+    /// Error response with its message localized
+    ///
+    /// Placeholders are filled from `MessageArgs::from_error`; use
+    /// `RewardEngine::error_response` to fill config values such as the daily
+    /// limit too.
+    pub fn error_response(&self, err: &PokemonEngineError, locale: Option<&str>) -> ErrorResponse {
+        self.error_response_with(err, locale, &MessageArgs::from_error(err))
+    }
+
+    /// Error response with its message localized and filled from `args`
+    ///
+    /// Errors without a template, or whose template needs a value `args`
+    /// lacks, keep their English `Display` message.
+    pub fn error_response_with(&self, err: &PokemonEngineError, locale: Option<&str>, args: &MessageArgs) -> ErrorResponse {
+        let mut response = err.to_response();
+        let message = self.reason(locale, response.reason, args);
+        if let Some(message) = message.filter(|m| is_filled(m)) {
+            response.message = message;
+        }
+        response
+    }
+
+    /// First result of `render` along the locale fallback chain
+    fn lookup<F>(&self, locale: Option<&str>, render: F) -> Option<String>
+    where
+        F: Fn(&MessageCatalog) -> Option<String>,
+    {
+        let requested = locale.map(normalize);
+        let language = requested
+            .as_deref()
+            .and_then(|l| l.split_once('-'))
+            .map(|(language, _)| language.to_string());
+        [requested, language, Some(self.default_locale.clone()), Some(FALLBACK_LOCALE.to_string())]
+            .into_iter()
+            .flatten()
+            .filter_map(|locale| self.catalogs.get(&locale))
+            .find_map(render)
+    }
+}
+
+/// Check if a rendered message has no placeholder left unfilled
+pub(crate) fn is_filled(message: &str) -> bool {
+    !PLACEHOLDERS.iter().any(|p| message.contains(p))
+}
+
+/// Lowercase a locale tag and use `-` as its separator (`id_ID` to `id-id`)
+fn normalize(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_response_fills_args_from_the_error() {
+        let messages = Messages::bundled();
+        let err = PokemonEngineError::DailyLimitReached("battle".to_string());
+        let args = MessageArgs {
+            limit: Some(Amount::from_lamports(1_500_000_000)),
+            ..MessageArgs::from_error(&err)
+        };
+        let response = messages.error_response_with(&err, Some("id-ID"), &args);
+        assert_eq!(response.reason, ReasonCode::DailyLimitReached);
+        assert_eq!(response.message, "Batas harian Battle sebesar 1,5 POKE sudah tercapai");
+    }
+
+    #[test]
+    fn error_response_keeps_display_message_for_unfilled_placeholders() {
+        let messages = Messages::bundled();
+        let err = PokemonEngineError::DailyLimitReached("battle".to_string());
+        let response = messages.error_response(&err, Some("en"));
+        assert_eq!(response.message, err.to_string());
+        assert!(!response.message.contains('{'));
+    }
+
+    #[test]
+    fn error_response_localizes_plain_templates() {
+        let messages = Messages::bundled();
+        let err = PokemonEngineError::InvalidGameType("PokeMatch".to_string());
+        assert_eq!(MessageArgs::from_error(&err).game, Some(GameType::PokeMatch));
+        let response = messages.error_response(&err, Some("id"));
+        assert_eq!(response.message, "Permainan ini tidak memberikan hadiah di sini");
+    }
+
+    #[test]
+    fn bundled_catalogs_only_name_known_reasons() {
+        for (locale, text) in BUNDLED {
+            let catalog = MessageCatalog::from_toml(text).unwrap();
+            for key in catalog.reasons.keys() {
+                assert!(
+                    ReasonCode::ALL.iter().any(|code| code.as_str() == key),
+                    "{} has a message for unknown reason {}",
+                    locale,
+                    key
+                );
+            }
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use crate::amount::Amount;
+use crate::error::{PokemonEngineError, Result};
+use crate::reason::ReasonCode;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
@@ -33,11 +36,70 @@ pub struct Reward {
     pub id: Uuid,
     pub player_id: String,
     pub game: GameType,
-    pub amount: u64, // Amount in lamports (9 decimals for Solana)
+    pub amount: Amount, // Exact amount in base units
     pub timestamp: DateTime<Utc>,
     pub claimed: bool,
     pub game_data: serde_json::Value,
     pub transaction_signature: Option<String>, // Solana transaction signature
+    #[serde(default)]
+    pub config_version: Option<String>, // Fingerprint of the config that produced the reward
+}
+
+/// State of a payout in the outbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayoutStatus {
+    Pending, // Waiting to be sent, or sent and waiting to confirm
+    Sent,    // Confirmed on chain
+    Failed,  // Given up after too many attempts
+}
+
+/// Payout of claimed rewards waiting in the outbox
+///
+/// `id` is the idempotency key of the payout: it is created once when the
+/// rewards are claimed and every attempt to send it reuses it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutRequest {
+    pub id: Uuid,
+    pub player_id: String,
+    pub wallet: String, // Recipient wallet address
+    pub reward_ids: Vec<Uuid>,
+    pub amount: Amount, // Total of the rewards
+    pub status: PayoutStatus,
+    pub attempts: u32, // Send attempts so far
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub signature: Option<String>, // Latest submitted transaction
+    pub last_valid_block_height: Option<u64>, // Block height after which `signature` can no longer land
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub sent_at: Option<DateTime<Utc>>, // When `signature` was saved, just before it was sent
+}
+
+impl PayoutRequest {
+    /// Create a pending payout of the given rewards, due immediately
+    ///
+    /// Fails with `AmountOverflow` if the rewards don't sum to a `u64`.
+    pub fn new(player_id: &str, wallet: &str, rewards: &[Reward], now: DateTime<Utc>) -> Result<Self> {
+        let amount = Amount::checked_sum(rewards.iter().map(|r| r.amount)).ok_or_else(|| {
+            PokemonEngineError::AmountOverflow(format!("Payout of {} rewards to {}", rewards.len(), player_id))
+        })?;
+        Ok(Self {
+            id: Uuid::new_v4(),
+            player_id: player_id.to_string(),
+            wallet: wallet.to_string(),
+            reward_ids: rewards.iter().map(|r| r.id).collect(),
+            amount,
+            status: PayoutStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            created_at: now,
+            signature: None,
+            last_valid_block_height: None,
+            last_error: None,
+            sent_at: None,
+        })
+    }
 }
 
 /// Daily statistics for a player
@@ -45,10 +107,53 @@ pub struct Reward {
 pub struct DailyStats {
     pub player_id: String,
     pub date: chrono::NaiveDate,
-    pub flypoke: u64,
-    pub battle: u64,
-    pub login: u64,
-    pub total: u64,
+    pub flypoke: Amount,
+    pub battle: Amount,
+    pub login: Amount,
+    pub total: Amount,
+}
+
+impl DailyStats {
+    /// Games whose earnings are counted in daily stats
+    pub const TRACKED_GAMES: [GameType; 3] = [GameType::FlyPoke, GameType::Battle, GameType::Login];
+
+    /// Amount earned from `game` on this day
+    ///
+    /// `None` for games not counted in daily stats (PokeMatch, Pokedex and
+    /// the welcome bonus), whose earnings are unknown here.
+    pub fn earned(&self, game: GameType) -> Option<Amount> {
+        match game {
+            GameType::FlyPoke => Some(self.flypoke),
+            GameType::Battle => Some(self.battle),
+            GameType::Login => Some(self.login),
+            GameType::PokeMatch | GameType::Pokedex | GameType::Welcome => None,
+        }
+    }
+}
+
+/// What a player can still earn from one game on a day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameAllowance {
+    pub game: GameType,
+    pub limit: Option<Amount>, // `None` for games without a daily limit
+    pub earned: Amount,
+    pub remaining: Option<Amount>, // Within both limits; `None` if unlimited or not counted in daily stats
+}
+
+/// What a player can still earn on a day, from `RewardEngine::get_daily_allowance`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyAllowance {
+    pub player_id: String,
+    pub date: chrono::NaiveDate,
+    pub games: Vec<GameAllowance>,
+    pub total_earned: Amount,
+    #[serde(default)]
+    pub global_limit: Option<Amount>, // `rewards.daily_total_limit`, if set
+    #[serde(default)]
+    pub global_remaining: Option<Amount>,
+    pub issuance_paused: bool, // Nothing can be earned while the circuit breaker pauses issuance
+    pub resets_at: DateTime<Utc>, // Next UTC midnight after `date`
+    pub resets_in_secs: u64, // Zero once `resets_at` has passed
 }
 
 /// Login streak information
@@ -65,6 +170,8 @@ pub struct GameEvent {
     pub player_id: String,
     pub game: GameType,
     pub event_data: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>, // Language of the response message, e.g. "id"
 }
 
 /// Reward response after processing
@@ -74,6 +181,21 @@ pub struct RewardResponse {
     pub success: bool,
     pub message: String,
     pub daily_limit_reached: bool,
+    #[serde(default)]
+    pub reason: Option<ReasonCode>, // Set when no reward was issued
+    #[serde(default)]
+    pub retryable: bool,
+    #[serde(default)]
+    pub remaining_allowance: Option<Amount>, // Left today in the event's game: the smaller of its own and the global daily limit, whichever apply
+}
+
+/// Error returned to clients, from `PokemonEngineError::to_response`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub reason: ReasonCode,
+    pub retryable: bool,
+    pub field: Option<String>, // Event data field at fault, when known
+    pub message: String,
 }
 
 /// FlyPoke game event data
@@ -107,3 +229,27 @@ pub struct PokedexEventData {
     pub collection_size: Option<u32>,
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_stats_earned_only_covers_tracked_games() {
+        let stats = DailyStats {
+            player_id: "player1".to_string(),
+            date: chrono::NaiveDate::from_ymd_opt(2026, 10, 18).unwrap(),
+            flypoke: Amount::from_lamports(1),
+            battle: Amount::from_lamports(2),
+            login: Amount::from_lamports(3),
+            total: Amount::from_lamports(6),
+        };
+        assert_eq!(stats.earned(GameType::FlyPoke), Some(Amount::from_lamports(1)));
+        assert_eq!(stats.earned(GameType::Battle), Some(Amount::from_lamports(2)));
+        assert_eq!(stats.earned(GameType::Login), Some(Amount::from_lamports(3)));
+        for game in [GameType::PokeMatch, GameType::Pokedex, GameType::Welcome] {
+            assert_eq!(stats.earned(game), None, "{}", game);
+            assert!(!DailyStats::TRACKED_GAMES.contains(&game));
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Vault balance monitoring
+//!
+//! Periodically compares the reward vault balance against the total of all
+//! unpaid rewards (pending, and claimed or queued for payout but not yet
+//! paid) and trips the engine's circuit breaker when the vault can no longer
+//! cover them.
+
+use crate::config::VaultMonitorConfig;
+use crate::engine::{CircuitBreaker, RewardEngine, TripCause};
+use crate::error::Result;
+use crate::solana::SolanaClient;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Alert level derived from vault coverage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VaultAlertLevel {
+    Healthy,
+    Warning,
+    Critical,
+    Tripped,
+}
+
+/// Result of a single vault balance check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultStatus {
+    pub vault_balance: u64,
+    pub unpaid_amount: u64, // Pending, claimed and queued rewards not yet paid
+    pub coverage_ratio: Option<f64>, // None when nothing is unpaid
+    pub level: VaultAlertLevel,
+    pub checked_at: DateTime<Utc>,
+}
+
+type AlertHandler = Box<dyn Fn(&VaultStatus) + Send + Sync>;
+
+/// Monitor that keeps the circuit breaker in sync with the vault balance
+///
+/// Thresholds are read from the `vault_monitor` section of the engine's
+/// active config on every check, so reloads and scheduled versions apply to
+/// a running monitor.
+pub struct VaultMonitor {
+    solana: Arc<SolanaClient>,
+    alert_handler: Option<AlertHandler>,
+    consecutive_failures: AtomicU32,
+}
+
+impl VaultMonitor {
+    /// Create a new vault monitor
+    pub fn new(solana: Arc<SolanaClient>) -> Self {
+        Self {
+            solana,
+            alert_handler: None,
+            consecutive_failures: AtomicU32::new(0),
+        }
+    }
+
+    /// Set a handler called for every check that is not healthy
+    ///
+    /// Such checks are always logged; the handler comes on top, e.g. to page
+    /// someone.
+    pub fn with_alert_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&VaultStatus) + Send + Sync + 'static,
+    {
+        self.alert_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Classify a vault balance against the unpaid amount under `config`
+    pub fn evaluate(config: &VaultMonitorConfig, vault_balance: u64, unpaid_amount: u64) -> VaultAlertLevel {
+        alert_level(config, vault_balance, unpaid_amount)
+    }
+
+    /// Checks that have failed in a row since the last successful one
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures.load(Ordering::Relaxed)
+    }
+
+    /// Run a single check and update the engine's circuit breaker
+    ///
+    /// Failed checks are logged. After `max_consecutive_failures` of them in a
+    /// row the breaker is tripped, since the vault can no longer be vouched
+    /// for; the next successful check resets that trip.
+    pub async fn check(&self, engine: &RewardEngine) -> Result<VaultStatus> {
+        let config = engine.config().vault_monitor;
+        let result = self.check_vault(&config, engine).await;
+        match &result {
+            Ok(_) => self.consecutive_failures.store(0, Ordering::Relaxed),
+            Err(err) => {
+                let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed).saturating_add(1);
+                log::warn!("Vault check failed ({} in a row): {}", failures, err);
+                if failures_trip(&config, failures) {
+                    engine.circuit_breaker().trip(
+                        TripCause::VaultUnreachable,
+                        format!("{} vault checks failed in a row, last: {}", failures, err),
+                    );
+                }
+            }
+        }
+        result
+    }
+
+    async fn check_vault(&self, config: &VaultMonitorConfig, engine: &RewardEngine) -> Result<VaultStatus> {
+        let vault_balance = self.solana.get_vault_balance().await?;
+        let unpaid_amount = engine.get_total_unpaid_amount().await?;
+        let status = VaultStatus {
+            vault_balance,
+            unpaid_amount,
+            coverage_ratio: coverage_ratio(vault_balance, unpaid_amount),
+            level: alert_level(config, vault_balance, unpaid_amount),
+            checked_at: Utc::now(),
+        };
+        update_breaker(config, &engine.circuit_breaker(), &status);
+
+        if status.level != VaultAlertLevel::Healthy {
+            log_status(&status);
+            if let Some(handler) = &self.alert_handler {
+                handler(&status);
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// Spawn a background task that checks the vault on the configured interval
+    ///
+    /// The interval is re-read from the active config after every check.
+    /// Failed checks are retried on the next tick; see `check` for how repeated
+    /// failures trip the breaker.
+    pub fn spawn(self, engine: Arc<RewardEngine>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let _ = self.check(&engine).await;
+                let period = engine.config().vault_monitor.check_interval_secs.max(1);
+                tokio::time::sleep(Duration::from_secs(period)).await;
+            }
+        })
+    }
+}
+
+/// Classify a vault balance against the unpaid amount under `config`
+fn alert_level(config: &VaultMonitorConfig, vault_balance: u64, unpaid_amount: u64) -> VaultAlertLevel {
+    match coverage_ratio(vault_balance, unpaid_amount) {
+        None => VaultAlertLevel::Healthy,
+        Some(ratio) if ratio < config.trip_ratio => VaultAlertLevel::Tripped,
+        Some(ratio) if ratio < config.critical_ratio => VaultAlertLevel::Critical,
+        Some(ratio) if ratio < config.warning_ratio => VaultAlertLevel::Warning,
+        Some(_) => VaultAlertLevel::Healthy,
+    }
+}
+
+/// Log a check that is not healthy: warnings as such, anything worse as an error
+fn log_status(status: &VaultStatus) {
+    let message = format!(
+        "Vault {:?}: balance {} covers unpaid rewards {} at ratio {:.2}",
+        status.level,
+        status.vault_balance,
+        status.unpaid_amount,
+        status.coverage_ratio.unwrap_or(f64::INFINITY)
+    );
+    match status.level {
+        VaultAlertLevel::Healthy => {}
+        VaultAlertLevel::Warning => log::warn!("{}", message),
+        VaultAlertLevel::Critical | VaultAlertLevel::Tripped => log::error!("{}", message),
+    }
+}
+
+/// Check if `failures` checks failed in a row trip the breaker
+fn failures_trip(config: &VaultMonitorConfig, failures: u32) -> bool {
+    config.max_consecutive_failures > 0 && failures >= config.max_consecutive_failures
+}
+
+/// Trip or reset the breaker after a successful check
+fn update_breaker(config: &VaultMonitorConfig, breaker: &CircuitBreaker, status: &VaultStatus) {
+    if breaker.trip_info().is_some_and(|trip| trip.cause == TripCause::VaultUnreachable) {
+        // The vault answered again; coverage decides from here
+        breaker.reset();
+    }
+    if status.level == VaultAlertLevel::Tripped {
+        breaker.trip(
+            TripCause::LowVaultFunds,
+            format!(
+                "Vault balance {} cannot cover unpaid rewards {}",
+                status.vault_balance, status.unpaid_amount
+            ),
+        );
+    } else if let Some(trip) = breaker.trip_info() {
+        // Only undo our own trips, and only once coverage has recovered
+        let recovered = status.coverage_ratio.is_none_or(|r| r >= config.resume_ratio);
+        if trip.cause == TripCause::LowVaultFunds && recovered {
+            breaker.reset();
+        }
+    }
+}
+
+/// Vault balance divided by unpaid amount, or `None` if nothing is unpaid
+fn coverage_ratio(vault_balance: u64, unpaid_amount: u64) -> Option<f64> {
+    if unpaid_amount == 0 {
+        None
+    } else {
+        Some(vault_balance as f64 / unpaid_amount as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(config: &VaultMonitorConfig, vault_balance: u64, unpaid_amount: u64) -> VaultStatus {
+        VaultStatus {
+            vault_balance,
+            unpaid_amount,
+            coverage_ratio: coverage_ratio(vault_balance, unpaid_amount),
+            level: alert_level(config, vault_balance, unpaid_amount),
+            checked_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn coverage_thresholds_set_the_alert_level() {
+        let config = VaultMonitorConfig::default();
+
+        assert_eq!(alert_level(&config, 0, 0), VaultAlertLevel::Healthy);
+        assert_eq!(alert_level(&config, 200, 100), VaultAlertLevel::Healthy);
+        assert_eq!(alert_level(&config, 199, 100), VaultAlertLevel::Warning);
+        assert_eq!(alert_level(&config, 125, 100), VaultAlertLevel::Warning);
+        assert_eq!(alert_level(&config, 124, 100), VaultAlertLevel::Critical);
+        assert_eq!(alert_level(&config, 100, 100), VaultAlertLevel::Critical);
+        assert_eq!(alert_level(&config, 99, 100), VaultAlertLevel::Tripped);
+        assert_eq!(alert_level(&config, 0, 1), VaultAlertLevel::Tripped);
+    }
+
+    #[test]
+    fn breaker_trips_after_consecutive_failures() {
+        let config = VaultMonitorConfig {
+            max_consecutive_failures: 3,
+            ..VaultMonitorConfig::default()
+        };
+        assert!(!failures_trip(&config, 2));
+        assert!(failures_trip(&config, 3));
+        assert!(failures_trip(&config, 4));
+
+        let never = VaultMonitorConfig {
+            max_consecutive_failures: 0,
+            ..VaultMonitorConfig::default()
+        };
+        assert!(!failures_trip(&never, u32::MAX));
+    }
+
+    #[test]
+    fn low_funds_trip_resets_once_coverage_recovers() {
+        let config = VaultMonitorConfig::default();
+        let breaker = CircuitBreaker::new();
+
+        update_breaker(&config, &breaker, &status(&config, 50, 100));
+        assert_eq!(breaker.trip_info().unwrap().cause, TripCause::LowVaultFunds);
+        assert!(breaker.issuance_paused(true));
+        assert!(!breaker.issuance_paused(false));
+
+        // Covered again, but still below `resume_ratio`
+        update_breaker(&config, &breaker, &status(&config, 120, 100));
+        assert!(breaker.is_open());
+
+        update_breaker(&config, &breaker, &status(&config, 150, 100));
+        assert!(!breaker.is_open());
+
+        update_breaker(&config, &breaker, &status(&config, 50, 100));
+        update_breaker(&config, &breaker, &status(&config, 0, 0));
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn unreachable_trip_resets_on_the_next_check() {
+        let config = VaultMonitorConfig::default();
+        let breaker = CircuitBreaker::new();
+
+        breaker.trip(TripCause::VaultUnreachable, "checks failed");
+        update_breaker(&config, &breaker, &status(&config, 120, 100));
+        assert!(!breaker.is_open());
+
+        // A check that finds the vault short replaces the trip
+        breaker.trip(TripCause::VaultUnreachable, "checks failed");
+        update_breaker(&config, &breaker, &status(&config, 50, 100));
+        assert_eq!(breaker.trip_info().unwrap().cause, TripCause::LowVaultFunds);
+    }
+
+    #[test]
+    fn manual_trips_are_left_alone() {
+        let config = VaultMonitorConfig::default();
+        let breaker = CircuitBreaker::new();
+
+        breaker.trip(TripCause::Manual, "maintenance");
+        update_breaker(&config, &breaker, &status(&config, 500, 100));
+        assert_eq!(breaker.trip_info().unwrap().cause, TripCause::Manual);
+    }
+}
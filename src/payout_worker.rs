@@ -0,0 +1,241 @@
+//! Outbox-driven payout worker
+//!
+//! `RewardEngine::request_payout` claims a player's rewards and leaves a
+//! `PayoutRequest` in the storage's outbox in the same step. The worker drains
+//! due payouts through `SolanaClient` in the background, a few at a time, and
+//! retries failures with exponential backoff.
+//!
+//! Delivery is at least once. Each transaction's signature is saved to the
+//! outbox before it is sent, so after a crash or timeout the worker checks
+//! what became of that transaction and only sends a new one once the old one
+//! has failed or expired. A payout is never paid twice.
+//!
+//! Every change to a payout goes through `RewardEngine::update_payout`, so an
+//! attached event log can restore the outbox exactly, signatures included.
+
+use crate::config::PayoutWorkerConfig;
+use crate::engine::RewardEngine;
+use crate::error::Result;
+use crate::models::{PayoutRequest, PayoutStatus, Reward};
+use crate::solana::{PayoutTransactionStatus, SolanaClient};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::Keypair;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Counts from one pass over the outbox
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayoutRunSummary {
+    pub sent: u64,        // Confirmed and recorded on the rewards
+    pub rescheduled: u64, // Waiting to confirm or to be retried
+    pub failed: u64,      // Out of attempts
+    pub errors: u64,      // Could not be processed, left as they were
+}
+
+enum Outcome {
+    Sent,
+    Rescheduled,
+    Failed,
+}
+
+/// Background worker sending the payouts queued in the outbox
+#[derive(Clone)]
+pub struct PayoutWorker {
+    config: PayoutWorkerConfig,
+    solana: Arc<SolanaClient>,
+    vault_keypair: Arc<Keypair>,
+}
+
+impl PayoutWorker {
+    /// Create a worker paying out of the vault owned by `vault_keypair`
+    pub fn new(config: PayoutWorkerConfig, solana: Arc<SolanaClient>, vault_keypair: Arc<Keypair>) -> Self {
+        Self {
+            config,
+            solana,
+            vault_keypair,
+        }
+    }
+
+    /// Process one batch of due payouts
+    ///
+    /// Does nothing while the engine's circuit breaker pauses payouts. Run a
+    /// single worker per outbox: payouts are not locked while in progress.
+    pub async fn run_once(&self, engine: &Arc<RewardEngine>) -> Result<PayoutRunSummary> {
+        let mut summary = PayoutRunSummary::default();
+        if engine.circuit_breaker().payouts_paused() {
+            return Ok(summary);
+        }
+
+        let due = engine.storage().due_payouts(Utc::now(), self.config.batch_size).await?;
+        let permits = Arc::new(Semaphore::new(self.config.max_concurrency.max(1)));
+        let mut tasks = JoinSet::new();
+        for payout in due {
+            let permit = Arc::clone(&permits).acquire_owned().await.expect("semaphore is never closed");
+            let worker = self.clone();
+            let engine = Arc::clone(engine);
+            tasks.spawn(async move {
+                let outcome = worker.process(&engine, payout).await;
+                drop(permit);
+                outcome
+            });
+        }
+
+        while let Some(outcome) = tasks.join_next().await {
+            match outcome {
+                Ok(Ok(Outcome::Sent)) => summary.sent += 1,
+                Ok(Ok(Outcome::Rescheduled)) => summary.rescheduled += 1,
+                Ok(Ok(Outcome::Failed)) => summary.failed += 1,
+                Ok(Err(_)) | Err(_) => summary.errors += 1,
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Spawn a background task that drains the outbox on the configured interval
+    ///
+    /// Failed passes are retried on the next tick.
+    pub fn spawn(self, engine: Arc<RewardEngine>) -> tokio::task::JoinHandle<()> {
+        let period = Duration::from_secs(self.config.poll_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let _ = self.run_once(&engine).await;
+            }
+        })
+    }
+
+    /// Move one payout forward: confirm its last transaction or send a new one
+    async fn process(&self, engine: &RewardEngine, mut payout: PayoutRequest) -> Result<Outcome> {
+        if let (Some(signature), Some(height)) = (payout.signature.clone(), payout.last_valid_block_height) {
+            match self.solana.payout_status(&signature, height).await {
+                Ok(PayoutTransactionStatus::Confirmed) => return self.complete(engine, payout, signature).await,
+                Ok(PayoutTransactionStatus::Pending) => {
+                    payout.next_attempt_at = Utc::now() + self.poll_interval();
+                    engine.update_payout(&payout).await?;
+                    return Ok(Outcome::Rescheduled);
+                }
+                Ok(PayoutTransactionStatus::Failed(err)) => payout.last_error = Some(err),
+                Ok(PayoutTransactionStatus::Expired) => {
+                    payout.last_error = Some(format!("Transaction {} expired", signature));
+                }
+                Err(err) => {
+                    // Unknown outcome: check again later, never resend blindly
+                    payout.last_error = Some(err.to_string());
+                    payout.next_attempt_at = Utc::now() + self.backoff(payout.attempts);
+                    engine.update_payout(&payout).await?;
+                    return Ok(Outcome::Rescheduled);
+                }
+            }
+            // The previous transaction can no longer land
+            payout.signature = None;
+            payout.last_valid_block_height = None;
+            payout.sent_at = None;
+        }
+
+        if payout.attempts >= self.config.max_attempts {
+            payout.status = PayoutStatus::Failed;
+            engine.update_payout(&payout).await?;
+            return Ok(Outcome::Failed);
+        }
+
+        payout.attempts += 1;
+        payout.next_attempt_at = Utc::now() + self.backoff(payout.attempts);
+        let prepared = match self
+            .solana
+            .prepare_payout(&payout.reward_ids, payout.amount.lamports(), &payout.wallet, &self.vault_keypair)
+            .await
+        {
+            Ok(prepared) => prepared,
+            Err(err) => {
+                payout.last_error = Some(err.to_string());
+                engine.update_payout(&payout).await?;
+                return Ok(Outcome::Rescheduled);
+            }
+        };
+
+        // Saved before sending, so a crash mid-send can't lead to a second transfer
+        payout.signature = Some(prepared.signature.clone());
+        payout.last_valid_block_height = Some(prepared.last_valid_block_height);
+        payout.sent_at = Some(Utc::now());
+        engine.update_payout(&payout).await?;
+
+        match self.solana.submit_payout(&prepared).await {
+            Ok(signature) => self.complete(engine, payout, signature).await,
+            Err(err) => {
+                payout.last_error = Some(err.to_string());
+                engine.update_payout(&payout).await?;
+                Ok(Outcome::Rescheduled)
+            }
+        }
+    }
+
+    /// Record a confirmed payout on its rewards and close it in the outbox
+    async fn complete(&self, engine: &RewardEngine, mut payout: PayoutRequest, signature: String) -> Result<Outcome> {
+        let fee = self.solana.transaction_fee(&signature).await?;
+        let rewards = engine.storage().get_rewards(&payout.player_id).await?;
+        let batch: Vec<&Reward> = rewards.iter().filter(|r| payout.reward_ids.contains(&r.id)).collect();
+        let fees = fee_shares(fee, batch.iter().map(|r| r.amount.lamports()));
+        for (reward, fee) in batch.into_iter().zip(fees) {
+            // Rewards recorded by an earlier, interrupted completion are skipped
+            if reward.transaction_signature.as_deref() != Some(signature.as_str()) {
+                engine.record_payout_with_fee(&reward.id, &signature, fee).await?;
+            }
+        }
+
+        payout.status = PayoutStatus::Sent;
+        payout.signature = Some(signature);
+        payout.last_error = None;
+        engine.update_payout(&payout).await?;
+        Ok(Outcome::Sent)
+    }
+
+    fn poll_interval(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.config.poll_interval_secs.max(1) as i64)
+    }
+
+    /// Delay before retrying after `attempts` attempts
+    fn backoff(&self, attempts: u32) -> chrono::Duration {
+        let doublings = attempts.saturating_sub(1).min(32);
+        let secs = self
+            .config
+            .base_backoff_secs
+            .saturating_mul(1u64 << doublings)
+            .min(self.config.max_backoff_secs);
+        chrono::Duration::seconds(secs.min(i64::MAX as u64) as i64)
+    }
+}
+
+/// Split a transaction fee over the rewards it paid, in order
+///
+/// Each reward bears as much of the fee as it can, up to its own amount; a
+/// fee larger than the whole batch is capped at the batch total.
+fn fee_shares(fee: u64, amounts: impl IntoIterator<Item = u64>) -> Vec<u64> {
+    let mut remaining = fee;
+    amounts
+        .into_iter()
+        .map(|amount| {
+            let share = remaining.min(amount);
+            remaining -= share;
+            share
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fees_are_withheld_in_reward_order() {
+        assert_eq!(fee_shares(5000, [10_000, 20_000]), vec![5000, 0]);
+        assert_eq!(fee_shares(5000, [3000, 20_000, 10]), vec![3000, 2000, 0]);
+        assert_eq!(fee_shares(0, [10, 20]), vec![0, 0]);
+        // Never more than the rewards paid
+        assert_eq!(fee_shares(5000, [1000, 2000]), vec![1000, 2000]);
+        assert!(fee_shares(5000, []).is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+//! Stable reason codes
+//!
+//! Every rejected game event and every `PokemonEngineError` maps to a
+//! `ReasonCode`. Codes serialize as fixed snake_case strings that won't change
+//! when message wording does, so clients can branch on them and localize.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Machine-readable reason for a rejection or error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasonCode {
+    /// Game's daily reward limit reached; resets the next UTC day
+    DailyLimitReached,
+    /// Player's daily total across games reached; resets the next UTC day
+    DailyTotalLimitReached,
+    /// Login reward already paid today
+    AlreadyLoggedInToday,
+    /// Welcome bonus already paid to this player
+    WelcomeBonusAlreadyClaimed,
+    /// Same reward claimed twice, or a record that already exists
+    Duplicate,
+    /// Reward, payout or player doesn't exist
+    NotFound,
+    /// Reward issuance paused by the circuit breaker
+    IssuancePaused,
+    /// Payouts paused by the circuit breaker
+    PayoutsPaused,
+    /// Event data missing a field or of the wrong shape
+    InvalidPayload,
+    /// Event field outside the range a reward can be paid for
+    InvalidField,
+    /// Game type not accepted here
+    InvalidGameType,
+    InvalidPlayerId,
+    InvalidWallet,
+    InvalidQuery,
+    InvalidAmount,
+    /// Computed amount doesn't fit in a `u64`
+    AmountOverflow,
+    /// Config failed to load or validate
+    InvalidConfig,
+    InvalidSnapshot,
+    /// Storage backend failed to respond
+    StorageUnavailable,
+    /// Solana RPC failed to respond
+    SolanaUnavailable,
+    /// Transaction landed on chain but failed
+    TransactionFailed,
+    /// Ledger rejected a movement
+    LedgerError,
+    Internal,
+}
+
+impl ReasonCode {
+    /// Every code, in declaration order
+    pub const ALL: [ReasonCode; 23] = [
+        ReasonCode::DailyLimitReached,
+        ReasonCode::DailyTotalLimitReached,
+        ReasonCode::AlreadyLoggedInToday,
+        ReasonCode::WelcomeBonusAlreadyClaimed,
+        ReasonCode::Duplicate,
+        ReasonCode::NotFound,
+        ReasonCode::IssuancePaused,
+        ReasonCode::PayoutsPaused,
+        ReasonCode::InvalidPayload,
+        ReasonCode::InvalidField,
+        ReasonCode::InvalidGameType,
+        ReasonCode::InvalidPlayerId,
+        ReasonCode::InvalidWallet,
+        ReasonCode::InvalidQuery,
+        ReasonCode::InvalidAmount,
+        ReasonCode::AmountOverflow,
+        ReasonCode::InvalidConfig,
+        ReasonCode::InvalidSnapshot,
+        ReasonCode::StorageUnavailable,
+        ReasonCode::SolanaUnavailable,
+        ReasonCode::TransactionFailed,
+        ReasonCode::LedgerError,
+        ReasonCode::Internal,
+    ];
+
+    /// Whether the same request may succeed if sent again unchanged
+    ///
+    /// True for temporary conditions on the engine's side: a pause or a
+    /// failing backend. Business rules such as the daily limit are not
+    /// retryable even though they reset later.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            ReasonCode::IssuancePaused
+                | ReasonCode::PayoutsPaused
+                | ReasonCode::StorageUnavailable
+                | ReasonCode::SolanaUnavailable
+        )
+    }
+
+    /// Code as it appears in JSON
+    ///
+    /// Must match the serde name; the tests check every code in `ALL`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReasonCode::DailyLimitReached => "daily_limit_reached",
+            ReasonCode::DailyTotalLimitReached => "daily_total_limit_reached",
+            ReasonCode::AlreadyLoggedInToday => "already_logged_in_today",
+            ReasonCode::WelcomeBonusAlreadyClaimed => "welcome_bonus_already_claimed",
+            ReasonCode::Duplicate => "duplicate",
+            ReasonCode::NotFound => "not_found",
+            ReasonCode::IssuancePaused => "issuance_paused",
+            ReasonCode::PayoutsPaused => "payouts_paused",
+            ReasonCode::InvalidPayload => "invalid_payload",
+            ReasonCode::InvalidField => "invalid_field",
+            ReasonCode::InvalidGameType => "invalid_game_type",
+            ReasonCode::InvalidPlayerId => "invalid_player_id",
+            ReasonCode::InvalidWallet => "invalid_wallet",
+            ReasonCode::InvalidQuery => "invalid_query",
+            ReasonCode::InvalidAmount => "invalid_amount",
+            ReasonCode::AmountOverflow => "amount_overflow",
+            ReasonCode::InvalidConfig => "invalid_config",
+            ReasonCode::InvalidSnapshot => "invalid_snapshot",
+            ReasonCode::StorageUnavailable => "storage_unavailable",
+            ReasonCode::SolanaUnavailable => "solana_unavailable",
+            ReasonCode::TransactionFailed => "transaction_failed",
+            ReasonCode::LedgerError => "ledger_error",
+            ReasonCode::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for ReasonCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn as_str_matches_the_serde_name() {
+        for code in ReasonCode::ALL {
+            let json = serde_json::to_value(code).unwrap();
+            assert_eq!(json, serde_json::Value::from(code.as_str()), "{:?}", code);
+            assert_eq!(serde_json::from_value::<ReasonCode>(json).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn all_lists_every_code_once() {
+        let distinct: HashSet<ReasonCode> = ReasonCode::ALL.into_iter().collect();
+        assert_eq!(distinct.len(), ReasonCode::ALL.len());
+
+        // Stops compiling when a code is added, as a reminder to list it in `ALL`
+        let position = |code: ReasonCode| match code {
+            ReasonCode::DailyLimitReached => 0,
+            ReasonCode::DailyTotalLimitReached => 1,
+            ReasonCode::AlreadyLoggedInToday => 2,
+            ReasonCode::WelcomeBonusAlreadyClaimed => 3,
+            ReasonCode::Duplicate => 4,
+            ReasonCode::NotFound => 5,
+            ReasonCode::IssuancePaused => 6,
+            ReasonCode::PayoutsPaused => 7,
+            ReasonCode::InvalidPayload => 8,
+            ReasonCode::InvalidField => 9,
+            ReasonCode::InvalidGameType => 10,
+            ReasonCode::InvalidPlayerId => 11,
+            ReasonCode::InvalidWallet => 12,
+            ReasonCode::InvalidQuery => 13,
+            ReasonCode::InvalidAmount => 14,
+            ReasonCode::AmountOverflow => 15,
+            ReasonCode::InvalidConfig => 16,
+            ReasonCode::InvalidSnapshot => 17,
+            ReasonCode::StorageUnavailable => 18,
+            ReasonCode::SolanaUnavailable => 19,
+            ReasonCode::TransactionFailed => 20,
+            ReasonCode::LedgerError => 21,
+            ReasonCode::Internal => 22,
+        };
+        for (index, code) in ReasonCode::ALL.into_iter().enumerate() {
+            assert_eq!(position(code), index);
+        }
+    }
+}
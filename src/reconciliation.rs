@@ -0,0 +1,399 @@
+//! On-chain vs. ledger reconciliation
+//!
+//! Compares the payouts recorded in `Storage` against the transfers that
+//! actually left the reward vault, so discrepancies can be investigated.
+
+use crate::engine::RewardEngine;
+use crate::error::Result;
+use crate::models::{DailyStats, LoginStreak, PayoutRequest, Reward};
+use crate::solana::{PayoutMemo, SolanaClient, VaultTransfer};
+use crate::storage::StorageVisitor;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Reward with a recorded signature that has no matching vault transfer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingTransfer {
+    pub reward_id: Uuid,
+    pub player_id: String,
+    pub signature: String,
+    pub amount: u64,
+}
+
+/// Vault transfer whose amount differs from the rewards it pays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmountMismatch {
+    pub signature: String,
+    pub reward_ids: Vec<Uuid>,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+/// Reward linked to more than one vault transfer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoublePayment {
+    pub reward_id: Uuid,
+    pub player_id: String,
+    pub amount: u64,
+    pub signatures: Vec<String>,
+}
+
+/// Structured result of a reconciliation run
+///
+/// With truncated history, rewards whose payout was sent before the oldest
+/// scanned transfer may have been paid outside the window. Those without a
+/// matching transfer are listed in `unverified_transfers` rather than
+/// `missing_transfers`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    pub generated_at: DateTime<Utc>,
+    pub transfers_scanned: usize,
+    pub rewards_checked: usize,
+    pub history_truncated: bool, // Older transfers were not scanned
+    pub orphan_transfers: Vec<VaultTransfer>,
+    pub missing_transfers: Vec<MissingTransfer>,
+    #[serde(default)]
+    pub unverified_transfers: Vec<MissingTransfer>, // Possibly paid before the scanned history
+    pub amount_mismatches: Vec<AmountMismatch>,
+    pub double_payments: Vec<DoublePayment>,
+}
+
+impl ReconciliationReport {
+    /// Check if no discrepancies were found
+    ///
+    /// `unverified_transfers` don't count; scan more history to settle them.
+    pub fn is_clean(&self) -> bool {
+        self.orphan_transfers.is_empty()
+            && self.missing_transfers.is_empty()
+            && self.amount_mismatches.is_empty()
+            && self.double_payments.is_empty()
+    }
+}
+
+/// Match paid rewards against vault transfers
+///
+/// `rewards` are the paid rewards plus any claimed rewards not yet recorded
+/// as paid. A transfer pays every reward whose `transaction_signature` equals
+/// its signature, plus any reward its `PayoutMemo` references. Batch-hash
+/// memos are resolved by hashing the `reward_ids` of each payout in
+/// `payouts`, the batches exactly as they were sent. A payout's `sent_at`
+/// (or `created_at`) is the earliest its rewards can have been paid; rewards
+/// paid outside the outbox are judged by their issue time.
+pub fn reconcile(
+    rewards: &[Reward],
+    payouts: &[PayoutRequest],
+    transfers: &[VaultTransfer],
+    history_truncated: bool,
+) -> ReconciliationReport {
+    let rewards_by_id: HashMap<Uuid, &Reward> = rewards.iter().map(|r| (r.id, r)).collect();
+    let transfer_signatures: BTreeSet<&str> =
+        transfers.iter().map(|t| t.signature.as_str()).collect();
+
+    let mut rewards_by_signature: HashMap<&str, Vec<&Reward>> = HashMap::new();
+    for reward in rewards {
+        if let Some(signature) = &reward.transaction_signature {
+            rewards_by_signature.entry(signature.as_str()).or_default().push(reward);
+        }
+    }
+
+    let rewards_by_batch_hash: HashMap<String, &[Uuid]> = payouts
+        .iter()
+        .map(|p| (PayoutMemo::batch_hash(&p.reward_ids), p.reward_ids.as_slice()))
+        .collect();
+
+    let mut report = ReconciliationReport {
+        generated_at: Utc::now(),
+        transfers_scanned: transfers.len(),
+        rewards_checked: rewards.len(),
+        history_truncated,
+        orphan_transfers: Vec::new(),
+        missing_transfers: Vec::new(),
+        unverified_transfers: Vec::new(),
+        amount_mismatches: Vec::new(),
+        double_payments: Vec::new(),
+    };
+
+    // Earliest each recorded signature can have landed: when the outbox sent it
+    let sent_at: HashMap<&str, DateTime<Utc>> = payouts
+        .iter()
+        .filter_map(|p| Some((p.signature.as_deref()?, p.sent_at.unwrap_or(p.created_at))))
+        .collect();
+
+    // Rewards paid before this were possibly paid before the scanned history
+    let window_start = transfers.iter().filter_map(|t| t.block_time).min();
+    let outside_window = |reward: &Reward, signature: &str| {
+        let paid_from = sent_at.get(signature).map_or(reward.timestamp, |&sent| sent.max(reward.timestamp));
+        history_truncated && window_start.is_none_or(|start| paid_from < start)
+    };
+
+    let mut payments: HashMap<Uuid, BTreeSet<String>> = HashMap::new();
+    for transfer in transfers {
+        let mut linked: BTreeSet<Uuid> = rewards_by_signature
+            .get(transfer.signature.as_str())
+            .map(|rs| rs.iter().map(|r| r.id).collect())
+            .unwrap_or_default();
+        match &transfer.memo {
+            Some(PayoutMemo::Rewards(ids)) => {
+                linked.extend(ids.iter().filter(|id| rewards_by_id.contains_key(id)));
+            }
+            Some(PayoutMemo::BatchHash(hash)) => {
+                if let Some(ids) = rewards_by_batch_hash.get(hash) {
+                    linked.extend(ids.iter().filter(|id| rewards_by_id.contains_key(id)));
+                }
+            }
+            None => {}
+        }
+
+        if linked.is_empty() {
+            report.orphan_transfers.push(transfer.clone());
+            continue;
+        }
+
+        let expected: u64 = linked
+            .iter()
+            .map(|id| rewards_by_id[id].amount.lamports())
+            .fold(0u64, |acc, amount| acc.saturating_add(amount));
+        if expected != transfer.amount {
+            report.amount_mismatches.push(AmountMismatch {
+                signature: transfer.signature.clone(),
+                reward_ids: linked.iter().copied().collect(),
+                expected,
+                actual: transfer.amount,
+            });
+        }
+
+        for id in linked {
+            payments.entry(id).or_default().insert(transfer.signature.clone());
+        }
+    }
+
+    for reward in rewards {
+        if let Some(signature) = &reward.transaction_signature {
+            if !transfer_signatures.contains(signature.as_str()) {
+                let missing = MissingTransfer {
+                    reward_id: reward.id,
+                    player_id: reward.player_id.clone(),
+                    signature: signature.clone(),
+                    amount: reward.amount.lamports(),
+                };
+                if outside_window(reward, signature) {
+                    report.unverified_transfers.push(missing);
+                } else {
+                    report.missing_transfers.push(missing);
+                }
+            }
+        }
+
+        if let Some(signatures) = payments.get(&reward.id) {
+            if signatures.len() > 1 {
+                report.double_payments.push(DoublePayment {
+                    reward_id: reward.id,
+                    player_id: reward.player_id.clone(),
+                    amount: reward.amount.lamports(),
+                    signatures: signatures.iter().cloned().collect(),
+                });
+            }
+        }
+    }
+
+    report
+}
+
+/// Reconciliation job reading vault history through a `SolanaClient`
+pub struct Reconciler {
+    solana: Arc<SolanaClient>,
+    max_transactions: usize,
+}
+
+impl Reconciler {
+    /// Create a reconciler scanning at most `max_transactions` vault signatures
+    pub fn new(solana: Arc<SolanaClient>, max_transactions: usize) -> Self {
+        Self {
+            solana,
+            max_transactions,
+        }
+    }
+
+    /// Reconcile the engine's paid and claimed rewards against the vault history
+    pub async fn run(&self, engine: &RewardEngine) -> Result<ReconciliationReport> {
+        let mut rewards = engine.get_paid_rewards().await?;
+        let mut unpaid = UnpaidClaims::default();
+        engine.storage().export(&mut unpaid).await?;
+        rewards.extend(unpaid.rewards);
+        let (transfers, truncated) = self
+            .solana
+            .get_vault_transfers(None, self.max_transactions)
+            .await?;
+        Ok(reconcile(&rewards, &unpaid.payouts, &transfers, truncated))
+    }
+}
+
+/// Collects claimed rewards with no payout recorded, and every payout batch
+#[derive(Default)]
+struct UnpaidClaims {
+    rewards: Vec<Reward>,
+    payouts: Vec<PayoutRequest>,
+}
+
+impl StorageVisitor for UnpaidClaims {
+    fn visit_reward(&mut self, reward: &Reward) -> Result<()> {
+        if reward.claimed && reward.transaction_signature.is_none() {
+            self.rewards.push(reward.clone());
+        }
+        Ok(())
+    }
+
+    fn visit_daily_stats(&mut self, _stats: &DailyStats) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_login_streak(&mut self, _streak: &LoginStreak) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_payout(&mut self, payout: &PayoutRequest) -> Result<()> {
+        self.payouts.push(payout.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::models::GameType;
+    use chrono::Duration;
+
+    fn reward(player_id: &str, lamports: u64, signature: Option<&str>) -> Reward {
+        Reward {
+            id: Uuid::new_v4(),
+            player_id: player_id.to_string(),
+            game: GameType::Battle,
+            amount: Amount::from_lamports(lamports),
+            timestamp: Utc::now(),
+            claimed: true,
+            game_data: serde_json::Value::Null,
+            transaction_signature: signature.map(str::to_string),
+            config_version: None,
+        }
+    }
+
+    fn transfer(signature: &str, amount: u64, memo: Option<PayoutMemo>) -> VaultTransfer {
+        VaultTransfer {
+            signature: signature.to_string(),
+            slot: 1,
+            block_time: Some(Utc::now()),
+            amount,
+            recipients: vec!["wallet".to_string()],
+            memo,
+        }
+    }
+
+    #[test]
+    fn matched_transfers_are_clean() {
+        let paid = reward("player1", 100, Some("sig1"));
+        let report = reconcile(&[paid], &[], &[transfer("sig1", 100, None)], false);
+
+        assert!(report.is_clean());
+        assert_eq!(report.transfers_scanned, 1);
+        assert_eq!(report.rewards_checked, 1);
+    }
+
+    #[test]
+    fn batch_hash_memos_resolve_through_stored_payouts() {
+        let rewards: Vec<Reward> = (0..10).map(|_| reward("player1", 10, None)).collect();
+        let payout = PayoutRequest::new("player1", "wallet", &rewards, Utc::now()).unwrap();
+        let memo = PayoutMemo::for_rewards(&payout.reward_ids);
+        assert!(matches!(memo, PayoutMemo::BatchHash(_)));
+
+        let transfers = [transfer("sig1", 100, Some(memo.clone()))];
+        let report = reconcile(&rewards, &[payout], &transfers, false);
+        assert!(report.is_clean());
+
+        // Without the payout the hash can't be resolved
+        let report = reconcile(&rewards, &[], &transfers, false);
+        assert_eq!(report.orphan_transfers.len(), 1);
+    }
+
+    #[test]
+    fn missing_orphan_and_mismatched_transfers_are_reported() {
+        let missing = reward("player1", 100, Some("sig-missing"));
+        let short = reward("player2", 50, Some("sig-short"));
+        let transfers = [transfer("sig-short", 40, None), transfer("sig-orphan", 70, None)];
+
+        let report = reconcile(&[missing.clone(), short.clone()], &[], &transfers, false);
+        assert!(!report.is_clean());
+        assert_eq!(report.missing_transfers.len(), 1);
+        assert_eq!(report.missing_transfers[0].reward_id, missing.id);
+        assert_eq!(report.orphan_transfers.len(), 1);
+        assert_eq!(report.orphan_transfers[0].signature, "sig-orphan");
+        assert_eq!(report.amount_mismatches.len(), 1);
+        assert_eq!(report.amount_mismatches[0].reward_ids, vec![short.id]);
+        assert_eq!(report.amount_mismatches[0].expected, 50);
+        assert_eq!(report.amount_mismatches[0].actual, 40);
+    }
+
+    #[test]
+    fn rewards_paid_twice_are_reported() {
+        let paid = reward("player1", 100, Some("sig1"));
+        let memo = PayoutMemo::for_rewards(&[paid.id]);
+        let transfers = [
+            transfer("sig1", 100, None),
+            transfer("sig2", 100, Some(memo)),
+        ];
+
+        let report = reconcile(std::slice::from_ref(&paid), &[], &transfers, false);
+        assert_eq!(report.double_payments.len(), 1);
+        assert_eq!(report.double_payments[0].reward_id, paid.id);
+        assert_eq!(report.double_payments[0].signatures, vec!["sig1", "sig2"]);
+    }
+
+    #[test]
+    fn rewards_older_than_truncated_history_are_unverified() {
+        let mut old = reward("player1", 100, Some("sig-old"));
+        old.timestamp = Utc::now() - Duration::days(30);
+        let recent = reward("player2", 50, Some("sig-recent"));
+        let mut scanned = transfer("sig-other", 0, None);
+        scanned.block_time = Some(Utc::now() - Duration::days(1));
+        let transfers = [scanned];
+
+        let report = reconcile(&[old.clone(), recent.clone()], &[], &transfers, true);
+        assert_eq!(report.unverified_transfers.len(), 1);
+        assert_eq!(report.unverified_transfers[0].reward_id, old.id);
+        assert_eq!(report.missing_transfers.len(), 1);
+        assert_eq!(report.missing_transfers[0].reward_id, recent.id);
+
+        // With the full history, the old reward is missing too
+        let report = reconcile(&[old, recent], &[], &transfers, false);
+        assert!(report.unverified_transfers.is_empty());
+        assert_eq!(report.missing_transfers.len(), 2);
+    }
+
+    #[test]
+    fn old_rewards_sent_inside_truncated_history_are_missing() {
+        let mut old = reward("player1", 100, None);
+        old.timestamp = Utc::now() - Duration::days(30);
+        let mut payout = PayoutRequest::new("player1", "wallet", std::slice::from_ref(&old), old.timestamp).unwrap();
+        payout.signature = Some("sig-retried".to_string());
+        payout.sent_at = Some(Utc::now() - Duration::hours(1));
+        old.transaction_signature = payout.signature.clone();
+
+        let mut scanned = transfer("sig-other", 0, None);
+        scanned.block_time = Some(Utc::now() - Duration::days(1));
+        let transfers = [scanned];
+
+        let report = reconcile(std::slice::from_ref(&old), std::slice::from_ref(&payout), &transfers, true);
+        assert!(report.unverified_transfers.is_empty());
+        assert_eq!(report.missing_transfers.len(), 1);
+        assert_eq!(report.missing_transfers[0].reward_id, old.id);
+
+        // Sent before the window, it may have landed there
+        payout.sent_at = Some(Utc::now() - Duration::days(2));
+        let report = reconcile(std::slice::from_ref(&old), &[payout], &transfers, true);
+        assert_eq!(report.unverified_transfers.len(), 1);
+        assert!(report.missing_transfers.is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+//! Multi-endpoint Solana RPC pool
+//!
+//! Tracks latency and error rates per endpoint, routes calls to the healthiest
+//! endpoint first and fails over to the next one on transport errors.
+
+use crate::config::RpcEndpointConfig;
+use crate::error::{PokemonEngineError, Result};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcError;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Consecutive failures after which an endpoint is put on cooldown
+const FAILURES_BEFORE_COOLDOWN: u32 = 3;
+
+/// How long an endpoint is skipped after repeated failures
+const COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Smoothing factor for latency and error rate moving averages
+const EWMA_ALPHA: f64 = 0.2;
+
+/// JSON-RPC error code returned by nodes that are behind or unhealthy
+const NODE_UNHEALTHY_CODE: i64 = -32005;
+
+/// Boxed future returned by pool operations
+pub type RpcFuture<'a, T> = Pin<Box<dyn Future<Output = std::result::Result<T, ClientError>> + Send + 'a>>;
+
+/// Snapshot of an endpoint's health
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointStats {
+    pub url: String,
+    pub weight: u32,
+    pub latency_ms: f64,
+    pub error_rate: f64,
+    pub requests: u64,
+    pub failures: u64,
+    pub healthy: bool,
+    pub score: f64,
+}
+
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    latency_ms: f64,
+    error_rate: f64,
+    requests: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    cooldown_until: Option<Instant>,
+}
+
+impl EndpointHealth {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.cooldown_until.is_none_or(|until| now >= until)
+    }
+
+    /// Higher is better: weight scaled down by latency and error rate
+    fn score(&self, weight: u32) -> f64 {
+        weight as f64 / ((1.0 + self.latency_ms / 100.0) * (1.0 + 10.0 * self.error_rate))
+    }
+
+    /// Fold one call's outcome into the averages, starting a cooldown after repeated failures
+    fn record(&mut self, elapsed: Duration, transport_failure: bool, now: Instant) {
+        let latency_ms = elapsed.as_secs_f64() * 1000.0;
+        let error = if transport_failure { 1.0 } else { 0.0 };
+
+        if self.requests == 0 {
+            self.latency_ms = latency_ms;
+            self.error_rate = error;
+        } else {
+            self.latency_ms += EWMA_ALPHA * (latency_ms - self.latency_ms);
+            self.error_rate += EWMA_ALPHA * (error - self.error_rate);
+        }
+        self.requests += 1;
+
+        if transport_failure {
+            self.failures += 1;
+            self.consecutive_failures += 1;
+            if self.consecutive_failures >= FAILURES_BEFORE_COOLDOWN {
+                self.cooldown_until = Some(now + COOLDOWN);
+            }
+        } else {
+            self.consecutive_failures = 0;
+            self.cooldown_until = None;
+        }
+    }
+}
+
+/// Routing order of endpoints given as (healthy, score): healthy ones by descending score, then the rest
+fn routing_order(endpoints: &[(bool, f64)]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..endpoints.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a_healthy, a_score) = endpoints[a];
+        let (b_healthy, b_score) = endpoints[b];
+        b_healthy.cmp(&a_healthy).then(b_score.total_cmp(&a_score))
+    });
+    order
+}
+
+struct RpcEndpoint {
+    url: String,
+    weight: u32,
+    client: RpcClient,
+    health: Mutex<EndpointHealth>,
+}
+
+impl RpcEndpoint {
+    fn health(&self) -> std::sync::MutexGuard<'_, EndpointHealth> {
+        self.health.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.health().is_healthy(now)
+    }
+
+    fn score(&self) -> f64 {
+        self.health().score(self.weight)
+    }
+
+    fn record(&self, elapsed: Duration, transport_failure: bool) {
+        self.health().record(elapsed, transport_failure, Instant::now());
+    }
+
+    fn stats(&self, now: Instant) -> EndpointStats {
+        let health = self.health();
+        let healthy = health.is_healthy(now);
+        let score = health.score(self.weight);
+        EndpointStats {
+            url: self.url.clone(),
+            weight: self.weight,
+            latency_ms: health.latency_ms,
+            error_rate: health.error_rate,
+            requests: health.requests,
+            failures: health.failures,
+            healthy,
+            score,
+        }
+    }
+}
+
+/// Pool of RPC endpoints with health scoring and failover
+pub struct RpcPool {
+    endpoints: Vec<RpcEndpoint>,
+}
+
+impl RpcPool {
+    /// Create a pool from endpoint configurations
+    pub fn new(endpoints: &[RpcEndpointConfig], commitment: CommitmentConfig) -> Result<Self> {
+        if endpoints.is_empty() {
+            return Err(PokemonEngineError::config("solana.endpoints", "No RPC endpoints configured"));
+        }
+
+        let endpoints = endpoints
+            .iter()
+            .map(|endpoint| RpcEndpoint {
+                url: endpoint.url.clone(),
+                weight: endpoint.weight.max(1),
+                client: RpcClient::new_with_commitment(endpoint.url.clone(), commitment),
+                health: Mutex::new(EndpointHealth::default()),
+            })
+            .collect();
+
+        Ok(Self { endpoints })
+    }
+
+    /// Endpoints in routing order: healthy ones by descending score, then the rest
+    fn ranked(&self) -> Vec<&RpcEndpoint> {
+        let now = Instant::now();
+        let health: Vec<(bool, f64)> = self.endpoints.iter().map(|e| (e.is_healthy(now), e.score())).collect();
+        routing_order(&health).into_iter().map(|index| &self.endpoints[index]).collect()
+    }
+
+    /// Run an operation on the best endpoint, failing over on transport errors
+    ///
+    /// Errors that would repeat on any node (e.g. a failed simulation) are
+    /// returned immediately without trying other endpoints.
+    pub async fn call<'a, T, F>(&'a self, op: F) -> std::result::Result<T, ClientError>
+    where
+        F: Fn(&'a RpcClient) -> RpcFuture<'a, T>,
+    {
+        let mut last_error = None;
+        for endpoint in self.ranked() {
+            let started = Instant::now();
+            match op(&endpoint.client).await {
+                Ok(value) => {
+                    endpoint.record(started.elapsed(), false);
+                    return Ok(value);
+                }
+                Err(err) => {
+                    let transport_failure = is_transport_error(&err);
+                    endpoint.record(started.elapsed(), transport_failure);
+                    if !transport_failure {
+                        return Err(err);
+                    }
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        // The pool is never empty, so at least one attempt was made
+        Err(last_error.expect("RPC pool has at least one endpoint"))
+    }
+
+    /// Run an operation on every healthy endpoint, returning the first success
+    ///
+    /// Endpoints are called concurrently, so a slow one doesn't hold back the
+    /// others. Results are taken in rank order once all have answered. Falls
+    /// back to all endpoints if none is currently healthy.
+    pub async fn broadcast<'a, T, F>(&'a self, op: F) -> std::result::Result<T, ClientError>
+    where
+        F: Fn(&'a RpcClient) -> RpcFuture<'a, T>,
+    {
+        let now = Instant::now();
+        let ranked = self.ranked();
+        let healthy: Vec<&RpcEndpoint> = ranked.iter().copied().filter(|e| e.is_healthy(now)).collect();
+        let targets = if healthy.is_empty() { ranked } else { healthy };
+
+        let calls = targets.into_iter().map(|endpoint| {
+            let call = op(&endpoint.client);
+            async move {
+                let started = Instant::now();
+                (endpoint, call.await, started.elapsed())
+            }
+        });
+
+        let mut first_ok = None;
+        let mut last_error = None;
+        for (endpoint, result, elapsed) in join_all(calls).await {
+            match result {
+                Ok(value) => {
+                    endpoint.record(elapsed, false);
+                    first_ok.get_or_insert(value);
+                }
+                Err(err) => {
+                    endpoint.record(elapsed, is_transport_error(&err));
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        match (first_ok, last_error) {
+            (Some(value), _) => Ok(value),
+            (None, Some(err)) => Err(err),
+            (None, None) => unreachable!("RPC pool has at least one endpoint"),
+        }
+    }
+
+    /// Current health of every endpoint
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        let now = Instant::now();
+        self.endpoints.iter().map(|e| e.stats(now)).collect()
+    }
+}
+
+/// Check if an error is caused by the endpoint rather than the request
+fn is_transport_error(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) | ClientErrorKind::Middleware(_) => true,
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => *code == NODE_UNHEALTHY_CODE,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST: Duration = Duration::from_millis(50);
+
+    fn order(endpoints: &[(u32, &EndpointHealth)], now: Instant) -> Vec<usize> {
+        let health: Vec<(bool, f64)> = endpoints
+            .iter()
+            .map(|(weight, health)| (health.is_healthy(now), health.score(*weight)))
+            .collect();
+        routing_order(&health)
+    }
+
+    #[test]
+    fn score_prefers_weight_and_penalizes_latency_and_errors() {
+        let now = Instant::now();
+        let mut fast = EndpointHealth::default();
+        fast.record(FAST, false, now);
+        let mut slow = EndpointHealth::default();
+        slow.record(Duration::from_millis(500), false, now);
+        let mut failing = EndpointHealth::default();
+        failing.record(FAST, true, now);
+
+        assert!(fast.score(1) > slow.score(1));
+        assert!(fast.score(1) > failing.score(1));
+        assert!(fast.score(2) > fast.score(1));
+        assert_eq!(order(&[(1, &slow), (1, &failing), (1, &fast)], now), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn failing_endpoint_is_demoted_then_cooled_down() {
+        let now = Instant::now();
+        let mut primary = EndpointHealth::default();
+        primary.record(FAST, false, now);
+        let mut backup = EndpointHealth::default();
+        backup.record(FAST * 2, false, now);
+        assert_eq!(order(&[(1, &primary), (1, &backup)], now), vec![0, 1]);
+
+        primary.record(FAST, true, now);
+        assert!(primary.is_healthy(now));
+        assert_eq!(order(&[(1, &primary), (1, &backup)], now), vec![1, 0]);
+
+        for _ in 1..FAILURES_BEFORE_COOLDOWN {
+            primary.record(FAST, true, now);
+        }
+        assert!(!primary.is_healthy(now));
+        assert!(primary.is_healthy(now + COOLDOWN));
+        assert_eq!(primary.failures, FAILURES_BEFORE_COOLDOWN as u64);
+
+        // Out of rotation even if its score were the best
+        assert_eq!(order(&[(100, &primary), (1, &backup)], now), vec![1, 0]);
+    }
+
+    #[test]
+    fn endpoint_recovers_after_successes() {
+        let now = Instant::now();
+        let mut primary = EndpointHealth::default();
+        for _ in 0..FAILURES_BEFORE_COOLDOWN {
+            primary.record(FAST, true, now);
+        }
+        let mut backup = EndpointHealth::default();
+        backup.record(FAST, false, now);
+        assert!(!primary.is_healthy(now));
+
+        // One success ends the cooldown
+        let later = now + COOLDOWN;
+        primary.record(FAST, false, later);
+        assert!(primary.is_healthy(now));
+        assert_eq!(primary.consecutive_failures, 0);
+        assert_eq!(order(&[(1, &primary), (1, &backup)], later), vec![1, 0]);
+
+        // The error rate decays until the weight wins again
+        for _ in 0..20 {
+            primary.record(FAST, false, later);
+        }
+        assert!(primary.error_rate < 0.02);
+        assert_eq!(order(&[(2, &primary), (1, &backup)], later), vec![0, 1]);
+    }
+}
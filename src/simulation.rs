@@ -0,0 +1,311 @@
+//! What-if simulation of reward config changes
+//!
+//! Feeds a recorded stream of game events through engines built from a
+//! baseline and a candidate `Config`, each over its own in-memory storage,
+//! and compares the resulting emission.
+
+use crate::amount::Amount;
+use crate::config::Config;
+use crate::engine::{Decision, DecisionContext, RewardEngine};
+use crate::error::{PokemonEngineError, Result};
+use crate::event_log::{EventLog, LogRecord};
+use crate::models::GameEvent;
+use crate::storage::MemoryStorage;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Entries read from the log per batch
+const LOG_BATCH: usize = 1_000;
+
+/// Game event with the time it originally happened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedEvent {
+    pub at: DateTime<Utc>,
+    pub event: GameEvent,
+}
+
+/// Per-player emission distribution (lamports)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Percentiles {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+/// Outcome of running the event stream under one config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScenarioSummary {
+    pub total_emission: u64,
+    pub rewards_issued: u64,
+    pub rejections: u64,
+    pub invalid_events: u64, // Events the engine could not parse
+    pub emission_by_game: BTreeMap<String, u64>,
+    pub emission_by_day: BTreeMap<NaiveDate, u64>,
+    pub players: u64,
+    pub players_hit_limit: u64, // Players with at least one daily limit rejection
+    pub player_emission: Percentiles,
+}
+
+/// Baseline vs. candidate value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delta {
+    pub baseline: u64,
+    pub candidate: u64,
+    pub change_pct: Option<f64>, // None when the baseline is zero
+}
+
+impl Delta {
+    fn new(baseline: u64, candidate: u64) -> Self {
+        let change_pct = (baseline > 0)
+            .then(|| (candidate as f64 - baseline as f64) / baseline as f64 * 100.0);
+        Self {
+            baseline,
+            candidate,
+            change_pct,
+        }
+    }
+}
+
+/// Comparison of a candidate config against the baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhatIfReport {
+    pub events: u64,
+    pub baseline: ScenarioSummary,
+    pub candidate: ScenarioSummary,
+    pub total_emission: Delta,
+    pub emission_by_game: BTreeMap<String, Delta>,
+    pub emission_by_day: BTreeMap<NaiveDate, Delta>,
+    pub players_hit_limit: Delta,
+}
+
+/// Run the event stream under one config in a sandboxed storage
+///
+/// Events are processed in time order, each decided at its original time.
+/// Claims and payouts are not part of the stream, and the circuit breaker
+/// never pauses issuance during a simulation.
+pub async fn simulate(events: &[TimedEvent], config: Config) -> Result<ScenarioSummary> {
+    let engine = RewardEngine::new(config, Box::new(MemoryStorage::new()))?;
+    let mut summary = ScenarioSummary::default();
+    let mut per_player: HashMap<&str, u64> = HashMap::new();
+    let mut hit_limit: HashSet<&str> = HashSet::new();
+
+    let mut ordered: Vec<&TimedEvent> = events.iter().collect();
+    ordered.sort_by_key(|e| e.at);
+
+    for timed in ordered {
+        let player_id = timed.event.player_id.as_str();
+        per_player.entry(player_id).or_default();
+
+        let context = DecisionContext {
+            now: timed.at,
+            ..DecisionContext::now()
+        };
+        let decision = match engine.decide(&timed.event, &context).await {
+            Ok(decision) => decision,
+            Err(_) => {
+                summary.invalid_events += 1;
+                continue;
+            }
+        };
+
+        match decision {
+            Decision::Accepted {
+                reward,
+                daily_stats,
+                login_streak,
+            } => {
+                engine
+                    .storage()
+                    .record_reward(&reward, daily_stats.as_ref(), login_streak.as_ref())
+                    .await?;
+                summary.rewards_issued += 1;
+                add_emission(&mut summary.total_emission, reward.amount)?;
+                add_emission(summary.emission_by_game.entry(reward.game.to_string()).or_default(), reward.amount)?;
+                add_emission(summary.emission_by_day.entry(reward.timestamp.date_naive()).or_default(), reward.amount)?;
+                add_emission(per_player.entry(player_id).or_default(), reward.amount)?;
+            }
+            Decision::Rejected {
+                daily_limit_reached,
+                ..
+            } => {
+                summary.rejections += 1;
+                if daily_limit_reached {
+                    hit_limit.insert(player_id);
+                }
+            }
+        }
+    }
+
+    let mut totals: Vec<u64> = per_player.into_values().collect();
+    totals.sort_unstable();
+    summary.players = totals.len() as u64;
+    summary.players_hit_limit = hit_limit.len() as u64;
+    summary.player_emission = Percentiles {
+        p50: percentile(&totals, 50),
+        p90: percentile(&totals, 90),
+        p99: percentile(&totals, 99),
+        max: totals.last().copied().unwrap_or(0),
+    };
+    Ok(summary)
+}
+
+/// Compare a candidate config against the baseline on the same events
+pub async fn what_if(events: &[TimedEvent], baseline: Config, candidate: Config) -> Result<WhatIfReport> {
+    let baseline = simulate(events, baseline).await?;
+    let candidate = simulate(events, candidate).await?;
+
+    Ok(WhatIfReport {
+        events: events.len() as u64,
+        total_emission: Delta::new(baseline.total_emission, candidate.total_emission),
+        emission_by_game: compare(&baseline.emission_by_game, &candidate.emission_by_game),
+        emission_by_day: compare(&baseline.emission_by_day, &candidate.emission_by_day),
+        players_hit_limit: Delta::new(baseline.players_hit_limit, candidate.players_hit_limit),
+        baseline,
+        candidate,
+    })
+}
+
+/// Collect the game events recorded in an event log
+///
+/// Events whose changes were aborted are left out. Rejected and failed events
+/// are kept, since a candidate config may accept them.
+pub async fn events_from_log(log: &dyn EventLog) -> Result<Vec<TimedEvent>> {
+    let mut events = Vec::new();
+    let mut aborted = HashSet::new();
+    let mut next = 1;
+    loop {
+        let entries = log.read(next, LOG_BATCH).await?;
+        let Some(last) = entries.last() else { break };
+        next = last.sequence + 1;
+
+        for entry in entries {
+            match entry.record {
+                LogRecord::GameEvent { event, .. } | LogRecord::FailedEvent { event, .. } => events.push((
+                    entry.sequence,
+                    TimedEvent {
+                        at: entry.recorded_at,
+                        event,
+                    },
+                )),
+                LogRecord::Aborted { sequence } => {
+                    aborted.insert(sequence);
+                }
+                LogRecord::Claim { .. }
+                | LogRecord::PayoutRequested { .. }
+                | LogRecord::PayoutUpdated { .. }
+                | LogRecord::Payout { .. }
+                | LogRecord::ConfigChanged { .. }
+                | LogRecord::ConfigCancelled { .. } => {}
+            }
+        }
+    }
+
+    Ok(events
+        .into_iter()
+        .filter(|(sequence, _)| !aborted.contains(sequence))
+        .map(|(_, event)| event)
+        .collect())
+}
+
+/// Add a reward amount to an emission counter, failing on overflow
+fn add_emission(total: &mut u64, amount: Amount) -> Result<()> {
+    *total = total.checked_add(amount.lamports()).ok_or_else(|| {
+        PokemonEngineError::AmountOverflow("Simulated emission doesn't fit in a u64".to_string())
+    })?;
+    Ok(())
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], pct: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn compare<K: Ord + Clone>(baseline: &BTreeMap<K, u64>, candidate: &BTreeMap<K, u64>) -> BTreeMap<K, Delta> {
+    baseline
+        .keys()
+        .chain(candidate.keys())
+        .map(|key| {
+            let delta = Delta::new(
+                baseline.get(key).copied().unwrap_or(0),
+                candidate.get(key).copied().unwrap_or(0),
+            );
+            (key.clone(), delta)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BattleEventData, GameType};
+    use chrono::{Duration, TimeZone};
+
+    const POKE: u64 = 1_000_000_000;
+
+    fn battle(player_id: &str, at: DateTime<Utc>) -> TimedEvent {
+        let data = BattleEventData { level: 1, streak: 0, perfect_victory: None };
+        TimedEvent {
+            at,
+            event: GameEvent {
+                player_id: player_id.to_string(),
+                game: GameType::Battle,
+                event_data: serde_json::to_value(data).unwrap(),
+                locale: None,
+            },
+        }
+    }
+
+    /// Five battles by one player and one by another, all on one day
+    fn events() -> Vec<TimedEvent> {
+        let start = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let mut events: Vec<TimedEvent> = (0..5).map(|i| battle("player1", start + Duration::minutes(i))).collect();
+        events.push(battle("player2", start));
+        events
+    }
+
+    #[tokio::test]
+    async fn what_if_reports_emission_and_limit_deltas() {
+        // 70 POKE a battle against a 300 POKE daily limit: player1's fifth is rejected
+        let baseline = Config::default();
+        // 50 POKE a battle: all five fit
+        let mut candidate = Config::default();
+        candidate.rewards.battle.base_reward = Amount::from_lamports(30 * POKE);
+
+        let report = what_if(&events(), baseline, candidate).await.unwrap();
+        assert_eq!(report.events, 6);
+
+        assert_eq!(report.baseline.rewards_issued, 5);
+        assert_eq!(report.baseline.rejections, 1);
+        assert_eq!(report.baseline.players, 2);
+        assert_eq!(report.baseline.player_emission.max, 280 * POKE);
+        assert_eq!(report.candidate.rewards_issued, 6);
+        assert_eq!(report.candidate.rejections, 0);
+
+        assert_eq!(report.total_emission.baseline, 350 * POKE);
+        assert_eq!(report.total_emission.candidate, 300 * POKE);
+        let change = report.total_emission.change_pct.unwrap();
+        assert!((change + 100.0 / 7.0).abs() < 1e-9);
+        assert_eq!(report.emission_by_game["battle"].candidate, 300 * POKE);
+        let day = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        assert_eq!(report.emission_by_day[&day].baseline, 350 * POKE);
+
+        assert_eq!(report.players_hit_limit.baseline, 1);
+        assert_eq!(report.players_hit_limit.candidate, 0);
+        assert_eq!(report.players_hit_limit.change_pct, Some(-100.0));
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let values: Vec<u64> = (1..=10).collect();
+        assert_eq!(percentile(&values, 50), 5);
+        assert_eq!(percentile(&values, 90), 9);
+        assert_eq!(percentile(&values, 99), 10);
+        assert_eq!(percentile(&[], 50), 0);
+    }
+}
@@ -3,23 +3,150 @@
 //! This module provides functionality to interact with Solana blockchain
 //! for distributing POKE token rewards.
 
+use crate::config::{Config, ConfigIssue, RpcEndpointConfig, SolanaConfig};
 use crate::error::{Result, PokemonEngineError};
 use crate::models::Reward;
+use crate::rpc_pool::{EndpointStats, RpcPool};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
 use solana_sdk::{
+    account::Account,
     commitment_config::CommitmentConfig,
+    hash::hashv,
+    program_pack::Pack,
     pubkey::Pubkey,
-    signature::Keypair,
+    signature::{Keypair, Signature, Signer},
+    transaction::{Transaction, VersionedTransaction},
+};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiLoadedAddresses, UiTransactionEncoding,
+    UiTransactionTokenBalance,
 };
 use std::str::FromStr;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Maximum page size accepted by `getSignaturesForAddress`
+const SIGNATURE_PAGE_SIZE: usize = 1000;
+
+/// How long to wait for a sent payout to reach the client's commitment
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Delay between signature status polls while confirming
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Largest batch whose reward ids are written into the memo verbatim
+const MAX_INLINE_MEMO_REWARDS: usize = 8;
+
+/// Prefix of memos listing reward ids
+const MEMO_REWARDS_PREFIX: &str = "poke:r:";
+
+/// Prefix of memos carrying a batch hash
+const MEMO_BATCH_PREFIX: &str = "poke:b:";
+
+/// Memo attached to payout transactions to link them to rewards
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum PayoutMemo {
+    Rewards(Vec<Uuid>),
+    BatchHash(String),
+}
+
+impl PayoutMemo {
+    /// Build the memo for a payout, hashing batches too large to list inline
+    pub fn for_rewards(reward_ids: &[Uuid]) -> Self {
+        if reward_ids.len() <= MAX_INLINE_MEMO_REWARDS {
+            PayoutMemo::Rewards(reward_ids.to_vec())
+        } else {
+            PayoutMemo::BatchHash(Self::batch_hash(reward_ids))
+        }
+    }
+    
+    /// Compact, order-independent hash of a batch of reward ids
+    pub fn batch_hash(reward_ids: &[Uuid]) -> String {
+        let mut ids = reward_ids.to_vec();
+        ids.sort();
+        let bytes: Vec<&[u8]> = ids.iter().map(|id| id.as_bytes().as_slice()).collect();
+        hashv(&bytes).to_string()
+    }
+    
+    /// Encode the memo as it is written on chain
+    pub fn encode(&self) -> String {
+        match self {
+            PayoutMemo::Rewards(ids) => {
+                let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                format!("{}{}", MEMO_REWARDS_PREFIX, ids.join(","))
+            }
+            PayoutMemo::BatchHash(hash) => format!("{}{}", MEMO_BATCH_PREFIX, hash),
+        }
+    }
+    
+    /// Parse a memo written by `encode`, returning `None` for foreign memos
+    pub fn parse(memo: &str) -> Option<Self> {
+        if let Some(ids) = memo.strip_prefix(MEMO_REWARDS_PREFIX) {
+            ids.split(',')
+                .map(|id| Uuid::parse_str(id).ok())
+                .collect::<Option<Vec<_>>>()
+                .map(PayoutMemo::Rewards)
+        } else {
+            memo.strip_prefix(MEMO_BATCH_PREFIX)
+                .filter(|hash| !hash.is_empty())
+                .map(|hash| PayoutMemo::BatchHash(hash.to_string()))
+        }
+    }
+    
+    /// Check if the memo identifies exactly this set of rewards
+    pub fn matches(&self, reward_ids: &[Uuid]) -> bool {
+        match self {
+            PayoutMemo::Rewards(ids) => {
+                let mut expected = reward_ids.to_vec();
+                let mut actual = ids.clone();
+                expected.sort();
+                actual.sort();
+                expected == actual
+            }
+            PayoutMemo::BatchHash(hash) => *hash == Self::batch_hash(reward_ids),
+        }
+    }
+}
+
+/// Outgoing token transfer from the reward vault observed on chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultTransfer {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<DateTime<Utc>>,
+    pub amount: u64, // Net amount that left the vault
+    pub recipients: Vec<String>, // Owners of the token accounts that were credited
+    pub memo: Option<PayoutMemo>, // Payout memo attached to the transaction, if any
+}
+
+/// Signed payout transaction that has not been sent yet
+#[derive(Debug, Clone)]
+pub struct PreparedPayout {
+    pub transaction: Transaction,
+    pub signature: String,
+    pub last_valid_block_height: u64, // The transaction can't land after this block height
+}
+
+/// What became of a submitted payout transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayoutTransactionStatus {
+    Confirmed,
+    Failed(String),
+    Pending, // Not confirmed yet, but may still land
+    Expired, // Never landed and no longer can
+}
 
 /// Solana client for reward distribution
 pub struct SolanaClient {
-    #[allow(dead_code)]
-    rpc_url: String,
-    #[allow(dead_code)]
     commitment: CommitmentConfig,
     token_mint: Option<Pubkey>,
     reward_vault: Option<Pubkey>,
+    rpc: RpcPool,
+    broadcast_sends: bool,
 }
 
 impl SolanaClient {
@@ -30,31 +157,104 @@ impl SolanaClient {
         token_mint: Option<String>,
         reward_vault: Option<String>,
     ) -> Result<Self> {
-        let commitment_config = match commitment {
-            "finalized" => CommitmentConfig::finalized(),
-            "confirmed" => CommitmentConfig::confirmed(),
-            "processed" => CommitmentConfig::processed(),
-            _ => CommitmentConfig::confirmed(),
-        };
+        let endpoints = [RpcEndpointConfig { url: rpc_url, weight: 1 }];
+        Self::with_endpoints(&endpoints, commitment, token_mint, reward_vault, false)
+    }
+    
+    /// Create a Solana client from configuration, using every configured RPC endpoint
+    pub fn from_config(config: &SolanaConfig) -> Result<Self> {
+        Self::with_endpoints(
+            &config.rpc_endpoints(),
+            &config.commitment,
+            config.token_mint.clone(),
+            config.reward_vault.clone(),
+            config.broadcast_sends,
+        )
+    }
+    
+    /// Create a Solana client routing requests across several RPC endpoints
+    pub fn with_endpoints(
+        endpoints: &[RpcEndpointConfig],
+        commitment: &str,
+        token_mint: Option<String>,
+        reward_vault: Option<String>,
+        broadcast_sends: bool,
+    ) -> Result<Self> {
+        let commitment_config = parse_commitment(commitment)
+            .map_err(|issue| PokemonEngineError::Config(vec![issue]))?;
         
         let mint = token_mint
             .map(|m| Pubkey::from_str(&m))
             .transpose()
-            .map_err(|e| PokemonEngineError::Solana(format!("Invalid token mint: {}", e)))?;
+            .map_err(|e| PokemonEngineError::config("solana.token_mint", format!("Invalid public key: {}", e)))?;
         
         let vault = reward_vault
             .map(|v| Pubkey::from_str(&v))
             .transpose()
-            .map_err(|e| PokemonEngineError::Solana(format!("Invalid reward vault: {}", e)))?;
+            .map_err(|e| PokemonEngineError::config("solana.reward_vault", format!("Invalid public key: {}", e)))?;
+        
+        let rpc = RpcPool::new(endpoints, commitment_config)?;
         
         Ok(Self {
-            rpc_url,
             commitment: commitment_config,
             token_mint: mint,
             reward_vault: vault,
+            rpc,
+            broadcast_sends,
         })
     }
     
+    /// Create a Solana client and validate its configuration against the chain
+    ///
+    /// Intended to run once at startup. Checks that the commitment level is known,
+    /// the token mint exists, is an SPL token mint with `config.token_decimals`
+    /// decimals and the expected mint authority, and that the reward vault is a
+    /// token account for that mint. Every problem found is reported together in
+    /// a single `Config` error; RPC failures are returned as `Solana` errors.
+    pub async fn connect(config: &Config) -> Result<Self> {
+        let solana = &config.solana;
+        
+        // Malformed settings are reported by `check_accounts`; keep going with
+        // what parses so chain problems are reported alongside them
+        let commitment = parse_commitment(&solana.commitment).unwrap_or(CommitmentConfig::confirmed());
+        let mint = solana.token_mint.as_deref().and_then(|m| Pubkey::from_str(m).ok());
+        let vault = solana.reward_vault.as_deref().and_then(|v| Pubkey::from_str(v).ok());
+        
+        let client = Self {
+            commitment,
+            token_mint: mint,
+            reward_vault: vault,
+            rpc: RpcPool::new(&solana.rpc_endpoints(), commitment)?,
+            broadcast_sends: solana.broadcast_sends,
+        };
+        
+        let mut mint_account = None;
+        let mut vault_account = None;
+        if let Some(mint) = mint {
+            mint_account = client.fetch_account(&mint).await?;
+            if let Some(vault) = vault {
+                vault_account = client.fetch_account(&vault).await?;
+            }
+        }
+        
+        check_accounts(config, mint_account.as_ref(), vault_account.as_ref())?;
+        Ok(client)
+    }
+    
+    /// Fetch an account, returning `None` if it does not exist
+    async fn fetch_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        let response = self.rpc
+            .call(|c| Box::pin(c.get_account_with_commitment(pubkey, self.commitment)))
+            .await
+            .map_err(|e| PokemonEngineError::Solana(format!("Failed to fetch account {}: {}", pubkey, e)))?;
+        Ok(response.value)
+    }
+    
+    /// Current health of each RPC endpoint
+    pub fn rpc_stats(&self) -> Vec<EndpointStats> {
+        self.rpc.stats()
+    }
+    
     /// Distribute reward to player's wallet
     /// 
     /// This function creates a token transfer transaction from the reward vault
@@ -63,48 +263,206 @@ impl SolanaClient {
         &self,
         reward: &Reward,
         player_wallet: &str,
-        _vault_keypair: &Keypair,
+        vault_keypair: &Keypair,
     ) -> Result<String> {
+        self.distribute_rewards(std::slice::from_ref(reward), player_wallet, vault_keypair).await
+    }
+    
+    /// Distribute several rewards to one player's wallet in a single transfer
+    ///
+    /// The transaction creates the player's associated token account if needed,
+    /// transfers the combined amount from the reward vault (signed by its owner,
+    /// `vault_keypair`, which also pays fees) and carries a `PayoutMemo`
+    /// identifying the rewards being paid.
+    pub async fn distribute_rewards(
+        &self,
+        rewards: &[Reward],
+        player_wallet: &str,
+        vault_keypair: &Keypair,
+    ) -> Result<String> {
+        let amount = rewards
+            .iter()
+            .try_fold(0u64, |acc, r| acc.checked_add(r.amount.lamports()))
+            .ok_or_else(|| PokemonEngineError::AmountOverflow("Payout amount".to_string()))?;
+        let reward_ids: Vec<Uuid> = rewards.iter().map(|r| r.id).collect();
+        
+        let payout = self.prepare_payout(&reward_ids, amount, player_wallet, vault_keypair).await?;
+        self.submit_payout(&payout).await
+    }
+    
+    /// Build and sign a payout transaction without sending it
+    ///
+    /// The signature is known before anything reaches the chain, so callers
+    /// can persist it first and later tell whether the transaction landed
+    /// (see `payout_status`) instead of blindly sending a second one.
+    pub async fn prepare_payout(
+        &self,
+        reward_ids: &[Uuid],
+        amount: u64,
+        player_wallet: &str,
+        vault_keypair: &Keypair,
+    ) -> Result<PreparedPayout> {
+        if reward_ids.is_empty() {
+            return Err(PokemonEngineError::InvalidAmount("No rewards to distribute".to_string()));
+        }
+        
         let player_pubkey = Pubkey::from_str(player_wallet)
-            .map_err(|e| PokemonEngineError::Solana(format!("Invalid player wallet: {}", e)))?;
+            .map_err(|e| PokemonEngineError::InvalidWallet(format!("{}: {}", player_wallet, e)))?;
         
         let token_mint = self.token_mint
-            .ok_or_else(|| PokemonEngineError::Solana("Token mint not configured".to_string()))?;
+            .ok_or_else(|| PokemonEngineError::config("solana.token_mint", "Not configured"))?;
         
-        let _vault_pubkey = self.reward_vault
-            .ok_or_else(|| PokemonEngineError::Solana("Reward vault not configured".to_string()))?;
+        let vault_pubkey = self.reward_vault
+            .ok_or_else(|| PokemonEngineError::config("solana.reward_vault", "Not configured"))?;
         
-        // Get or create associated token account for player
-        let _player_token_account = spl_associated_token_account::get_associated_token_address(
+        let authority = vault_keypair.pubkey();
+        let player_token_account = spl_associated_token_account::get_associated_token_address(
             &player_pubkey,
             &token_mint,
         );
         
-        // Create transfer instruction
-        // Note: This is a simplified version. In production, you would:
-        // 1. Get the vault's token account
-        // 2. Check if player has associated token account, create if not
-        // 3. Build proper transfer instruction
-        // 4. Send transaction via RPC client
+        let memo = PayoutMemo::for_rewards(reward_ids).encode();
+        
+        let instructions = vec![
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &authority,
+                &player_pubkey,
+                &token_mint,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::transfer(
+                &spl_token::id(),
+                &vault_pubkey,
+                &player_token_account,
+                &authority,
+                &[],
+                amount,
+            )
+            .map_err(|e| PokemonEngineError::Solana(format!("Failed to build transfer: {}", e)))?,
+            spl_memo::build_memo(memo.as_bytes(), &[&authority]),
+        ];
+        
+        let (blockhash, last_valid_block_height) = self.rpc
+            .call(|c| Box::pin(c.get_latest_blockhash_with_commitment(self.commitment)))
+            .await
+            .map_err(|e| PokemonEngineError::Solana(format!("Failed to fetch blockhash: {}", e)))?;
+        
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&authority),
+            &[vault_keypair],
+            blockhash,
+        );
+        
+        Ok(PreparedPayout {
+            signature: transaction.signatures[0].to_string(),
+            transaction,
+            last_valid_block_height,
+        })
+    }
+    
+    /// Send a prepared payout and wait until it is confirmed
+    pub async fn submit_payout(&self, payout: &PreparedPayout) -> Result<String> {
+        let signature = self.send_and_confirm(&payout.transaction).await?;
+        Ok(signature.to_string())
+    }
+    
+    /// Check what became of a previously submitted payout transaction
+    ///
+    /// A transaction that is not found is only reported as `Expired` once the
+    /// chain is past `last_valid_block_height`, after which it can never land.
+    pub async fn payout_status(
+        &self,
+        signature: &str,
+        last_valid_block_height: u64,
+    ) -> Result<PayoutTransactionStatus> {
+        let signatures = [Signature::from_str(signature)
+            .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid signature: {}", e)))?];
+        
+        let statuses = self.rpc
+            .call(|c| Box::pin(c.get_signature_statuses_with_history(&signatures)))
+            .await
+            .map_err(|e| PokemonEngineError::Solana(format!("Failed to fetch signature status: {}", e)))?;
+        
+        if let Some(status) = statuses.value.into_iter().next().flatten() {
+            if let Some(err) = status.err {
+                return Ok(PayoutTransactionStatus::Failed(err.to_string()));
+            }
+            if status.satisfies_commitment(self.commitment) {
+                return Ok(PayoutTransactionStatus::Confirmed);
+            }
+            return Ok(PayoutTransactionStatus::Pending);
+        }
         
-        // Placeholder implementation
-        // In production, use solana_client::rpc_client::RpcClient to:
-        // 1. Get recent blockhash
-        // 2. Build transaction with transfer instruction
-        // 3. Sign with vault_keypair
-        // 4. Send and confirm transaction
-        // 5. Return transaction signature
+        let block_height = self.rpc
+            .call(|c| Box::pin(c.get_block_height_with_commitment(self.commitment)))
+            .await
+            .map_err(|e| PokemonEngineError::Solana(format!("Failed to fetch block height: {}", e)))?;
         
-        Ok(format!("placeholder_signature_{}", reward.id))
+        if block_height > last_valid_block_height {
+            Ok(PayoutTransactionStatus::Expired)
+        } else {
+            Ok(PayoutTransactionStatus::Pending)
+        }
+    }
+    
+    /// Send a transaction and wait until it reaches the client's commitment
+    ///
+    /// The transaction is submitted with preflight checks through the best
+    /// endpoint. With `broadcast_sends` enabled it is then also pushed to every
+    /// healthy endpoint without preflight, which is safe because duplicates of
+    /// a signed transaction are deduplicated by signature.
+    async fn send_and_confirm(&self, transaction: &Transaction) -> Result<Signature> {
+        let signature = self.rpc
+            .call(|c| Box::pin(c.send_transaction(transaction)))
+            .await
+            .map_err(|e| PokemonEngineError::Solana(format!("Failed to send payout: {}", e)))?;
+        
+        if self.broadcast_sends {
+            let config = RpcSendTransactionConfig {
+                skip_preflight: true,
+                ..RpcSendTransactionConfig::default()
+            };
+            // The transaction is already in flight, so broadcast failures are not fatal
+            let _ = self.rpc
+                .broadcast(|c| Box::pin(c.send_transaction_with_config(transaction, config)))
+                .await;
+        }
+        
+        let signatures = [signature];
+        let deadline = Instant::now() + CONFIRM_TIMEOUT;
+        loop {
+            let statuses = self.rpc
+                .call(|c| Box::pin(c.get_signature_statuses(&signatures)))
+                .await
+                .map_err(|e| PokemonEngineError::Solana(format!("Failed to fetch signature status: {}", e)))?;
+            
+            if let Some(status) = statuses.value.into_iter().next().flatten() {
+                if let Some(err) = status.err {
+                    return Err(PokemonEngineError::TransactionFailed(format!("Payout {}: {}", signature, err)));
+                }
+                if status.satisfies_commitment(self.commitment) {
+                    return Ok(signature);
+                }
+            }
+            
+            if Instant::now() >= deadline {
+                return Err(PokemonEngineError::Solana(format!(
+                    "Timed out waiting for payout {} to confirm",
+                    signature
+                )));
+            }
+            tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+        }
     }
     
     /// Get token balance for a wallet address
     pub async fn get_token_balance(&self, wallet_address: &str) -> Result<u64> {
         let pubkey = Pubkey::from_str(wallet_address)
-            .map_err(|e| PokemonEngineError::Solana(format!("Invalid wallet address: {}", e)))?;
+            .map_err(|e| PokemonEngineError::InvalidWallet(format!("{}: {}", wallet_address, e)))?;
         
         let token_mint = self.token_mint
-            .ok_or_else(|| PokemonEngineError::Solana("Token mint not configured".to_string()))?;
+            .ok_or_else(|| PokemonEngineError::config("solana.token_mint", "Not configured"))?;
         
         // Get associated token account
         let _token_account = spl_associated_token_account::get_associated_token_address(
@@ -117,21 +475,608 @@ impl SolanaClient {
         Ok(0)
     }
     
+    /// Get the token balance currently held by the reward vault
+    ///
+    /// The reward vault is the token account that payouts are transferred from,
+    /// so its balance is read directly rather than through an associated account.
+    pub async fn get_vault_balance(&self) -> Result<u64> {
+        let vault = self.reward_vault
+            .ok_or_else(|| PokemonEngineError::config("solana.reward_vault", "Not configured"))?;
+        
+        let balance = self.rpc
+            .call(|c| Box::pin(c.get_token_account_balance(&vault)))
+            .await
+            .map_err(|e| PokemonEngineError::Solana(format!("Failed to fetch vault balance: {}", e)))?;
+        
+        balance.amount
+            .parse::<u64>()
+            .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid vault balance: {}", e)))
+    }
+    
+    /// Get outgoing transfers from the reward vault, newest first
+    ///
+    /// Walks the vault's transaction history back to `until` (exclusive) or until
+    /// `max_transactions` signatures have been scanned. Failed transactions and
+    /// deposits into the vault are skipped. The returned flag is `true` if the
+    /// scan stopped at `max_transactions` before reaching the end of history.
+    pub async fn get_vault_transfers(
+        &self,
+        until: Option<&str>,
+        max_transactions: usize,
+    ) -> Result<(Vec<VaultTransfer>, bool)> {
+        let vault = self.reward_vault
+            .ok_or_else(|| PokemonEngineError::config("solana.reward_vault", "Not configured"))?;
+        
+        let until = until
+            .map(Signature::from_str)
+            .transpose()
+            .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid signature: {}", e)))?;
+        
+        let mut transfers = Vec::new();
+        let mut scanned = 0;
+        let mut before = None;
+        
+        loop {
+            let limit = SIGNATURE_PAGE_SIZE.min(max_transactions.saturating_sub(scanned));
+            if limit == 0 {
+                return Ok((transfers, true));
+            }
+            
+            let page = self.rpc
+                .call(|c| {
+                    Box::pin(c.get_signatures_for_address_with_config(
+                        &vault,
+                        GetConfirmedSignaturesForAddress2Config {
+                            before,
+                            until,
+                            limit: Some(limit),
+                            commitment: Some(self.commitment),
+                        },
+                    ))
+                })
+                .await
+                .map_err(|e| PokemonEngineError::Solana(format!("Failed to fetch vault history: {}", e)))?;
+            
+            // Never trust the endpoint to honour `limit`
+            let page_len = page.len().min(limit);
+            scanned += page_len;
+            
+            for status in page.into_iter().take(page_len) {
+                let signature = Signature::from_str(&status.signature)
+                    .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid signature: {}", e)))?;
+                before = Some(signature);
+                
+                if status.err.is_some() {
+                    continue;
+                }
+                
+                if let Some(transfer) = self.fetch_vault_transfer(&vault, &signature).await? {
+                    transfers.push(transfer);
+                }
+            }
+            
+            if page_len < limit {
+                return Ok((transfers, false));
+            }
+        }
+    }
+    
+    /// Fetch a transaction and extract the vault transfer it contains, if any
+    async fn fetch_vault_transfer(
+        &self,
+        vault: &Pubkey,
+        signature: &Signature,
+    ) -> Result<Option<VaultTransfer>> {
+        let transaction = self.fetch_transaction(signature).await?;
+        Ok(parse_vault_transfer(vault, signature, &transaction))
+    }
+    
+    /// Fetch a confirmed transaction in binary encoding
+    async fn fetch_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(self.commitment),
+            max_supported_transaction_version: Some(0),
+        };
+        
+        self.rpc
+            .call(|c| Box::pin(c.get_transaction_with_config(signature, config)))
+            .await
+            .map_err(|e| PokemonEngineError::Solana(format!("Failed to fetch transaction {}: {}", signature, e)))
+    }
+    
     /// Verify transaction signature
-    pub async fn verify_transaction(&self, _signature: &str) -> Result<bool> {
-        // In production, query RPC to verify transaction
-        // For now, return placeholder
-        Ok(true)
+    ///
+    /// Returns `true` if the transaction landed successfully at the client's commitment.
+    pub async fn verify_transaction(&self, signature: &str) -> Result<bool> {
+        let signature = Signature::from_str(signature)
+            .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid signature: {}", e)))?;
+        
+        let signatures = [signature];
+        let statuses = self.rpc
+            .call(|c| Box::pin(c.get_signature_statuses(&signatures)))
+            .await
+            .map_err(|e| PokemonEngineError::Solana(format!("Failed to fetch signature status: {}", e)))?;
+        
+        Ok(statuses
+            .value
+            .into_iter()
+            .next()
+            .flatten()
+            .is_some_and(|status| status.err.is_none() && status.satisfies_commitment(self.commitment)))
+    }
+    
+    /// Get the fee charged for a confirmed transaction, from its metadata
+    pub async fn transaction_fee(&self, signature: &str) -> Result<u64> {
+        let signature = Signature::from_str(signature)
+            .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid signature: {}", e)))?;
+        
+        let transaction = self.fetch_transaction(&signature).await?;
+        transaction
+            .transaction
+            .meta
+            .map(|meta| meta.fee)
+            .ok_or_else(|| PokemonEngineError::Solana(format!("Transaction {} has no status metadata", signature)))
+    }
+    
+    /// Get the payout memo attached to a transaction, if any
+    pub async fn get_payout_memo(&self, signature: &str) -> Result<Option<PayoutMemo>> {
+        let signature = Signature::from_str(signature)
+            .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid signature: {}", e)))?;
+        
+        let transaction = self.fetch_transaction(&signature).await?;
+        Ok(transaction
+            .transaction
+            .transaction
+            .decode()
+            .and_then(|decoded| parse_payout_memo(&decoded)))
     }
+    
+    /// Verify that a transaction succeeded and its memo pays exactly these rewards
+    pub async fn verify_payout(&self, signature: &str, rewards: &[Reward]) -> Result<bool> {
+        if !self.verify_transaction(signature).await? {
+            return Ok(false);
+        }
+        
+        let reward_ids: Vec<Uuid> = rewards.iter().map(|r| r.id).collect();
+        Ok(self
+            .get_payout_memo(signature)
+            .await?
+            .is_some_and(|memo| memo.matches(&reward_ids)))
+    }
+}
+
+/// Parse a commitment level, rejecting anything but the three standard levels
+pub(crate) fn parse_commitment(commitment: &str) -> std::result::Result<CommitmentConfig, ConfigIssue> {
+    match commitment {
+        "finalized" => Ok(CommitmentConfig::finalized()),
+        "confirmed" => Ok(CommitmentConfig::confirmed()),
+        "processed" => Ok(CommitmentConfig::processed()),
+        other => Err(ConfigIssue::new(
+            "solana.commitment",
+            format!("Unknown commitment level \"{}\" (expected processed, confirmed or finalized)", other),
+        )),
+    }
+}
+
+/// Parse a public key setting, reporting a malformed key as an issue at `path`
+pub(crate) fn parse_pubkey(path: &str, value: &str) -> std::result::Result<Pubkey, ConfigIssue> {
+    Pubkey::from_str(value).map_err(|e| ConfigIssue::new(path, format!("Invalid public key: {}", e)))
+}
+
+/// Parse a required public key setting, recording an issue if it is missing or malformed
+fn parse_required_pubkey(path: &str, value: Option<&str>, issues: &mut Vec<ConfigIssue>) -> Option<Pubkey> {
+    let Some(value) = value else {
+        issues.push(ConfigIssue::new(path, "Not configured"));
+        return None;
+    };
+    parse_pubkey(path, value).map_err(|issue| issues.push(issue)).ok()
+}
+
+/// Validate the Solana settings against the fetched mint and vault accounts
+///
+/// `None` stands for an account that does not exist. The vault is only checked
+/// when the mint setting parses. Every problem is returned in one `Config` error.
+fn check_accounts(config: &Config, mint_account: Option<&Account>, vault_account: Option<&Account>) -> Result<()> {
+    let solana = &config.solana;
+    let mut issues = Vec::new();
+    
+    if let Err(issue) = parse_commitment(&solana.commitment) {
+        issues.push(issue);
+    }
+    let mint = parse_required_pubkey("solana.token_mint", solana.token_mint.as_deref(), &mut issues);
+    let vault = parse_required_pubkey("solana.reward_vault", solana.reward_vault.as_deref(), &mut issues);
+    
+    if let Some(mint) = mint {
+        issues.extend(mint_issues(
+            &mint,
+            mint_account,
+            config.token_decimals,
+            solana.expected_mint_authority.as_deref(),
+        ));
+        if let Some(vault) = vault {
+            issues.extend(vault_issues(&vault, vault_account, &mint));
+        }
+    }
+    
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(PokemonEngineError::Config(issues))
+    }
+}
+
+/// Check a fetched mint account's program, decimals and authority
+///
+/// `expected_authority` is a base58 pubkey, `"none"` for a fixed supply, or
+/// `None` to skip the authority check.
+fn mint_issues(
+    mint: &Pubkey,
+    account: Option<&Account>,
+    expected_decimals: u8,
+    expected_authority: Option<&str>,
+) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    
+    let Some(account) = account else {
+        issues.push(ConfigIssue::new("solana.token_mint", format!("Mint account {} not found", mint)));
+        return issues;
+    };
+    
+    if account.owner != spl_token::id() {
+        issues.push(ConfigIssue::new(
+            "solana.token_mint",
+            format!("Account {} is not owned by the SPL Token program", mint),
+        ));
+        return issues;
+    }
+    
+    let Ok(state) = spl_token::state::Mint::unpack(&account.data) else {
+        issues.push(ConfigIssue::new("solana.token_mint", format!("Account {} is not a token mint", mint)));
+        return issues;
+    };
+    
+    if state.decimals != expected_decimals {
+        issues.push(ConfigIssue::new(
+            "token_decimals",
+            format!("Configured {} decimals but mint {} has {}", expected_decimals, mint, state.decimals),
+        ));
+    }
+    
+    let actual_authority: Option<Pubkey> = state.mint_authority.into();
+    match expected_authority {
+        None => {}
+        Some("none") => {
+            if let Some(authority) = actual_authority {
+                issues.push(ConfigIssue::new(
+                    "solana.expected_mint_authority",
+                    format!("Expected a fixed supply but mint authority is {}", authority),
+                ));
+            }
+        }
+        Some(expected) => match parse_pubkey("solana.expected_mint_authority", expected) {
+            Err(issue) => issues.push(issue),
+            Ok(expected) if actual_authority != Some(expected) => {
+                let actual = actual_authority.map_or("none".to_string(), |a| a.to_string());
+                issues.push(ConfigIssue::new(
+                    "solana.expected_mint_authority",
+                    format!("Expected mint authority {} but found {}", expected, actual),
+                ));
+            }
+            Ok(_) => {}
+        },
+    }
+    
+    issues
+}
+
+/// Check that a fetched vault account is an unfrozen token account for the mint
+fn vault_issues(vault: &Pubkey, account: Option<&Account>, mint: &Pubkey) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    
+    let Some(account) = account else {
+        issues.push(ConfigIssue::new("solana.reward_vault", format!("Vault account {} not found", vault)));
+        return issues;
+    };
+    
+    let state = if account.owner == spl_token::id() {
+        spl_token::state::Account::unpack(&account.data).ok()
+    } else {
+        None
+    };
+    
+    let Some(state) = state else {
+        issues.push(ConfigIssue::new(
+            "solana.reward_vault",
+            format!("Account {} is not an SPL token account", vault),
+        ));
+        return issues;
+    };
+    
+    if state.mint != *mint {
+        issues.push(ConfigIssue::new(
+            "solana.reward_vault",
+            format!("Vault {} holds mint {} instead of {}", vault, state.mint, mint),
+        ));
+    }
+    
+    if state.is_frozen() {
+        issues.push(ConfigIssue::new("solana.reward_vault", format!("Vault {} is frozen", vault)));
+    }
+    
+    issues
 }
 
-/// Helper function to convert lamports to POKE amount
-pub fn lamports_to_poke(lamports: u64) -> f64 {
-    lamports as f64 / 1_000_000_000.0
+/// Extract the outgoing vault transfer from a confirmed transaction
+fn parse_vault_transfer(
+    vault: &Pubkey,
+    signature: &Signature,
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Option<VaultTransfer> {
+    let meta = transaction.transaction.meta.as_ref()?;
+    if meta.err.is_some() {
+        return None;
+    }
+    
+    let decoded = transaction.transaction.transaction.decode()?;
+    let mut account_keys: Vec<Pubkey> = decoded.message.static_account_keys().to_vec();
+    let loaded: Option<UiLoadedAddresses> = meta.loaded_addresses.clone().into();
+    if let Some(loaded) = loaded {
+        for key in loaded.writable.iter().chain(loaded.readonly.iter()) {
+            account_keys.push(Pubkey::from_str(key).ok()?);
+        }
+    }
+    
+    let pre = Option::<Vec<UiTransactionTokenBalance>>::from(meta.pre_token_balances.clone()).unwrap_or_default();
+    let post = Option::<Vec<UiTransactionTokenBalance>>::from(meta.post_token_balances.clone()).unwrap_or_default();
+    let balance_of = |balances: &[UiTransactionTokenBalance], index: u8| -> u64 {
+        balances
+            .iter()
+            .find(|b| b.account_index == index)
+            .and_then(|b| b.ui_token_amount.amount.parse().ok())
+            .unwrap_or(0)
+    };
+    
+    let vault_index = u8::try_from(account_keys.iter().position(|k| k == vault)?).ok()?;
+    let vault_pre = balance_of(&pre, vault_index);
+    let vault_post = balance_of(&post, vault_index);
+    if vault_post >= vault_pre {
+        // Deposit or unrelated transaction touching the vault
+        return None;
+    }
+    
+    let recipients = post
+        .iter()
+        .filter(|b| b.account_index != vault_index)
+        .filter(|b| {
+            let after: u64 = b.ui_token_amount.amount.parse().unwrap_or(0);
+            after > balance_of(&pre, b.account_index)
+        })
+        .filter_map(|b| Option::<String>::from(b.owner.clone()))
+        .collect();
+    
+    Some(VaultTransfer {
+        signature: signature.to_string(),
+        slot: transaction.slot,
+        block_time: transaction.block_time.and_then(|t| DateTime::from_timestamp(t, 0)),
+        amount: vault_pre - vault_post,
+        recipients,
+        memo: parse_payout_memo(&decoded),
+    })
 }
 
-/// Helper function to convert POKE amount to lamports
-pub fn poke_to_lamports(poke: f64) -> u64 {
-    (poke * 1_000_000_000.0) as u64
+/// Extract the payout memo from a transaction's memo instructions
+fn parse_payout_memo(transaction: &VersionedTransaction) -> Option<PayoutMemo> {
+    let account_keys = transaction.message.static_account_keys();
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .filter(|ix| {
+            account_keys
+                .get(ix.program_id_index as usize)
+                .is_some_and(|program| *program == spl_memo::id() || *program == spl_memo::v1::id())
+        })
+        .filter_map(|ix| std::str::from_utf8(&ix.data).ok())
+        .find_map(PayoutMemo::parse)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(count: usize) -> Vec<Uuid> {
+        (0..count).map(|_| Uuid::new_v4()).collect()
+    }
+
+    fn token_account(data: Vec<u8>) -> Account {
+        Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    fn mint_account(decimals: u8, authority: Option<Pubkey>) -> Account {
+        let state = spl_token::state::Mint {
+            mint_authority: authority.into(),
+            supply: 0,
+            decimals,
+            is_initialized: true,
+            freeze_authority: None.into(),
+        };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint::pack(state, &mut data).unwrap();
+        token_account(data)
+    }
+
+    fn vault_account(mint: Pubkey) -> Account {
+        let state = spl_token::state::Account {
+            mint,
+            owner: Pubkey::new_unique(),
+            amount: 0,
+            delegate: None.into(),
+            state: spl_token::state::AccountState::Initialized,
+            is_native: None.into(),
+            delegated_amount: 0,
+            close_authority: None.into(),
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(state, &mut data).unwrap();
+        token_account(data)
+    }
+
+    fn paths(issues: &[ConfigIssue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.path.as_str()).collect()
+    }
+
+    fn solana_config(mint: &Pubkey, vault: &Pubkey, authority: &Pubkey) -> Config {
+        let mut config = Config::default();
+        config.solana.token_mint = Some(mint.to_string());
+        config.solana.reward_vault = Some(vault.to_string());
+        config.solana.expected_mint_authority = Some(authority.to_string());
+        config
+    }
+
+    #[test]
+    fn small_batches_are_listed_inline() {
+        let reward_ids = ids(MAX_INLINE_MEMO_REWARDS);
+        let memo = PayoutMemo::for_rewards(&reward_ids);
+        assert_eq!(memo, PayoutMemo::Rewards(reward_ids.clone()));
+
+        let encoded = memo.encode();
+        assert!(encoded.starts_with(MEMO_REWARDS_PREFIX));
+        assert_eq!(PayoutMemo::parse(&encoded), Some(memo.clone()));
+
+        let mut reversed = reward_ids;
+        reversed.reverse();
+        assert!(memo.matches(&reversed));
+        assert!(!memo.matches(&reversed[1..]));
+    }
+
+    #[test]
+    fn large_batches_are_hashed() {
+        let reward_ids = ids(MAX_INLINE_MEMO_REWARDS + 1);
+        let memo = PayoutMemo::for_rewards(&reward_ids);
+        assert_eq!(memo, PayoutMemo::BatchHash(PayoutMemo::batch_hash(&reward_ids)));
+
+        let encoded = memo.encode();
+        assert!(encoded.starts_with(MEMO_BATCH_PREFIX));
+        assert_eq!(PayoutMemo::parse(&encoded), Some(memo.clone()));
+
+        let mut reversed = reward_ids;
+        reversed.reverse();
+        assert!(memo.matches(&reversed));
+        assert!(!memo.matches(&reversed[1..]));
+    }
+
+    #[test]
+    fn foreign_memos_are_ignored() {
+        assert_eq!(PayoutMemo::parse("hello"), None);
+        assert_eq!(PayoutMemo::parse(MEMO_BATCH_PREFIX), None);
+        assert_eq!(PayoutMemo::parse("poke:r:not-a-uuid"), None);
+    }
+
+    #[test]
+    fn matching_accounts_pass() {
+        let (mint, vault, authority) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let config = solana_config(&mint, &vault, &authority);
+        let mint_state = mint_account(config.token_decimals, Some(authority));
+
+        assert!(mint_issues(&mint, Some(&mint_state), config.token_decimals, Some(&authority.to_string())).is_empty());
+        assert!(vault_issues(&vault, Some(&vault_account(mint)), &mint).is_empty());
+        check_accounts(&config, Some(&mint_state), Some(&vault_account(mint))).unwrap();
+    }
+
+    #[test]
+    fn wrong_decimals_are_reported() {
+        let mint = Pubkey::new_unique();
+        let issues = mint_issues(&mint, Some(&mint_account(6, None)), 9, None);
+        assert_eq!(paths(&issues), ["token_decimals"]);
+        assert!(issues[0].message.contains("has 6"));
+    }
+
+    #[test]
+    fn unexpected_mint_authority_is_reported() {
+        let mint = Pubkey::new_unique();
+        let expected = Pubkey::new_unique();
+        let actual = Pubkey::new_unique();
+
+        let issues = mint_issues(&mint, Some(&mint_account(9, Some(actual))), 9, Some(&expected.to_string()));
+        assert_eq!(paths(&issues), ["solana.expected_mint_authority"]);
+        assert!(issues[0].message.contains(&actual.to_string()));
+
+        let issues = mint_issues(&mint, Some(&mint_account(9, Some(actual))), 9, Some("none"));
+        assert_eq!(paths(&issues), ["solana.expected_mint_authority"]);
+
+        assert!(mint_issues(&mint, Some(&mint_account(9, None)), 9, Some("none")).is_empty());
+    }
+
+    #[test]
+    fn missing_or_foreign_mint_is_reported() {
+        let mint = Pubkey::new_unique();
+        assert_eq!(paths(&mint_issues(&mint, None, 9, None)), ["solana.token_mint"]);
+
+        let mut foreign = mint_account(9, None);
+        foreign.owner = Pubkey::new_unique();
+        assert_eq!(paths(&mint_issues(&mint, Some(&foreign), 9, None)), ["solana.token_mint"]);
+    }
+
+    #[test]
+    fn vault_that_is_not_a_token_account_is_reported() {
+        let (mint, vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut foreign = vault_account(mint);
+        foreign.owner = Pubkey::new_unique();
+        let issues = vault_issues(&vault, Some(&foreign), &mint);
+        assert_eq!(paths(&issues), ["solana.reward_vault"]);
+        assert!(issues[0].message.contains("not an SPL token account"));
+
+        // A mint is owned by the token program but is not a token account
+        let issues = vault_issues(&vault, Some(&mint_account(9, None)), &mint);
+        assert!(issues[0].message.contains("not an SPL token account"));
+
+        assert_eq!(paths(&vault_issues(&vault, None, &mint)), ["solana.reward_vault"]);
+    }
+
+    #[test]
+    fn vault_for_the_wrong_mint_is_reported() {
+        let (mint, other, vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let issues = vault_issues(&vault, Some(&vault_account(other)), &mint);
+        assert_eq!(paths(&issues), ["solana.reward_vault"]);
+        assert!(issues[0].message.contains(&other.to_string()));
+    }
+
+    #[test]
+    fn every_problem_is_reported_in_one_config_error() {
+        let (mint, vault, authority) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut config = solana_config(&mint, &vault, &authority);
+        config.solana.commitment = "recent".to_string();
+
+        let result = check_accounts(
+            &config,
+            Some(&mint_account(config.token_decimals + 1, Some(Pubkey::new_unique()))),
+            Some(&vault_account(Pubkey::new_unique())),
+        );
+        let Err(PokemonEngineError::Config(issues)) = result else {
+            panic!("expected a config error, got {:?}", result);
+        };
+        assert_eq!(
+            paths(&issues),
+            ["solana.commitment", "token_decimals", "solana.expected_mint_authority", "solana.reward_vault"]
+        );
+    }
+
+    #[test]
+    fn malformed_settings_are_reported_before_the_chain_checks() {
+        let mut config = Config::default();
+        config.solana.token_mint = Some("not-a-key".to_string());
+
+        let result = check_accounts(&config, None, None);
+        let Err(PokemonEngineError::Config(issues)) = result else {
+            panic!("expected a config error, got {:?}", result);
+        };
+        assert_eq!(paths(&issues), ["solana.token_mint", "solana.reward_vault"]);
+        assert!(issues[0].message.starts_with("Invalid public key"));
+        assert_eq!(issues[1].message, "Not configured");
+    }
+}
@@ -0,0 +1,655 @@
+//! Conformance suite for `Storage` implementations
+//!
+//! Every check takes a fresh, empty storage and panics if the implementation
+//! deviates from the semantics of `MemoryStorage`. The easiest way to run the
+//! whole suite is the `storage_conformance_tests!` macro, which expands to one
+//! `#[tokio::test]` per check (the calling crate needs `tokio` with the
+//! `macros` and `rt-multi-thread` features):
+//!
+//! ```rust,ignore
+//! mod my_storage {
+//!     pokemon_engine::storage_conformance_tests!(|| MyStorage::connect_test_db());
+//! }
+//! ```
+//!
+//! Semantics pinned down by the suite:
+//! - Per-player queries only return that player's rewards, in any order.
+//! - `create_reward` with an existing id replaces the reward, moving it if
+//!   the player changed.
+//! - `mark_reward_claimed` and `set_transaction_signature` fail with
+//!   `ReasonCode::NotFound` for an unknown reward id.
+//! - `mark_all_rewards_claimed` succeeds for a player with no rewards.
+//! - `update_daily_stats` and `update_login_streak` overwrite existing records.
+//! - `has_welcome_bonus` is `true` once the player has a welcome reward, and
+//!   stays `true` after it is claimed.
+//! - `query_rewards` orders by `(timestamp, id)` and pages never skip or
+//!   repeat a reward.
+//! - `export` visits every reward, daily stats record, login streak and payout
+//!   once, payouts after every reward.
+//! - `restore_payout` stores a payout as is, without claiming its rewards.
+//! - `enqueue_payout` claims the payout's rewards and stores the payout in one
+//!   step, or fails and changes nothing: `ReasonCode::Duplicate` if a reward
+//!   is already claimed or the payout id exists, `ReasonCode::NotFound` if a
+//!   reward is unknown or belongs to another player.
+//! - `update_payout` fails with `ReasonCode::NotFound` for an unknown payout.
+//! - `due_payouts` returns pending payouts due by the given time, ordered by
+//!   `(next_attempt_at, id)`.
+
+use crate::amount::Amount;
+use crate::error::PokemonEngineError;
+use crate::models::{DailyStats, GameType, LoginStreak, PayoutRequest, PayoutStatus, Reward};
+use crate::reason::ReasonCode;
+use crate::storage::{snapshot, MemoryStorage, RewardQuery, RewardStatus, SortOrder, Storage};
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Number of concurrent writers used by the concurrency checks
+const CONCURRENT_WRITERS: usize = 32;
+
+fn reward(player_id: &str, game: GameType, amount: u64) -> Reward {
+    Reward {
+        id: Uuid::new_v4(),
+        player_id: player_id.to_string(),
+        game,
+        amount: Amount::from_lamports(amount),
+        timestamp: Utc::now(),
+        claimed: false,
+        game_data: serde_json::json!({ "score": 1500, "nested": { "tags": ["a", "b"] } }),
+        transaction_signature: None,
+        config_version: Some("3f2a9c1d0b7e4f65".to_string()),
+    }
+}
+
+fn stats(player_id: &str, date: NaiveDate, flypoke: u64, battle: u64, login: u64) -> DailyStats {
+    DailyStats {
+        player_id: player_id.to_string(),
+        date,
+        flypoke: Amount::from_lamports(flypoke),
+        battle: Amount::from_lamports(battle),
+        login: Amount::from_lamports(login),
+        total: Amount::from_lamports(flypoke + battle + login),
+    }
+}
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 1, day).expect("valid date")
+}
+
+/// Compare rewards by their serialized form, ignoring order
+fn assert_same_rewards(actual: &[Reward], expected: &[&Reward], context: &str) {
+    let mut actual: Vec<serde_json::Value> =
+        actual.iter().map(|r| serde_json::to_value(r).unwrap()).collect();
+    let mut expected: Vec<serde_json::Value> =
+        expected.iter().map(|r| serde_json::to_value(r).unwrap()).collect();
+    let key = |v: &serde_json::Value| v["id"].as_str().unwrap_or_default().to_string();
+    actual.sort_by_key(key);
+    expected.sort_by_key(key);
+    assert_eq!(actual, expected, "{}", context);
+}
+
+fn assert_same_payout(actual: Option<PayoutRequest>, expected: &PayoutRequest, context: &str) {
+    assert_eq!(
+        serde_json::to_value(actual).unwrap(),
+        serde_json::to_value(Some(expected)).unwrap(),
+        "{}",
+        context
+    );
+}
+
+fn assert_error<T: std::fmt::Debug>(result: crate::error::Result<T>, reason: ReasonCode, context: &str) {
+    match result {
+        Err(err) if err.reason_code() == reason => {}
+        other => panic!("{}: expected a {} error, got {:?}", context, reason, other),
+    }
+}
+
+/// Rewards round-trip unchanged and are partitioned by player
+pub async fn create_and_get_rewards<S: Storage>(storage: S) {
+    let mut first = reward("player1", GameType::FlyPoke, 10_000_000_000);
+    first.timestamp = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+    let mut second = reward("player1", GameType::Battle, 1_000_000_000_000_000_000);
+    second.transaction_signature = Some("signature".to_string());
+    second.claimed = true;
+    let other = reward("player10", GameType::Login, 20_000_000_000);
+
+    for r in [&first, &second, &other] {
+        storage.create_reward(r).await.unwrap();
+    }
+
+    assert_same_rewards(&storage.get_rewards("player1").await.unwrap(), &[&first, &second], "get_rewards(player1)");
+    assert_same_rewards(&storage.get_rewards("player10").await.unwrap(), &[&other], "get_rewards(player10)");
+    assert!(storage.get_rewards("player").await.unwrap().is_empty(), "unknown player has no rewards");
+}
+
+/// Creating a reward with an existing id replaces it, moving it if the player changed
+pub async fn create_reward_replaces_by_id<S: Storage>(storage: S) {
+    let original = reward("player1", GameType::Battle, 10);
+    storage.create_reward(&original).await.unwrap();
+
+    let mut moved = original.clone();
+    moved.player_id = "player2".to_string();
+    moved.amount = Amount::from_lamports(30);
+    storage.create_reward(&moved).await.unwrap();
+
+    assert!(storage.get_rewards("player1").await.unwrap().is_empty(), "moved away from player1");
+    assert!(storage.get_pending_rewards("player1").await.unwrap().is_empty(), "no longer pending for player1");
+    assert_same_rewards(&storage.get_rewards("player2").await.unwrap(), &[&moved], "moved to player2");
+    assert_eq!(storage.get_total_pending_amount().await.unwrap(), 30, "pending total counts it once");
+}
+
+/// Player ids are opaque strings, including unicode and separators
+pub async fn player_ids_are_opaque<S: Storage>(storage: S) {
+    let ids = ["", "a:b", "a", "a\u{0}b", "pemain-ñ-🎮", "a/b/../c"];
+    let rewards: Vec<Reward> = ids.iter().map(|id| reward(id, GameType::Battle, 1)).collect();
+    for r in &rewards {
+        storage.create_reward(r).await.unwrap();
+    }
+
+    for (id, r) in ids.iter().zip(&rewards) {
+        assert_same_rewards(&storage.get_rewards(id).await.unwrap(), &[r], &format!("get_rewards({:?})", id));
+    }
+}
+
+/// Pending rewards exclude claimed ones
+pub async fn pending_rewards_exclude_claimed<S: Storage>(storage: S) {
+    let pending = reward("player1", GameType::FlyPoke, 10);
+    let mut claimed = reward("player1", GameType::Battle, 20);
+    claimed.claimed = true;
+    storage.create_reward(&pending).await.unwrap();
+    storage.create_reward(&claimed).await.unwrap();
+
+    assert_same_rewards(&storage.get_pending_rewards("player1").await.unwrap(), &[&pending], "get_pending_rewards");
+    assert!(storage.get_pending_rewards("player2").await.unwrap().is_empty());
+}
+
+/// Queries filter, sort and page through one player's rewards
+pub async fn query_rewards<S: Storage>(storage: S) {
+    let mut rewards = Vec::new();
+    for i in 0..12u32 {
+        let game = if i % 3 == 0 { GameType::Battle } else { GameType::FlyPoke };
+        let mut r = reward("player1", game, (i as u64 + 1) * 1_000_000_000);
+        // Pairs share a timestamp so the id tie-break is exercised
+        r.timestamp = Utc.with_ymd_and_hms(2025, 1, 1 + i / 2, 12, 0, 0).unwrap();
+        r.claimed = i % 4 == 0;
+        storage.create_reward(&r).await.unwrap();
+        rewards.push(r);
+    }
+    storage.create_reward(&reward("player2", GameType::Battle, 1)).await.unwrap();
+
+    let key = |r: &Reward| (r.timestamp, r.id);
+    let mut oldest_first = rewards.clone();
+    oldest_first.sort_by_key(key);
+
+    for order in [SortOrder::OldestFirst, SortOrder::NewestFirst] {
+        let mut expected: Vec<Uuid> = oldest_first.iter().map(|r| r.id).collect();
+        if order == SortOrder::NewestFirst {
+            expected.reverse();
+        }
+
+        let mut query = RewardQuery { order, limit: 5, ..Default::default() };
+        let mut seen = Vec::new();
+        loop {
+            let page = storage.query_rewards("player1", &query).await.unwrap();
+            assert!(page.rewards.len() <= 5, "page larger than limit");
+            seen.extend(page.rewards.iter().map(|r| r.id));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(seen, expected, "paging {:?}", order);
+    }
+
+    let query = RewardQuery {
+        game: Some(GameType::FlyPoke),
+        status: Some(RewardStatus::Pending),
+        from: Some(Utc.with_ymd_and_hms(2025, 1, 2, 0, 0, 0).unwrap()),
+        until: Some(Utc.with_ymd_and_hms(2025, 1, 6, 0, 0, 0).unwrap()),
+        min_amount: Some(Amount::from_lamports(4_000_000_000)),
+        order: SortOrder::OldestFirst,
+        ..Default::default()
+    };
+    let expected: Vec<Uuid> = oldest_first
+        .iter()
+        .filter(|r| {
+            r.game == GameType::FlyPoke
+                && !r.claimed
+                && r.timestamp >= query.from.unwrap()
+                && r.timestamp < query.until.unwrap()
+                && r.amount >= Amount::from_lamports(4_000_000_000)
+        })
+        .map(|r| r.id)
+        .collect();
+    assert!(!expected.is_empty());
+    let page = storage.query_rewards("player1", &query).await.unwrap();
+    assert_eq!(page.rewards.iter().map(|r| r.id).collect::<Vec<_>>(), expected, "filtered query");
+    assert!(page.next_cursor.is_none());
+
+    let claimed = RewardQuery { status: Some(RewardStatus::Claimed), ..Default::default() };
+    let page = storage.query_rewards("player1", &claimed).await.unwrap();
+    assert_eq!(page.rewards.len(), rewards.iter().filter(|r| r.claimed).count());
+
+    let unknown = storage.query_rewards("nobody", &RewardQuery::default()).await.unwrap();
+    assert!(unknown.rewards.is_empty() && unknown.next_cursor.is_none());
+
+    let bad_cursor = RewardQuery { cursor: Some("not a cursor".to_string()), ..Default::default() };
+    assert!(matches!(
+        storage.query_rewards("player1", &bad_cursor).await,
+        Err(PokemonEngineError::InvalidQuery(_))
+    ));
+}
+
+/// Claiming one reward leaves the others untouched; unknown ids are errors
+pub async fn mark_reward_claimed<S: Storage>(storage: S) {
+    let mut target = reward("player1", GameType::FlyPoke, 10);
+    let untouched = reward("player1", GameType::Battle, 20);
+    storage.create_reward(&target).await.unwrap();
+    storage.create_reward(&untouched).await.unwrap();
+
+    storage.mark_reward_claimed(&target.id).await.unwrap();
+    // Claiming twice is not an error
+    storage.mark_reward_claimed(&target.id).await.unwrap();
+    target.claimed = true;
+
+    assert_same_rewards(&storage.get_rewards("player1").await.unwrap(), &[&target, &untouched], "after mark_reward_claimed");
+    assert_error(
+        storage.mark_reward_claimed(&Uuid::new_v4()).await,
+        ReasonCode::NotFound,
+        "mark_reward_claimed(unknown)",
+    );
+}
+
+/// Claiming all rewards only affects the given player
+pub async fn mark_all_rewards_claimed<S: Storage>(storage: S) {
+    let rewards: Vec<Reward> = (0..3).map(|i| reward("player1", GameType::FlyPoke, i)).collect();
+    let other = reward("player2", GameType::FlyPoke, 5);
+    for r in rewards.iter().chain([&other]) {
+        storage.create_reward(r).await.unwrap();
+    }
+
+    storage.mark_all_rewards_claimed("player1").await.unwrap();
+    storage.mark_all_rewards_claimed("nobody").await.unwrap();
+
+    assert!(storage.get_pending_rewards("player1").await.unwrap().is_empty());
+    assert!(storage.get_rewards("player1").await.unwrap().iter().all(|r| r.claimed));
+    assert_same_rewards(&storage.get_pending_rewards("player2").await.unwrap(), &[&other], "other player untouched");
+}
+
+/// Transaction signatures are recorded and listed across players
+pub async fn transaction_signatures<S: Storage>(storage: S) {
+    let mut first = reward("player1", GameType::FlyPoke, 10);
+    let mut second = reward("player2", GameType::Battle, 20);
+    let unpaid = reward("player1", GameType::Login, 30);
+    for r in [&first, &second, &unpaid] {
+        storage.create_reward(r).await.unwrap();
+    }
+    assert!(storage.get_paid_rewards().await.unwrap().is_empty(), "no paid rewards yet");
+
+    storage.set_transaction_signature(&first.id, "sig1").await.unwrap();
+    storage.set_transaction_signature(&second.id, "sig1").await.unwrap();
+    storage.set_transaction_signature(&second.id, "sig2").await.unwrap();
+    first.transaction_signature = Some("sig1".to_string());
+    second.transaction_signature = Some("sig2".to_string());
+
+    assert_same_rewards(&storage.get_paid_rewards().await.unwrap(), &[&first, &second], "get_paid_rewards");
+    assert_error(
+        storage.set_transaction_signature(&Uuid::new_v4(), "sig").await,
+        ReasonCode::NotFound,
+        "set_transaction_signature(unknown)",
+    );
+}
+
+/// The pending total sums unclaimed rewards of every player
+pub async fn total_pending_amount<S: Storage>(storage: S) {
+    assert_eq!(storage.get_total_pending_amount().await.unwrap(), 0, "empty storage");
+
+    let mut claimed = reward("player1", GameType::Battle, 1_000);
+    claimed.claimed = true;
+    for r in [
+        reward("player1", GameType::FlyPoke, 10),
+        reward("player2", GameType::FlyPoke, 20),
+        claimed,
+    ] {
+        storage.create_reward(&r).await.unwrap();
+    }
+    assert_eq!(storage.get_total_pending_amount().await.unwrap(), 30);
+
+    storage.mark_all_rewards_claimed("player2").await.unwrap();
+    assert_eq!(storage.get_total_pending_amount().await.unwrap(), 10);
+}
+
+/// The unpaid total sums every reward without a transaction signature, claimed or not
+pub async fn total_unpaid_amount<S: Storage>(storage: S) {
+    assert_eq!(storage.get_total_unpaid_amount().await.unwrap(), 0, "empty storage");
+
+    let mut claimed = reward("player1", GameType::Battle, 1_000);
+    claimed.claimed = true;
+    let mut paid = reward("player1", GameType::Battle, 5_000);
+    paid.claimed = true;
+    paid.transaction_signature = Some("signature".to_string());
+    let queued = reward("player2", GameType::FlyPoke, 20);
+    for r in [&reward("player1", GameType::FlyPoke, 10), &claimed, &paid, &queued] {
+        storage.create_reward(r).await.unwrap();
+    }
+    assert_eq!(storage.get_total_unpaid_amount().await.unwrap(), 1_030);
+
+    let payout = PayoutRequest::new("player2", "wallet", std::slice::from_ref(&queued), Utc::now()).unwrap();
+    storage.enqueue_payout(&payout).await.unwrap();
+    assert_eq!(storage.get_total_pending_amount().await.unwrap(), 10, "queued is no longer pending");
+    assert_eq!(storage.get_total_unpaid_amount().await.unwrap(), 1_030, "but still unpaid");
+
+    storage.set_transaction_signature(&queued.id, "signature").await.unwrap();
+    assert_eq!(storage.get_total_unpaid_amount().await.unwrap(), 1_010);
+}
+
+/// Daily stats are keyed by player and date and overwritten on update
+pub async fn daily_stats<S: Storage>(storage: S) {
+    assert!(storage.get_daily_stats("player1", date(1)).await.unwrap().is_none());
+
+    let day1 = stats("player1", date(1), 10, 20, 30);
+    let day2 = stats("player1", date(2), 1, 2, 3);
+    let other = stats("player2", date(1), 5, 5, 5);
+    for s in [&day1, &day2, &other] {
+        storage.update_daily_stats(s).await.unwrap();
+    }
+
+    let updated = stats("player1", date(1), 100, 20, 30);
+    storage.update_daily_stats(&updated).await.unwrap();
+
+    let json = |s: Option<DailyStats>| serde_json::to_value(s).unwrap();
+    assert_eq!(json(storage.get_daily_stats("player1", date(1)).await.unwrap()), json(Some(updated)));
+    assert_eq!(json(storage.get_daily_stats("player1", date(2)).await.unwrap()), json(Some(day2)));
+    assert_eq!(json(storage.get_daily_stats("player2", date(1)).await.unwrap()), json(Some(other)));
+    assert!(storage.get_daily_stats("player2", date(2)).await.unwrap().is_none());
+}
+
+/// Login streaks are keyed by player and overwritten on update
+pub async fn login_streaks<S: Storage>(storage: S) {
+    assert!(storage.get_login_streak("player1").await.unwrap().is_none());
+
+    let streak = LoginStreak {
+        player_id: "player1".to_string(),
+        current_streak: 3,
+        last_login_date: date(3),
+    };
+    storage.update_login_streak(&streak).await.unwrap();
+
+    let updated = LoginStreak {
+        current_streak: 4,
+        last_login_date: date(4),
+        ..streak
+    };
+    storage.update_login_streak(&updated).await.unwrap();
+
+    let json = |s: Option<LoginStreak>| serde_json::to_value(s).unwrap();
+    assert_eq!(json(storage.get_login_streak("player1").await.unwrap()), json(Some(updated)));
+    assert!(storage.get_login_streak("player2").await.unwrap().is_none());
+}
+
+/// A welcome reward counts as having the welcome bonus, claimed or not
+pub async fn welcome_bonus<S: Storage>(storage: S) {
+    assert!(!storage.has_welcome_bonus("player1").await.unwrap(), "no rewards");
+
+    storage.create_reward(&reward("player1", GameType::Login, 10)).await.unwrap();
+    assert!(!storage.has_welcome_bonus("player1").await.unwrap(), "other games don't count");
+
+    storage.create_reward(&reward("player1", GameType::Welcome, 100)).await.unwrap();
+    assert!(storage.has_welcome_bonus("player1").await.unwrap(), "unclaimed welcome reward");
+    assert!(!storage.has_welcome_bonus("player2").await.unwrap(), "other player");
+
+    storage.mark_all_rewards_claimed("player1").await.unwrap();
+    assert!(storage.has_welcome_bonus("player1").await.unwrap(), "claimed welcome reward");
+}
+
+/// `record_reward` persists the reward and whichever state is provided
+pub async fn record_reward<S: Storage>(storage: S) {
+    let today = date(5);
+    let first = reward("player1", GameType::Login, 20);
+    let first_stats = stats("player1", today, 0, 0, 20);
+    let streak = LoginStreak {
+        player_id: "player1".to_string(),
+        current_streak: 2,
+        last_login_date: today,
+    };
+    storage.record_reward(&first, Some(&first_stats), Some(&streak)).await.unwrap();
+
+    let second = reward("player1", GameType::Welcome, 100);
+    storage.record_reward(&second, None, None).await.unwrap();
+
+    assert_same_rewards(&storage.get_rewards("player1").await.unwrap(), &[&first, &second], "recorded rewards");
+    assert_eq!(
+        serde_json::to_value(storage.get_daily_stats("player1", today).await.unwrap()).unwrap(),
+        serde_json::to_value(Some(first_stats)).unwrap(),
+    );
+    assert_eq!(
+        serde_json::to_value(storage.get_login_streak("player1").await.unwrap()).unwrap(),
+        serde_json::to_value(Some(streak)).unwrap(),
+    );
+}
+
+/// Payouts are enqueued with their claims, scheduled and updated
+pub async fn payout_outbox<S: Storage>(storage: S) {
+    let now = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+    let rewards: Vec<Reward> = (1..=3).map(|i| reward("player1", GameType::FlyPoke, i * 10)).collect();
+    let kept = reward("player1", GameType::Battle, 1_000);
+    for r in rewards.iter().chain([&kept]) {
+        storage.create_reward(r).await.unwrap();
+    }
+    assert!(storage.get_payout(&Uuid::new_v4()).await.unwrap().is_none(), "unknown payout");
+
+    let mut first = PayoutRequest::new("player1", "wallet1", &rewards[..2], now).unwrap();
+    let mut second = PayoutRequest::new("player1", "wallet1", &rewards[2..], now - Duration::seconds(1)).unwrap();
+    storage.enqueue_payout(&first).await.unwrap();
+    storage.enqueue_payout(&second).await.unwrap();
+
+    assert_same_rewards(&storage.get_pending_rewards("player1").await.unwrap(), &[&kept], "claimed by enqueue");
+    assert_eq!(storage.get_total_pending_amount().await.unwrap(), 1_000);
+    assert_same_payout(storage.get_payout(&first.id).await.unwrap(), &first, "get_payout");
+
+    let due = storage.due_payouts(now, 10).await.unwrap();
+    assert_eq!(due.iter().map(|p| p.id).collect::<Vec<_>>(), vec![second.id, first.id], "due order");
+    assert_eq!(storage.due_payouts(now, 1).await.unwrap().len(), 1, "limit");
+    assert!(storage.due_payouts(now - Duration::seconds(2), 10).await.unwrap().is_empty(), "not yet due");
+
+    first.attempts = 1;
+    first.next_attempt_at = now + Duration::seconds(30);
+    first.signature = Some("sig1".to_string());
+    first.last_valid_block_height = Some(1_000_000);
+    first.last_error = Some("timed out".to_string());
+    first.sent_at = Some(now);
+    storage.update_payout(&first).await.unwrap();
+    assert_same_payout(storage.get_payout(&first.id).await.unwrap(), &first, "after update");
+
+    second.status = PayoutStatus::Sent;
+    storage.update_payout(&second).await.unwrap();
+    let due = storage.due_payouts(now + Duration::minutes(1), 10).await.unwrap();
+    assert_eq!(due.iter().map(|p| p.id).collect::<Vec<_>>(), vec![first.id], "rescheduled and sent");
+
+    let unknown = PayoutRequest::new("player1", "wallet1", &[], now).unwrap();
+    assert_error(storage.update_payout(&unknown).await, ReasonCode::NotFound, "update_payout(unknown)");
+}
+
+/// A payout that can't be enqueued leaves rewards and outbox unchanged
+pub async fn payout_enqueue_is_atomic<S: Storage>(storage: S) {
+    let now = Utc::now();
+    let pending = reward("player1", GameType::FlyPoke, 10);
+    let mut claimed = reward("player1", GameType::Battle, 20);
+    claimed.claimed = true;
+    let other = reward("player2", GameType::FlyPoke, 30);
+    for r in [&pending, &claimed, &other] {
+        storage.create_reward(r).await.unwrap();
+    }
+
+    let attempts = [
+        (
+            PayoutRequest::new("player1", "wallet1", &[pending.clone(), claimed.clone()], now).unwrap(),
+            ReasonCode::Duplicate,
+        ),
+        (
+            PayoutRequest::new("player1", "wallet1", &[pending.clone(), other.clone()], now).unwrap(),
+            ReasonCode::NotFound,
+        ),
+        (
+            PayoutRequest::new("player1", "wallet1", &[pending.clone(), reward("player1", GameType::Login, 5)], now).unwrap(),
+            ReasonCode::NotFound,
+        ),
+    ];
+    for (payout, reason) in &attempts {
+        assert_error(storage.enqueue_payout(payout).await, *reason, "enqueue_payout");
+        assert!(storage.get_payout(&payout.id).await.unwrap().is_none(), "payout not stored");
+    }
+    assert_same_rewards(&storage.get_pending_rewards("player1").await.unwrap(), &[&pending], "player1 untouched");
+    assert_same_rewards(&storage.get_pending_rewards("player2").await.unwrap(), &[&other], "player2 untouched");
+
+    let payout = PayoutRequest::new("player1", "wallet1", std::slice::from_ref(&pending), now).unwrap();
+    storage.enqueue_payout(&payout).await.unwrap();
+    let mut duplicate = PayoutRequest::new("player2", "wallet2", std::slice::from_ref(&other), now).unwrap();
+    duplicate.id = payout.id;
+    assert_error(storage.enqueue_payout(&duplicate).await, ReasonCode::Duplicate, "duplicate payout id");
+    assert_same_rewards(&storage.get_pending_rewards("player2").await.unwrap(), &[&other], "duplicate claimed nothing");
+    assert!(storage.due_payouts(now, 10).await.unwrap().iter().all(|p| p.player_id == "player1"));
+}
+
+/// Concurrent writers never lose rewards
+pub async fn concurrent_creates<S: Storage + 'static>(storage: S) {
+    let storage = Arc::new(storage);
+    let mut handles = Vec::new();
+    for i in 0..CONCURRENT_WRITERS {
+        let storage = Arc::clone(&storage);
+        handles.push(tokio::spawn(async move {
+            let player = format!("player{}", i % 4);
+            storage.create_reward(&reward(&player, GameType::FlyPoke, 1)).await.unwrap();
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    let mut count = 0;
+    for i in 0..4 {
+        count += storage.get_rewards(&format!("player{}", i)).await.unwrap().len();
+    }
+    assert_eq!(count, CONCURRENT_WRITERS);
+    assert_eq!(storage.get_total_pending_amount().await.unwrap(), CONCURRENT_WRITERS as u64);
+}
+
+/// Concurrent claims and creates leave every reward in a consistent state
+pub async fn concurrent_claims<S: Storage + 'static>(storage: S) {
+    let storage = Arc::new(storage);
+    for _ in 0..CONCURRENT_WRITERS {
+        storage.create_reward(&reward("player1", GameType::FlyPoke, 1)).await.unwrap();
+    }
+
+    let mut handles = Vec::new();
+    for i in 0..CONCURRENT_WRITERS {
+        let storage = Arc::clone(&storage);
+        handles.push(tokio::spawn(async move {
+            if i % 2 == 0 {
+                storage.mark_all_rewards_claimed("player1").await.unwrap();
+            } else {
+                storage.create_reward(&reward("player2", GameType::Battle, 1)).await.unwrap();
+            }
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    assert!(storage.get_pending_rewards("player1").await.unwrap().is_empty());
+    assert_eq!(storage.get_pending_rewards("player2").await.unwrap().len(), CONCURRENT_WRITERS / 2);
+}
+
+/// Export visits every record, and a snapshot of it loads back unchanged
+pub async fn export_snapshot<S: Storage>(storage: S) {
+    let mut claimed = reward("player1", GameType::Battle, 20);
+    claimed.claimed = true;
+    claimed.transaction_signature = Some("signature".to_string());
+    let mut rewards = [
+        reward("player1", GameType::FlyPoke, 10),
+        claimed,
+        reward("player2", GameType::Welcome, 100),
+        reward("player2", GameType::FlyPoke, 30),
+    ];
+    let daily = [stats("player1", date(1), 10, 20, 0), stats("player1", date(2), 0, 0, 5)];
+    let streak = LoginStreak {
+        player_id: "player2".to_string(),
+        current_streak: 4,
+        last_login_date: date(2),
+    };
+    for r in &rewards {
+        storage.create_reward(r).await.unwrap();
+    }
+    for s in &daily {
+        storage.update_daily_stats(s).await.unwrap();
+    }
+    storage.update_login_streak(&streak).await.unwrap();
+    let mut payout = PayoutRequest::new("player2", "wallet", &rewards[3..], Utc::now()).unwrap();
+    storage.enqueue_payout(&payout).await.unwrap();
+    rewards[3].claimed = true;
+    payout.attempts = 2;
+    payout.signature = Some("in-flight".to_string());
+    storage.update_payout(&payout).await.unwrap();
+
+    let mut buffer = Vec::new();
+    let summary = snapshot::export_snapshot(&storage, &mut buffer).await.unwrap();
+    assert_eq!(
+        summary,
+        snapshot::SnapshotSummary { rewards: 4, daily_stats: 2, login_streaks: 1, payouts: 1 },
+        "export counts"
+    );
+
+    let restored = MemoryStorage::new();
+    snapshot::import_snapshot(buffer.as_slice(), &restored).await.unwrap();
+    for player in ["player1", "player2"] {
+        let expected: Vec<&Reward> = rewards.iter().filter(|r| r.player_id == player).collect();
+        assert_same_rewards(&restored.get_rewards(player).await.unwrap(), &expected, "restored rewards");
+    }
+    for s in &daily {
+        let restored = restored.get_daily_stats(&s.player_id, s.date).await.unwrap();
+        assert_eq!(serde_json::to_value(restored).unwrap(), serde_json::to_value(Some(s)).unwrap());
+    }
+    let restored_streak = restored.get_login_streak("player2").await.unwrap();
+    assert_eq!(serde_json::to_value(restored_streak).unwrap(), serde_json::to_value(Some(&streak)).unwrap());
+    let restored_payout = restored.get_payout(&payout.id).await.unwrap();
+    assert_eq!(serde_json::to_value(restored_payout).unwrap(), serde_json::to_value(Some(&payout)).unwrap());
+}
+
+/// Generate one `#[tokio::test]` per conformance check for a storage factory
+///
+/// The factory is any expression callable with no arguments that returns a
+/// fresh, empty storage.
+#[macro_export]
+macro_rules! storage_conformance_tests {
+    ($factory:expr) => {
+        $crate::storage_conformance_tests!(@tests $factory;
+            create_and_get_rewards,
+            create_reward_replaces_by_id,
+            player_ids_are_opaque,
+            pending_rewards_exclude_claimed,
+            query_rewards,
+            mark_reward_claimed,
+            mark_all_rewards_claimed,
+            transaction_signatures,
+            total_pending_amount,
+            total_unpaid_amount,
+            daily_stats,
+            login_streaks,
+            welcome_bonus,
+            record_reward,
+            export_snapshot,
+            payout_outbox,
+            payout_enqueue_is_atomic,
+            concurrent_creates,
+            concurrent_claims,
+        );
+    };
+    (@tests $factory:expr; $($check:ident),* $(,)?) => {
+        $(
+            #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+            async fn $check() {
+                $crate::storage::conformance::$check(($factory)()).await;
+            }
+        )*
+    };
+}
@@ -0,0 +1,516 @@
+use crate::models::{Reward, DailyStats, LoginStreak, GameType, PayoutRequest, PayoutStatus};
+use crate::error::{Result, PokemonEngineError};
+use crate::storage::{add_unpaid, RewardPage, RewardQuery, Storage, StorageVisitor};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use uuid::Uuid;
+
+/// Shards per lock family unless configured otherwise
+const DEFAULT_SHARDS: usize = 64;
+
+/// Everything stored for one player
+#[derive(Default)]
+struct PlayerData {
+    rewards: Vec<Reward>, // In creation order
+    positions: HashMap<Uuid, usize>,
+    pending: usize,
+    welcome_rewards: usize, // Claimed or not
+    daily_stats: HashMap<NaiveDate, DailyStats>,
+    login_streak: Option<LoginStreak>,
+}
+
+impl PlayerData {
+    fn insert(&mut self, reward: &Reward) -> (u64, u64) {
+        match self.positions.get(&reward.id) {
+            Some(&index) => {
+                let previous = std::mem::replace(&mut self.rewards[index], reward.clone());
+                self.untrack(&previous);
+                self.track(reward);
+                (pending_amount(&previous), pending_amount(reward))
+            }
+            None => {
+                self.positions.insert(reward.id, self.rewards.len());
+                self.rewards.push(reward.clone());
+                self.track(reward);
+                (0, pending_amount(reward))
+            }
+        }
+    }
+
+    /// Mark one reward claimed, returning the amount no longer pending
+    fn claim(&mut self, reward_id: &Uuid) -> Option<u64> {
+        let index = *self.positions.get(reward_id)?;
+        let reward = &mut self.rewards[index];
+        if reward.claimed {
+            return Some(0);
+        }
+        reward.claimed = true;
+        self.pending -= 1;
+        Some(reward.amount.lamports())
+    }
+
+    fn track(&mut self, reward: &Reward) {
+        if !reward.claimed {
+            self.pending += 1;
+        }
+        if reward.game == GameType::Welcome {
+            self.welcome_rewards += 1;
+        }
+    }
+
+    fn untrack(&mut self, reward: &Reward) {
+        if !reward.claimed {
+            self.pending -= 1;
+        }
+        if reward.game == GameType::Welcome {
+            self.welcome_rewards -= 1;
+        }
+    }
+}
+
+/// Group of players sharing one lock
+#[derive(Default)]
+struct PlayerShard {
+    players: HashMap<String, PlayerData>,
+    pending_amount: u64,
+}
+
+impl PlayerShard {
+    /// Shard's pending total after removing `removed` and adding `added`
+    fn pending_after(&self, removed: u64, added: u64) -> Result<u64> {
+        self.pending_amount
+            .checked_sub(removed)
+            .and_then(|pending| pending.checked_add(added))
+            .ok_or_else(pending_overflow)
+    }
+
+    /// Pending amount of one of a player's rewards, 0 if it isn't stored
+    fn pending_of(&self, player_id: &str, reward_id: &Uuid) -> u64 {
+        self.players.get(player_id)
+            .and_then(|player| player.positions.get(reward_id).map(|&index| &player.rewards[index]))
+            .map_or(0, pending_amount)
+    }
+
+    /// Remove one of a player's rewards, leaving the pending total to the caller
+    fn remove(&mut self, player_id: &str, reward_id: &Uuid) {
+        let Some(player) = self.players.get_mut(player_id) else { return };
+        let Some(index) = player.positions.remove(reward_id) else { return };
+        let removed = player.rewards.remove(index);
+        player.untrack(&removed);
+        for position in player.positions.values_mut() {
+            if *position > index {
+                *position -= 1;
+            }
+        }
+    }
+}
+
+/// In-memory storage implementation (for testing or simple use cases)
+///
+/// Data is grouped per player and spread over independently locked shards,
+/// so per-player lookups only touch that player's rewards and writes for
+/// different players rarely contend. A separate sharded index maps reward
+/// ids to their owner for id-based updates. The payout outbox sits behind
+/// its own lock, always taken after a player shard.
+pub struct MemoryStorage {
+    players: Vec<RwLock<PlayerShard>>,
+    owners: Vec<RwLock<HashMap<Uuid, String>>>,
+    payouts: RwLock<HashMap<Uuid, PayoutRequest>>,
+    hasher: RandomState,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Create storage with a specific number of lock shards
+    pub fn with_shards(shards: usize) -> Self {
+        let shards = shards.max(1);
+        Self {
+            players: (0..shards).map(|_| RwLock::default()).collect(),
+            owners: (0..shards).map(|_| RwLock::default()).collect(),
+            payouts: RwLock::default(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn shard_index(&self, key: impl Hash) -> usize {
+        (self.hasher.hash_one(key) % self.players.len() as u64) as usize
+    }
+
+    fn read_players(&self, player_id: &str) -> RwLockReadGuard<'_, PlayerShard> {
+        read(&self.players[self.shard_index(player_id)])
+    }
+
+    fn write_players(&self, player_id: &str) -> RwLockWriteGuard<'_, PlayerShard> {
+        write(&self.players[self.shard_index(player_id)])
+    }
+
+    fn owner(&self, reward_id: &Uuid) -> Result<String> {
+        read(&self.owners[self.shard_index(reward_id)])
+            .get(reward_id)
+            .cloned()
+            .ok_or_else(|| PokemonEngineError::NotFound(format!("Reward {}", reward_id)))
+    }
+
+    /// Read a player's data, if any
+    fn with_player<T>(&self, player_id: &str, f: impl FnOnce(&PlayerData) -> T) -> Option<T> {
+        self.read_players(player_id).players.get(player_id).map(f)
+    }
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn create_reward(&self, reward: &Reward) -> Result<()> {
+        // Held until the reward is in place, so id lookups never see a dangling owner
+        let mut owners = write(&self.owners[self.shard_index(reward.id)]);
+        // Re-creating a reward under another player moves it
+        let previous_owner = owners.get(&reward.id).filter(|owner| **owner != reward.player_id).cloned();
+        let new_index = self.shard_index(reward.player_id.as_str());
+
+        // Every pending total is checked before anything changes, so an overflow leaves both players as they were
+        match previous_owner {
+            None => {
+                let mut shard = write(&self.players[new_index]);
+                let pending = shard.pending_after(shard.pending_of(&reward.player_id, &reward.id), pending_amount(reward))?;
+                shard.players.entry(reward.player_id.clone()).or_default().insert(reward);
+                shard.pending_amount = pending;
+            }
+            Some(previous_owner) => {
+                let old_index = self.shard_index(previous_owner.as_str());
+                if old_index == new_index {
+                    let mut shard = write(&self.players[new_index]);
+                    let pending = shard.pending_after(shard.pending_of(&previous_owner, &reward.id), pending_amount(reward))?;
+                    shard.remove(&previous_owner, &reward.id);
+                    shard.players.entry(reward.player_id.clone()).or_default().insert(reward);
+                    shard.pending_amount = pending;
+                } else {
+                    // Taken in index order, so two opposite moves can't deadlock
+                    let (mut first, mut second) = (
+                        write(&self.players[old_index.min(new_index)]),
+                        write(&self.players[old_index.max(new_index)]),
+                    );
+                    let (old, new) = if old_index < new_index { (&mut *first, &mut *second) } else { (&mut *second, &mut *first) };
+                    let old_pending = old.pending_after(old.pending_of(&previous_owner, &reward.id), 0)?;
+                    let new_pending = new.pending_after(new.pending_of(&reward.player_id, &reward.id), pending_amount(reward))?;
+                    old.remove(&previous_owner, &reward.id);
+                    old.pending_amount = old_pending;
+                    new.players.entry(reward.player_id.clone()).or_default().insert(reward);
+                    new.pending_amount = new_pending;
+                }
+            }
+        }
+        owners.insert(reward.id, reward.player_id.clone());
+        drop(owners);
+        Ok(())
+    }
+
+    async fn get_rewards(&self, player_id: &str) -> Result<Vec<Reward>> {
+        Ok(self
+            .with_player(player_id, |player| player.rewards.clone())
+            .unwrap_or_default())
+    }
+
+    async fn get_pending_rewards(&self, player_id: &str) -> Result<Vec<Reward>> {
+        Ok(self
+            .with_player(player_id, |player| {
+                if player.pending == 0 {
+                    return Vec::new();
+                }
+                player.rewards.iter().filter(|r| !r.claimed).cloned().collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn query_rewards(&self, player_id: &str, query: &RewardQuery) -> Result<RewardPage> {
+        self.with_player(player_id, |player| query.page(&player.rewards))
+            .unwrap_or_else(|| query.page(&[]))
+    }
+
+    async fn get_paid_rewards(&self) -> Result<Vec<Reward>> {
+        let mut paid = Vec::new();
+        for shard in &self.players {
+            let shard = read(shard);
+            for player in shard.players.values() {
+                paid.extend(
+                    player
+                        .rewards
+                        .iter()
+                        .filter(|r| r.transaction_signature.is_some())
+                        .cloned(),
+                );
+            }
+        }
+        Ok(paid)
+    }
+
+    async fn set_transaction_signature(&self, reward_id: &uuid::Uuid, signature: &str) -> Result<()> {
+        let player_id = self.owner(reward_id)?;
+        let mut shard = self.write_players(&player_id);
+        let player = shard.players.get_mut(&player_id);
+        match player.and_then(|p| p.positions.get(reward_id).map(|&i| &mut p.rewards[i])) {
+            Some(reward) => {
+                reward.transaction_signature = Some(signature.to_string());
+                Ok(())
+            }
+            None => Err(PokemonEngineError::NotFound(format!("Reward {}", reward_id))),
+        }
+    }
+
+    async fn get_total_pending_amount(&self) -> Result<u64> {
+        self.players
+            .iter()
+            .try_fold(0u64, |total, shard| total.checked_add(read(shard).pending_amount))
+            .ok_or_else(pending_overflow)
+    }
+
+    async fn get_total_unpaid_amount(&self) -> Result<u64> {
+        let mut total = 0u64;
+        for shard in &self.players {
+            let shard = read(shard);
+            for reward in shard.players.values().flat_map(|player| &player.rewards) {
+                if reward.transaction_signature.is_none() {
+                    total = add_unpaid(total, reward)?;
+                }
+            }
+        }
+        Ok(total)
+    }
+
+    async fn mark_reward_claimed(&self, reward_id: &uuid::Uuid) -> Result<()> {
+        let player_id = self.owner(reward_id)?;
+        let mut shard = self.write_players(&player_id);
+        let claimed = shard
+            .players
+            .get_mut(&player_id)
+            .and_then(|player| player.claim(reward_id))
+            .ok_or_else(|| PokemonEngineError::NotFound(format!("Reward {}", reward_id)))?;
+        shard.pending_amount = shard.pending_after(claimed, 0)?;
+        Ok(())
+    }
+
+    async fn mark_all_rewards_claimed(&self, player_id: &str) -> Result<()> {
+        let mut shard = self.write_players(player_id);
+        let Some(player) = shard.players.get_mut(player_id) else {
+            return Ok(());
+        };
+
+        let claimed = player
+            .rewards
+            .iter()
+            .filter(|r| !r.claimed)
+            .try_fold(0u64, |total, r| total.checked_add(r.amount.lamports()))
+            .ok_or_else(pending_overflow)?;
+        for reward in player.rewards.iter_mut() {
+            reward.claimed = true;
+        }
+        player.pending = 0;
+        shard.pending_amount = shard.pending_after(claimed, 0)?;
+        Ok(())
+    }
+
+    async fn get_daily_stats(&self, player_id: &str, date: NaiveDate) -> Result<Option<DailyStats>> {
+        Ok(self
+            .with_player(player_id, |player| player.daily_stats.get(&date).cloned())
+            .flatten())
+    }
+
+    async fn update_daily_stats(&self, stats: &DailyStats) -> Result<()> {
+        let mut shard = self.write_players(&stats.player_id);
+        shard
+            .players
+            .entry(stats.player_id.clone())
+            .or_default()
+            .daily_stats
+            .insert(stats.date, stats.clone());
+        Ok(())
+    }
+
+    async fn get_login_streak(&self, player_id: &str) -> Result<Option<LoginStreak>> {
+        Ok(self
+            .with_player(player_id, |player| player.login_streak.clone())
+            .flatten())
+    }
+
+    async fn update_login_streak(&self, streak: &LoginStreak) -> Result<()> {
+        let mut shard = self.write_players(&streak.player_id);
+        shard.players.entry(streak.player_id.clone()).or_default().login_streak = Some(streak.clone());
+        Ok(())
+    }
+
+    async fn has_welcome_bonus(&self, player_id: &str) -> Result<bool> {
+        Ok(self
+            .with_player(player_id, |player| player.welcome_rewards > 0)
+            .unwrap_or(false))
+    }
+
+    async fn enqueue_payout(&self, payout: &PayoutRequest) -> Result<()> {
+        let mut shard = self.write_players(&payout.player_id);
+        let mut payouts = write(&self.payouts);
+        if payouts.contains_key(&payout.id) {
+            return Err(PokemonEngineError::AlreadyExists(format!("Payout {}", payout.id)));
+        }
+
+        let player = shard.players.get_mut(&payout.player_id);
+        let Some(player) = player else {
+            return Err(PokemonEngineError::NotFound(format!("Rewards of player {}", payout.player_id)));
+        };
+        let mut claimed = 0u64;
+        for reward_id in &payout.reward_ids {
+            match player.positions.get(reward_id).map(|&i| &player.rewards[i]) {
+                Some(reward) if reward.claimed => {
+                    return Err(PokemonEngineError::RewardAlreadyClaimed(reward_id.to_string()));
+                }
+                Some(reward) => {
+                    claimed = claimed.checked_add(reward.amount.lamports()).ok_or_else(pending_overflow)?;
+                }
+                None => return Err(PokemonEngineError::NotFound(format!("Reward {}", reward_id))),
+            }
+        }
+
+        for reward_id in &payout.reward_ids {
+            player.claim(reward_id);
+        }
+        let pending = shard.pending_after(claimed, 0)?;
+        shard.pending_amount = pending;
+        payouts.insert(payout.id, payout.clone());
+        Ok(())
+    }
+
+    async fn get_payout(&self, payout_id: &Uuid) -> Result<Option<PayoutRequest>> {
+        Ok(read(&self.payouts).get(payout_id).cloned())
+    }
+
+    async fn due_payouts(&self, now: DateTime<Utc>, limit: usize) -> Result<Vec<PayoutRequest>> {
+        let mut due: Vec<PayoutRequest> = read(&self.payouts)
+            .values()
+            .filter(|p| p.status == PayoutStatus::Pending && p.next_attempt_at <= now)
+            .cloned()
+            .collect();
+        due.sort_by_key(|p| (p.next_attempt_at, p.id));
+        due.truncate(limit);
+        Ok(due)
+    }
+
+    async fn update_payout(&self, payout: &PayoutRequest) -> Result<()> {
+        match write(&self.payouts).get_mut(&payout.id) {
+            Some(stored) => {
+                *stored = payout.clone();
+                Ok(())
+            }
+            None => Err(PokemonEngineError::NotFound(format!("Payout {}", payout.id))),
+        }
+    }
+
+    async fn restore_payout(&self, payout: &PayoutRequest) -> Result<()> {
+        write(&self.payouts).insert(payout.id, payout.clone());
+        Ok(())
+    }
+
+    async fn export(&self, visitor: &mut (dyn StorageVisitor + Send)) -> Result<()> {
+        for shard in &self.players {
+            let shard = read(shard);
+            for player in shard.players.values() {
+                for reward in &player.rewards {
+                    visitor.visit_reward(reward)?;
+                }
+                for stats in player.daily_stats.values() {
+                    visitor.visit_daily_stats(stats)?;
+                }
+                if let Some(streak) = &player.login_streak {
+                    visitor.visit_login_streak(streak)?;
+                }
+            }
+        }
+        for payout in read(&self.payouts).values() {
+            visitor.visit_payout(payout)?;
+        }
+        Ok(())
+    }
+}
+
+fn pending_amount(reward: &Reward) -> u64 {
+    if reward.claimed { 0 } else { reward.amount.lamports() }
+}
+
+fn pending_overflow() -> PokemonEngineError {
+    PokemonEngineError::AmountOverflow("Total pending amount doesn't fit in a u64".to_string())
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+
+    fn reward(player_id: &str, amount: u64) -> Reward {
+        Reward {
+            id: Uuid::new_v4(),
+            player_id: player_id.to_string(),
+            game: GameType::Battle,
+            amount: Amount::from_lamports(amount),
+            timestamp: Utc::now(),
+            claimed: false,
+            game_data: serde_json::Value::Null,
+            transaction_signature: None,
+            config_version: None,
+        }
+    }
+
+    fn moved(reward: &Reward, player_id: &str, amount: u64) -> Reward {
+        Reward { player_id: player_id.to_string(), amount: Amount::from_lamports(amount), ..reward.clone() }
+    }
+
+    /// A move whose new pending total overflows leaves the reward with its previous owner
+    #[tokio::test]
+    async fn overflowing_move_changes_nothing() {
+        for shards in [1, 64] {
+            let storage = MemoryStorage::with_shards(shards);
+            let original = reward("player1", 10);
+            storage.create_reward(&original).await.unwrap();
+            storage.create_reward(&reward("player2", u64::MAX - 20)).await.unwrap();
+
+            // Overflows whether or not the players share a shard
+            let result = storage.create_reward(&moved(&original, "player2", u64::MAX)).await;
+            assert!(matches!(result, Err(PokemonEngineError::AmountOverflow(_))), "{} shards: {:?}", shards, result);
+
+            let rewards = storage.get_rewards("player1").await.unwrap();
+            assert_eq!(rewards.iter().map(|r| r.id).collect::<Vec<_>>(), [original.id], "{} shards", shards);
+            assert_eq!(storage.get_rewards("player2").await.unwrap().len(), 1, "{} shards", shards);
+            storage.mark_reward_claimed(&original.id).await.unwrap();
+            assert!(storage.get_pending_rewards("player1").await.unwrap().is_empty(), "{} shards: still owned by player1", shards);
+        }
+    }
+
+    /// A move is checked against the shard total it leaves, not the one it started from
+    #[tokio::test]
+    async fn move_within_a_full_shard_nets_out() {
+        let storage = MemoryStorage::with_shards(1);
+        let original = reward("player1", 10);
+        storage.create_reward(&original).await.unwrap();
+        storage.create_reward(&reward("player2", u64::MAX - 15)).await.unwrap();
+
+        storage.create_reward(&moved(&original, "player2", 12)).await.unwrap();
+        assert!(storage.get_rewards("player1").await.unwrap().is_empty());
+        assert_eq!(storage.get_rewards("player2").await.unwrap().len(), 2);
+        assert_eq!(storage.get_total_pending_amount().await.unwrap(), u64::MAX - 3);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::models::{Reward, DailyStats, LoginStreak, PayoutRequest};
+use crate::error::{PokemonEngineError, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+
+pub mod conformance;
+mod memory_storage;
+mod query;
+mod sled_storage;
+pub mod snapshot;
+#[cfg(feature = "sqlite")]
+mod sqlite_storage;
+
+pub use memory_storage::MemoryStorage;
+pub use query::{Cursor, RewardPage, RewardQuery, RewardStatus, SortOrder, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+pub use sled_storage::SledStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite_storage::SqliteStorage;
+
+/// Receives every record of a storage during `Storage::export`
+pub trait StorageVisitor {
+    fn visit_reward(&mut self, reward: &Reward) -> Result<()>;
+    
+    fn visit_daily_stats(&mut self, stats: &DailyStats) -> Result<()>;
+    
+    fn visit_login_streak(&mut self, streak: &LoginStreak) -> Result<()>;
+    
+    fn visit_payout(&mut self, payout: &PayoutRequest) -> Result<()>;
+}
+
+/// Sums unpaid rewards for the default `Storage::get_total_unpaid_amount`
+struct UnpaidTotal(u64);
+
+impl StorageVisitor for UnpaidTotal {
+    fn visit_reward(&mut self, reward: &Reward) -> Result<()> {
+        if reward.transaction_signature.is_none() {
+            self.0 = add_unpaid(self.0, reward)?;
+        }
+        Ok(())
+    }
+    
+    fn visit_daily_stats(&mut self, _stats: &DailyStats) -> Result<()> {
+        Ok(())
+    }
+    
+    fn visit_login_streak(&mut self, _streak: &LoginStreak) -> Result<()> {
+        Ok(())
+    }
+    
+    fn visit_payout(&mut self, _payout: &PayoutRequest) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Add a reward to an unpaid total, failing on overflow
+fn add_unpaid(total: u64, reward: &Reward) -> Result<u64> {
+    total.checked_add(reward.amount.lamports()).ok_or_else(|| {
+        PokemonEngineError::AmountOverflow("Total unpaid amount doesn't fit in a u64".to_string())
+    })
+}
+
+/// Storage trait for reward data persistence
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Create a new reward
+    async fn create_reward(&self, reward: &Reward) -> Result<()>;
+    
+    /// Get all rewards for a player
+    async fn get_rewards(&self, player_id: &str) -> Result<Vec<Reward>>;
+    
+    /// Get pending (unclaimed) rewards for a player
+    async fn get_pending_rewards(&self, player_id: &str) -> Result<Vec<Reward>>;
+    
+    /// Get one page of a player's rewards matching a query
+    ///
+    /// The default implementation filters the result of `get_rewards`;
+    /// backends that can filter and sort natively should override it.
+    async fn query_rewards(&self, player_id: &str, query: &RewardQuery) -> Result<RewardPage> {
+        query.page(&self.get_rewards(player_id).await?)
+    }
+    
+    /// Get all rewards that have a payout transaction signature recorded
+    async fn get_paid_rewards(&self) -> Result<Vec<Reward>>;
+    
+    /// Record the payout transaction signature for a reward
+    async fn set_transaction_signature(&self, reward_id: &uuid::Uuid, signature: &str) -> Result<()>;
+    
+    /// Get the total amount of pending (unclaimed) rewards across all players
+    async fn get_total_pending_amount(&self) -> Result<u64>;
+    
+    /// Get the total amount of rewards not yet paid out across all players
+    ///
+    /// Covers pending rewards and claimed ones without a transaction
+    /// signature, including those queued in the payout outbox: everything
+    /// the vault still owes. The default implementation sums the rewards
+    /// visited by `export`; backends that can sum natively should override it.
+    async fn get_total_unpaid_amount(&self) -> Result<u64> {
+        let mut visitor = UnpaidTotal(0);
+        self.export(&mut visitor).await?;
+        Ok(visitor.0)
+    }
+    
+    /// Mark reward as claimed
+    async fn mark_reward_claimed(&self, reward_id: &uuid::Uuid) -> Result<()>;
+    
+    /// Mark all pending rewards as claimed for a player
+    async fn mark_all_rewards_claimed(&self, player_id: &str) -> Result<()>;
+    
+    /// Get daily stats for a player
+    async fn get_daily_stats(&self, player_id: &str, date: NaiveDate) -> Result<Option<DailyStats>>;
+    
+    /// Update daily stats
+    async fn update_daily_stats(&self, stats: &DailyStats) -> Result<()>;
+    
+    /// Get login streak for a player
+    async fn get_login_streak(&self, player_id: &str) -> Result<Option<LoginStreak>>;
+    
+    /// Update login streak
+    async fn update_login_streak(&self, streak: &LoginStreak) -> Result<()>;
+    
+    /// Check if player was ever given the welcome bonus, claimed or not
+    async fn has_welcome_bonus(&self, player_id: &str) -> Result<bool>;
+    
+    /// Mark a payout's rewards claimed and add it to the payout outbox
+    ///
+    /// Both happen atomically: if any of the rewards is missing, belongs to
+    /// another player or is already claimed, or a payout with the same id
+    /// exists, nothing is changed and an error is returned.
+    async fn enqueue_payout(&self, payout: &PayoutRequest) -> Result<()>;
+    
+    /// Get a payout from the outbox
+    async fn get_payout(&self, payout_id: &uuid::Uuid) -> Result<Option<PayoutRequest>>;
+    
+    /// Get up to `limit` pending payouts due at `now`, earliest first
+    async fn due_payouts(&self, now: DateTime<Utc>, limit: usize) -> Result<Vec<PayoutRequest>>;
+    
+    /// Save the status, attempts and schedule of an existing payout
+    async fn update_payout(&self, payout: &PayoutRequest) -> Result<()>;
+    
+    /// Add a payout to the outbox as is, replacing one with the same id
+    ///
+    /// Its rewards are left untouched. Used to load snapshots, whose rewards
+    /// already carry their claimed state.
+    async fn restore_payout(&self, payout: &PayoutRequest) -> Result<()>;
+    
+    /// Visit every stored reward, daily stats record, login streak and payout
+    ///
+    /// Used for snapshots. Payouts are visited after every reward, otherwise
+    /// records are visited in no particular order; an error from the visitor
+    /// stops the export and is returned.
+    async fn export(&self, visitor: &mut (dyn StorageVisitor + Send)) -> Result<()>;
+    
+    /// Persist a newly issued reward together with the state it changes
+    ///
+    /// The engine calls this once per accepted event with the updated daily
+    /// stats and login streak (if any). The default implementation applies the
+    /// writes one by one; backends with transactions should override it to
+    /// make them atomic.
+    async fn record_reward(
+        &self,
+        reward: &Reward,
+        stats: Option<&DailyStats>,
+        streak: Option<&LoginStreak>,
+    ) -> Result<()> {
+        if let Some(streak) = streak {
+            self.update_login_streak(streak).await?;
+        }
+        self.create_reward(reward).await?;
+        if let Some(stats) = stats {
+            self.update_daily_stats(stats).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+//! Filtered, sorted and paginated reward queries
+//!
+//! Pages are cut with an opaque keyset cursor over `(timestamp, id)`, so a
+//! page stays stable while new rewards are being issued.
+
+use crate::error::{PokemonEngineError, Result};
+use crate::amount::Amount;
+use crate::models::{GameType, Reward};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use uuid::Uuid;
+
+/// Page size used when a query does not set one
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page a single query may return
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Reward status filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RewardStatus {
+    Pending, // Not claimed yet
+    Claimed, // Claimed, whether or not it was paid out
+    Paid,    // Payout transaction signature recorded
+}
+
+impl RewardStatus {
+    fn matches(self, reward: &Reward) -> bool {
+        match self {
+            RewardStatus::Pending => !reward.claimed,
+            RewardStatus::Claimed => reward.claimed,
+            RewardStatus::Paid => reward.transaction_signature.is_some(),
+        }
+    }
+}
+
+/// Sort order by reward timestamp
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+/// Query over one player's rewards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RewardQuery {
+    pub game: Option<GameType>,
+    pub status: Option<RewardStatus>,
+    pub from: Option<DateTime<Utc>>,  // Inclusive
+    pub until: Option<DateTime<Utc>>, // Exclusive
+    pub min_amount: Option<Amount>,
+    pub order: SortOrder,
+    pub limit: usize, // 0 means DEFAULT_PAGE_SIZE, capped at MAX_PAGE_SIZE
+    pub cursor: Option<String>, // `next_cursor` of the previous page
+}
+
+impl Default for RewardQuery {
+    fn default() -> Self {
+        Self {
+            game: None,
+            status: None,
+            from: None,
+            until: None,
+            min_amount: None,
+            order: SortOrder::default(),
+            limit: DEFAULT_PAGE_SIZE,
+            cursor: None,
+        }
+    }
+}
+
+/// One page of query results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewardPage {
+    pub rewards: Vec<Reward>,
+    pub next_cursor: Option<String>, // None on the last page
+}
+
+/// Decoded position of the last reward on a page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub timestamp: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    fn of(reward: &Reward) -> Self {
+        Self {
+            timestamp: reward.timestamp,
+            id: reward.id,
+        }
+    }
+
+    /// Encode as an opaque string tied to the sort order
+    pub fn encode(&self, order: SortOrder) -> Result<String> {
+        let nanos = self.timestamp.timestamp_nanos_opt().ok_or_else(|| {
+            PokemonEngineError::InvalidQuery(format!("Timestamp out of range: {}", self.timestamp))
+        })?;
+        let raw = format!("{}:{}:{}", order_tag(order), nanos, self.id);
+        Ok(bs58::encode(raw).into_string())
+    }
+
+    /// Decode a cursor produced by `encode` for the same sort order
+    pub fn decode(cursor: &str, order: SortOrder) -> Result<Self> {
+        let invalid = || PokemonEngineError::InvalidQuery(format!("Invalid cursor: {}", cursor));
+        let raw = bs58::decode(cursor).into_vec().map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+
+        let mut parts = raw.splitn(3, ':');
+        let (Some(tag), Some(nanos), Some(id)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        if tag != order_tag(order) {
+            return Err(PokemonEngineError::InvalidQuery(
+                "Cursor was issued for a different sort order".to_string(),
+            ));
+        }
+        Ok(Self {
+            timestamp: DateTime::from_timestamp_nanos(nanos.parse().map_err(|_| invalid())?),
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+
+    fn cmp_reward(&self, reward: &Reward) -> Ordering {
+        (reward.timestamp, reward.id).cmp(&(self.timestamp, self.id))
+    }
+}
+
+fn order_tag(order: SortOrder) -> &'static str {
+    match order {
+        SortOrder::NewestFirst => "d",
+        SortOrder::OldestFirst => "a",
+    }
+}
+
+impl RewardQuery {
+    /// Effective page size
+    pub fn page_size(&self) -> usize {
+        match self.limit {
+            0 => DEFAULT_PAGE_SIZE,
+            limit => limit.min(MAX_PAGE_SIZE),
+        }
+    }
+
+    /// Decode the query's cursor, if any
+    pub fn decode_cursor(&self) -> Result<Option<Cursor>> {
+        self.cursor
+            .as_deref()
+            .map(|cursor| Cursor::decode(cursor, self.order))
+            .transpose()
+    }
+
+    /// Check a reward against the filters (not the cursor)
+    pub fn matches(&self, reward: &Reward) -> bool {
+        self.game.is_none_or(|game| reward.game == game)
+            && self.status.is_none_or(|status| status.matches(reward))
+            && self.from.is_none_or(|from| reward.timestamp >= from)
+            && self.until.is_none_or(|until| reward.timestamp < until)
+            && self.min_amount.is_none_or(|min| reward.amount >= min)
+    }
+
+    /// Select one page from a player's rewards
+    ///
+    /// Storage backends without a native query path use this on the result
+    /// of `get_rewards`.
+    pub fn page<'a>(&self, rewards: impl IntoIterator<Item = &'a Reward>) -> Result<RewardPage> {
+        let cursor = self.decode_cursor()?;
+        let after_cursor = |reward: &Reward| match (cursor, self.order) {
+            (None, _) => true,
+            (Some(c), SortOrder::OldestFirst) => c.cmp_reward(reward) == Ordering::Greater,
+            (Some(c), SortOrder::NewestFirst) => c.cmp_reward(reward) == Ordering::Less,
+        };
+
+        let mut selected: Vec<&Reward> = rewards
+            .into_iter()
+            .filter(|r| self.matches(r) && after_cursor(r))
+            .collect();
+        selected.sort_by(|a, b| {
+            let ordering = (a.timestamp, a.id).cmp(&(b.timestamp, b.id));
+            match self.order {
+                SortOrder::OldestFirst => ordering,
+                SortOrder::NewestFirst => ordering.reverse(),
+            }
+        });
+
+        let rewards: Vec<Reward> = selected.into_iter().take(self.page_size() + 1).cloned().collect();
+        self.finish_page(rewards)
+    }
+
+    /// Build a page from up to `page_size() + 1` sorted rewards
+    ///
+    /// The extra reward only signals that another page exists.
+    pub fn finish_page(&self, mut rewards: Vec<Reward>) -> Result<RewardPage> {
+        let next_cursor = if rewards.len() > self.page_size() {
+            rewards.truncate(self.page_size());
+            rewards.last().map(|r| Cursor::of(r).encode(self.order)).transpose()?
+        } else {
+            None
+        };
+        Ok(RewardPage { rewards, next_cursor })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn reward(minutes: i64) -> Reward {
+        Reward {
+            id: Uuid::new_v4(),
+            player_id: "player1".to_string(),
+            game: GameType::FlyPoke,
+            amount: Amount::from_lamports(10),
+            timestamp: Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap() + Duration::minutes(minutes),
+            claimed: false,
+            game_data: serde_json::Value::Null,
+            transaction_signature: None,
+            config_version: None,
+        }
+    }
+
+    fn with_limit(limit: usize) -> RewardQuery {
+        RewardQuery { limit, ..RewardQuery::default() }
+    }
+
+    fn is_invalid_query<T: std::fmt::Debug>(result: Result<T>) -> bool {
+        matches!(result, Err(PokemonEngineError::InvalidQuery(_)))
+    }
+
+    #[test]
+    fn cursors_round_trip() {
+        let cursor = Cursor::of(&reward(0));
+        for order in [SortOrder::NewestFirst, SortOrder::OldestFirst] {
+            let encoded = cursor.encode(order).unwrap();
+            assert_eq!(Cursor::decode(&encoded, order).unwrap(), cursor);
+        }
+    }
+
+    #[test]
+    fn malformed_cursors_are_invalid_queries() {
+        let id = Uuid::new_v4();
+        let malformed = [
+            "not base58 0OIl".to_string(),
+            bs58::encode("d:123").into_string(),
+            bs58::encode(format!("d:soon:{}", id)).into_string(),
+            bs58::encode("d:123:not-a-uuid").into_string(),
+            bs58::encode([0xff, 0xfe]).into_string(),
+        ];
+        for cursor in &malformed {
+            assert!(is_invalid_query(Cursor::decode(cursor, SortOrder::NewestFirst)), "{}", cursor);
+        }
+
+        let ascending = Cursor::of(&reward(0)).encode(SortOrder::OldestFirst).unwrap();
+        assert!(is_invalid_query(Cursor::decode(&ascending, SortOrder::NewestFirst)));
+
+        let query = RewardQuery { cursor: Some("0OIl".to_string()), ..RewardQuery::default() };
+        assert!(is_invalid_query(query.decode_cursor()));
+        assert!(is_invalid_query(query.page(&[reward(0)])));
+    }
+
+    #[test]
+    fn page_size_is_clamped() {
+        assert_eq!(with_limit(0).page_size(), DEFAULT_PAGE_SIZE);
+        assert_eq!(with_limit(1).page_size(), 1);
+        assert_eq!(with_limit(MAX_PAGE_SIZE).page_size(), MAX_PAGE_SIZE);
+        assert_eq!(with_limit(MAX_PAGE_SIZE + 1).page_size(), MAX_PAGE_SIZE);
+        assert_eq!(with_limit(usize::MAX).page_size(), MAX_PAGE_SIZE);
+        assert_eq!(RewardQuery::default().page_size(), DEFAULT_PAGE_SIZE);
+    }
+
+    #[test]
+    fn next_cursor_is_set_only_when_a_reward_is_left_over() {
+        let query = with_limit(2);
+        let rewards: Vec<Reward> = (0..3).map(reward).collect();
+
+        let full = query.finish_page(rewards[..2].to_vec()).unwrap();
+        assert_eq!(full.rewards.len(), 2);
+        assert_eq!(full.next_cursor, None);
+
+        assert_eq!(query.finish_page(Vec::new()).unwrap().next_cursor, None);
+
+        let more = query.finish_page(rewards.clone()).unwrap();
+        let ids: Vec<Uuid> = more.rewards.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![rewards[0].id, rewards[1].id]);
+        let cursor = Cursor::decode(&more.next_cursor.unwrap(), query.order).unwrap();
+        assert_eq!(cursor, Cursor::of(&rewards[1]));
+    }
+}
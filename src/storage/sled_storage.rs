@@ -0,0 +1,458 @@
+use crate::models::{Reward, DailyStats, LoginStreak, GameType, PayoutRequest, PayoutStatus};
+use crate::error::{Result, PokemonEngineError};
+use crate::storage::{add_unpaid, Storage, StorageVisitor};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use sled::transaction::{
+    ConflictableTransactionError, ConflictableTransactionResult, TransactionError, Transactional, TransactionalTree,
+};
+use sled::{Db, Tree};
+use std::path::Path;
+
+const REWARDS_TREE: &str = "rewards";
+const PLAYER_REWARDS_TREE: &str = "player_rewards";
+const DAILY_STATS_TREE: &str = "daily_stats";
+const LOGIN_STREAKS_TREE: &str = "login_streaks";
+const PAYOUTS_TREE: &str = "payouts";
+
+/// Persistent storage implementation backed by sled
+///
+/// Rewards are stored by id in the `rewards` tree, with a `player_rewards`
+/// index keyed by player id and reward id so per-player lookups don't scan
+/// every reward. Daily stats, login streaks and the payout outbox live in
+/// their own trees.
+pub struct SledStorage {
+    db: Db,
+    rewards: Tree,
+    player_rewards: Tree,
+    daily_stats: Tree,
+    login_streaks: Tree,
+    payouts: Tree,
+}
+
+impl SledStorage {
+    /// Open (or create) a sled database at the given path
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_db(sled::open(path)?)
+    }
+
+    /// Open a temporary database that is removed when dropped
+    pub fn temporary() -> Result<Self> {
+        Self::from_db(sled::Config::new().temporary(true).open()?)
+    }
+
+    /// Use an already opened sled database
+    pub fn from_db(db: Db) -> Result<Self> {
+        Ok(Self {
+            rewards: db.open_tree(REWARDS_TREE)?,
+            player_rewards: db.open_tree(PLAYER_REWARDS_TREE)?,
+            daily_stats: db.open_tree(DAILY_STATS_TREE)?,
+            login_streaks: db.open_tree(LOGIN_STREAKS_TREE)?,
+            payouts: db.open_tree(PAYOUTS_TREE)?,
+            db,
+        })
+    }
+
+    /// Flush pending writes to disk
+    pub async fn flush(&self) -> Result<()> {
+        self.db.flush_async().await?;
+        Ok(())
+    }
+
+    /// Load all rewards of a player through the player index
+    fn player_rewards(&self, player_id: &str) -> Result<Vec<Reward>> {
+        let mut rewards = Vec::new();
+        for entry in self.player_rewards.scan_prefix(player_key(player_id)) {
+            let (key, _) = entry?;
+            let reward_id = &key[key.len() - 16..];
+            if let Some(value) = self.rewards.get(reward_id)? {
+                rewards.push(serde_json::from_slice(&value)?);
+            }
+        }
+        Ok(rewards)
+    }
+
+    /// Atomically rewrite one reward
+    fn update_reward<F>(&self, reward_id: &uuid::Uuid, update: F) -> Result<()>
+    where
+        F: Fn(&mut Reward),
+    {
+        let key = reward_id.as_bytes();
+        self.rewards
+            .transaction(|rewards| {
+                let value = rewards.get(key)?.ok_or_else(|| {
+                    abort_with(PokemonEngineError::NotFound(format!("Reward {}", reward_id)))
+                })?;
+                let mut reward: Reward = serde_json::from_slice(&value).map_err(abort)?;
+                update(&mut reward);
+                rewards.insert(key, serde_json::to_vec(&reward).map_err(abort)?)?;
+                Ok(())
+            })
+            .map_err(from_transaction_error)
+    }
+
+    /// Iterate over every stored reward
+    fn all_rewards(&self) -> impl Iterator<Item = Result<Reward>> + '_ {
+        self.rewards.iter().map(|entry| {
+            let (_, value) = entry?;
+            Ok(serde_json::from_slice(&value)?)
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for SledStorage {
+    async fn create_reward(&self, reward: &Reward) -> Result<()> {
+        let value = serde_json::to_vec(reward)?;
+        (&self.rewards, &self.player_rewards)
+            .transaction(|(rewards, player_rewards)| write_reward(rewards, player_rewards, reward, &value))
+            .map_err(from_transaction_error)
+    }
+
+    async fn get_rewards(&self, player_id: &str) -> Result<Vec<Reward>> {
+        self.player_rewards(player_id)
+    }
+
+    async fn get_pending_rewards(&self, player_id: &str) -> Result<Vec<Reward>> {
+        Ok(self
+            .player_rewards(player_id)?
+            .into_iter()
+            .filter(|r| !r.claimed)
+            .collect())
+    }
+
+    async fn get_paid_rewards(&self) -> Result<Vec<Reward>> {
+        let mut paid = Vec::new();
+        for reward in self.all_rewards() {
+            let reward = reward?;
+            if reward.transaction_signature.is_some() {
+                paid.push(reward);
+            }
+        }
+        Ok(paid)
+    }
+
+    async fn set_transaction_signature(&self, reward_id: &uuid::Uuid, signature: &str) -> Result<()> {
+        self.update_reward(reward_id, |reward| {
+            reward.transaction_signature = Some(signature.to_string());
+        })
+    }
+
+    async fn get_total_pending_amount(&self) -> Result<u64> {
+        let mut total = 0u64;
+        for reward in self.all_rewards() {
+            let reward = reward?;
+            if !reward.claimed {
+                total = total.checked_add(reward.amount.lamports()).ok_or_else(|| {
+                    PokemonEngineError::AmountOverflow("Total pending amount doesn't fit in a u64".to_string())
+                })?;
+            }
+        }
+        Ok(total)
+    }
+
+    async fn get_total_unpaid_amount(&self) -> Result<u64> {
+        let mut total = 0u64;
+        for reward in self.all_rewards() {
+            let reward = reward?;
+            if reward.transaction_signature.is_none() {
+                total = add_unpaid(total, &reward)?;
+            }
+        }
+        Ok(total)
+    }
+
+    async fn mark_reward_claimed(&self, reward_id: &uuid::Uuid) -> Result<()> {
+        self.update_reward(reward_id, |reward| reward.claimed = true)
+    }
+
+    async fn mark_all_rewards_claimed(&self, player_id: &str) -> Result<()> {
+        let pending: Vec<Reward> = self
+            .player_rewards(player_id)?
+            .into_iter()
+            .filter(|r| !r.claimed)
+            .collect();
+
+        self.rewards
+            .transaction(|rewards| {
+                for reward in &pending {
+                    let key = reward.id.as_bytes();
+                    // Re-read inside the transaction so concurrent updates are not lost
+                    let Some(value) = rewards.get(key)? else { continue };
+                    let mut current: Reward = serde_json::from_slice(&value).map_err(abort)?;
+                    if !current.claimed {
+                        current.claimed = true;
+                        rewards.insert(key, serde_json::to_vec(&current).map_err(abort)?)?;
+                    }
+                }
+                Ok(())
+            })
+            .map_err(from_transaction_error)
+    }
+
+    async fn get_daily_stats(&self, player_id: &str, date: NaiveDate) -> Result<Option<DailyStats>> {
+        self.daily_stats
+            .get(daily_stats_key(player_id, date))?
+            .map(|value| serde_json::from_slice(&value).map_err(Into::into))
+            .transpose()
+    }
+
+    async fn update_daily_stats(&self, stats: &DailyStats) -> Result<()> {
+        self.daily_stats.insert(
+            daily_stats_key(&stats.player_id, stats.date),
+            serde_json::to_vec(stats)?,
+        )?;
+        Ok(())
+    }
+
+    async fn get_login_streak(&self, player_id: &str) -> Result<Option<LoginStreak>> {
+        self.login_streaks
+            .get(player_key(player_id))?
+            .map(|value| serde_json::from_slice(&value).map_err(Into::into))
+            .transpose()
+    }
+
+    async fn update_login_streak(&self, streak: &LoginStreak) -> Result<()> {
+        self.login_streaks
+            .insert(player_key(&streak.player_id), serde_json::to_vec(streak)?)?;
+        Ok(())
+    }
+
+    async fn has_welcome_bonus(&self, player_id: &str) -> Result<bool> {
+        Ok(self
+            .player_rewards(player_id)?
+            .iter()
+            .any(|r| r.game == GameType::Welcome))
+    }
+
+    async fn enqueue_payout(&self, payout: &PayoutRequest) -> Result<()> {
+        let value = serde_json::to_vec(payout)?;
+        (&self.rewards, &self.payouts)
+            .transaction(|(rewards, payouts)| {
+                if payouts.get(payout.id.as_bytes())?.is_some() {
+                    return Err(abort_with(PokemonEngineError::AlreadyExists(format!("Payout {}", payout.id))));
+                }
+                for reward_id in &payout.reward_ids {
+                    let key = reward_id.as_bytes();
+                    let value = rewards
+                        .get(key)?
+                        .ok_or_else(|| abort_with(PokemonEngineError::NotFound(format!("Reward {}", reward_id))))?;
+                    let mut reward: Reward = serde_json::from_slice(&value).map_err(abort)?;
+                    if reward.player_id != payout.player_id {
+                        return Err(abort_with(PokemonEngineError::NotFound(format!("Reward {}", reward_id))));
+                    }
+                    if reward.claimed {
+                        return Err(abort_with(PokemonEngineError::RewardAlreadyClaimed(reward_id.to_string())));
+                    }
+                    reward.claimed = true;
+                    rewards.insert(key, serde_json::to_vec(&reward).map_err(abort)?)?;
+                }
+                payouts.insert(payout.id.as_bytes(), value.as_slice())?;
+                Ok(())
+            })
+            .map_err(from_transaction_error)
+    }
+
+    async fn get_payout(&self, payout_id: &uuid::Uuid) -> Result<Option<PayoutRequest>> {
+        self.payouts
+            .get(payout_id.as_bytes())?
+            .map(|value| serde_json::from_slice(&value).map_err(Into::into))
+            .transpose()
+    }
+
+    async fn due_payouts(&self, now: DateTime<Utc>, limit: usize) -> Result<Vec<PayoutRequest>> {
+        let mut due = Vec::new();
+        for entry in self.payouts.iter() {
+            let (_, value) = entry?;
+            let payout: PayoutRequest = serde_json::from_slice(&value)?;
+            if payout.status == PayoutStatus::Pending && payout.next_attempt_at <= now {
+                due.push(payout);
+            }
+        }
+        due.sort_by_key(|p| (p.next_attempt_at, p.id));
+        due.truncate(limit);
+        Ok(due)
+    }
+
+    async fn update_payout(&self, payout: &PayoutRequest) -> Result<()> {
+        let value = serde_json::to_vec(payout)?;
+        self.payouts
+            .transaction(|payouts| {
+                if payouts.get(payout.id.as_bytes())?.is_none() {
+                    return Err(abort_with(PokemonEngineError::NotFound(format!("Payout {}", payout.id))));
+                }
+                payouts.insert(payout.id.as_bytes(), value.as_slice())?;
+                Ok(())
+            })
+            .map_err(from_transaction_error)
+    }
+
+    async fn record_reward(
+        &self,
+        reward: &Reward,
+        stats: Option<&DailyStats>,
+        streak: Option<&LoginStreak>,
+    ) -> Result<()> {
+        let value = serde_json::to_vec(reward)?;
+        let stats = match stats {
+            Some(stats) => Some((daily_stats_key(&stats.player_id, stats.date), serde_json::to_vec(stats)?)),
+            None => None,
+        };
+        let streak = match streak {
+            Some(streak) => Some((player_key(&streak.player_id), serde_json::to_vec(streak)?)),
+            None => None,
+        };
+        // One transaction, so a crash never leaves a reward without its daily stats
+        (&self.rewards, &self.player_rewards, &self.daily_stats, &self.login_streaks)
+            .transaction(|(rewards, player_rewards, daily_stats, login_streaks)| {
+                if let Some((key, value)) = &streak {
+                    login_streaks.insert(key.as_slice(), value.as_slice())?;
+                }
+                write_reward(rewards, player_rewards, reward, &value)?;
+                if let Some((key, value)) = &stats {
+                    daily_stats.insert(key.as_slice(), value.as_slice())?;
+                }
+                Ok(())
+            })
+            .map_err(from_transaction_error)
+    }
+
+    async fn restore_payout(&self, payout: &PayoutRequest) -> Result<()> {
+        self.payouts.insert(payout.id.as_bytes(), serde_json::to_vec(payout)?)?;
+        Ok(())
+    }
+
+    async fn export(&self, visitor: &mut (dyn StorageVisitor + Send)) -> Result<()> {
+        for reward in self.all_rewards() {
+            visitor.visit_reward(&reward?)?;
+        }
+        for entry in self.daily_stats.iter() {
+            let (_, value) = entry?;
+            visitor.visit_daily_stats(&serde_json::from_slice(&value)?)?;
+        }
+        for entry in self.login_streaks.iter() {
+            let (_, value) = entry?;
+            visitor.visit_login_streak(&serde_json::from_slice(&value)?)?;
+        }
+        for entry in self.payouts.iter() {
+            let (_, value) = entry?;
+            visitor.visit_payout(&serde_json::from_slice(&value)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a reward and its index entry inside a transaction
+///
+/// Re-creating a reward under another player moves its index entry.
+fn write_reward(
+    rewards: &TransactionalTree,
+    player_rewards: &TransactionalTree,
+    reward: &Reward,
+    value: &[u8],
+) -> ConflictableTransactionResult<(), PokemonEngineError> {
+    if let Some(previous) = rewards.insert(reward.id.as_bytes(), value)? {
+        let previous: Reward = serde_json::from_slice(&previous).map_err(abort)?;
+        if previous.player_id != reward.player_id {
+            player_rewards.remove(player_reward_key(&previous.player_id, &reward.id))?;
+        }
+    }
+    player_rewards.insert(player_reward_key(&reward.player_id, &reward.id), &[])?;
+    Ok(())
+}
+
+/// Length-prefixed player id, so one id is never a prefix of another's key
+fn player_key(player_id: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(4 + player_id.len());
+    key.extend_from_slice(&(player_id.len() as u32).to_be_bytes());
+    key.extend_from_slice(player_id.as_bytes());
+    key
+}
+
+fn player_reward_key(player_id: &str, reward_id: &uuid::Uuid) -> Vec<u8> {
+    let mut key = player_key(player_id);
+    key.extend_from_slice(reward_id.as_bytes());
+    key
+}
+
+fn daily_stats_key(player_id: &str, date: NaiveDate) -> Vec<u8> {
+    let mut key = player_key(player_id);
+    key.extend_from_slice(date.format("%Y-%m-%d").to_string().as_bytes());
+    key
+}
+
+fn abort(err: serde_json::Error) -> ConflictableTransactionError<PokemonEngineError> {
+    ConflictableTransactionError::Abort(err.into())
+}
+
+fn abort_with(err: PokemonEngineError) -> ConflictableTransactionError<PokemonEngineError> {
+    ConflictableTransactionError::Abort(err)
+}
+
+fn from_transaction_error(err: TransactionError<PokemonEngineError>) -> PokemonEngineError {
+    match err {
+        TransactionError::Abort(err) => err,
+        TransactionError::Storage(err) => err.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_reward_engine_with_sled;
+
+    /// Reopen a database another handle was just dropped from
+    ///
+    /// sled releases its file lock from deferred cleanup, which may run a
+    /// little after the last handle is dropped.
+    async fn reopen(path: &Path) -> SledStorage {
+        let mut attempts = 0;
+        loop {
+            match SledStorage::open(path) {
+                Ok(storage) => return storage,
+                Err(e) if attempts < 100 => {
+                    attempts += 1;
+                    log::debug!("Database still locked ({}), retrying", e);
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                }
+                Err(e) => panic!("database stayed locked: {}", e),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn engine_state_survives_reopening() {
+        let path = std::env::temp_dir().join(format!("pokemon-engine-sled-{}", uuid::Uuid::new_v4()));
+
+        let reward = {
+            let engine = create_reward_engine_with_sled(&path).unwrap();
+            let response = engine.process_login_event("player1").await.unwrap();
+            assert!(response.success);
+            response.reward.unwrap()
+        };
+
+        let storage = reopen(&path).await;
+        assert_eq!(storage.player_rewards.scan_prefix(player_key("player1")).count(), 1);
+        let rewards = storage.get_rewards("player1").await.unwrap();
+        assert_eq!(rewards.len(), 1);
+        assert_eq!(rewards[0].id, reward.id);
+        assert_eq!(rewards[0].amount, reward.amount);
+        assert_eq!(storage.get_pending_rewards("player1").await.unwrap().len(), 1);
+        assert!(storage.get_rewards("player2").await.unwrap().is_empty());
+
+        let streak = storage.get_login_streak("player1").await.unwrap().unwrap();
+        assert_eq!(streak.current_streak, 1);
+        assert_eq!(streak.last_login_date, reward.timestamp.date_naive());
+
+        let stats = storage
+            .get_daily_stats("player1", reward.timestamp.date_naive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.login, reward.amount);
+        assert_eq!(stats.total, reward.amount);
+
+        drop(storage);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
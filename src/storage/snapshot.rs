@@ -0,0 +1,401 @@
+//! Versioned JSON Lines snapshots of a `Storage`
+//!
+//! A snapshot is a header line, one line per record and a footer with the
+//! record counts, so truncated files are detected:
+//!
+//! ```text
+//! {"type":"header","format":"pokemon-engine-snapshot","version":2,"created_at":"..."}
+//! {"type":"reward","id":"...","player_id":"...",...}
+//! {"type":"daily_stats","player_id":"...","date":"2025-01-01",...}
+//! {"type":"login_streak","player_id":"...","current_streak":3,...}
+//! {"type":"payout","id":"...","reward_ids":["..."],"status":"pending",...}
+//! {"type":"footer","rewards":1,"daily_stats":1,"login_streaks":1,"payouts":1}
+//! ```
+//!
+//! Welcome bonus grants are ordinary rewards and travel with them. Payouts
+//! from the outbox, queued, in flight or done, come after the rewards they
+//! pay and are restored as they are. Version 1 snapshots, which have no
+//! payouts, are still accepted. Export and import both stream, so memory use
+//! doesn't grow with the number of records (apart from the id sets used to
+//! reject duplicates on import).
+
+use crate::error::{PokemonEngineError, Result};
+use crate::models::{DailyStats, LoginStreak, PayoutRequest, Reward};
+use crate::storage::{Storage, StorageVisitor};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Value of the header's `format` field
+pub const SNAPSHOT_FORMAT: &str = "pokemon-engine-snapshot";
+
+/// Snapshot version written by this library
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// Oldest snapshot version this library can import
+pub const MIN_SNAPSHOT_VERSION: u32 = 1;
+
+/// One line of a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SnapshotLine {
+    Header {
+        format: String,
+        version: u32,
+        created_at: DateTime<Utc>,
+    },
+    Reward(Reward),
+    DailyStats(DailyStats),
+    LoginStreak(LoginStreak),
+    Payout(PayoutRequest),
+    Footer(SnapshotSummary),
+}
+
+/// Number of records in a snapshot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotSummary {
+    pub rewards: u64,
+    pub daily_stats: u64,
+    pub login_streaks: u64,
+    #[serde(default)]
+    pub payouts: u64, // Missing from version 1 footers
+}
+
+struct SnapshotWriter<W: Write> {
+    writer: W,
+    summary: SnapshotSummary,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    fn write_line(&mut self, line: &SnapshotLine) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, line)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+impl<W: Write> StorageVisitor for SnapshotWriter<W> {
+    fn visit_reward(&mut self, reward: &Reward) -> Result<()> {
+        self.summary.rewards += 1;
+        self.write_line(&SnapshotLine::Reward(reward.clone()))
+    }
+
+    fn visit_daily_stats(&mut self, stats: &DailyStats) -> Result<()> {
+        self.summary.daily_stats += 1;
+        self.write_line(&SnapshotLine::DailyStats(stats.clone()))
+    }
+
+    fn visit_login_streak(&mut self, streak: &LoginStreak) -> Result<()> {
+        self.summary.login_streaks += 1;
+        self.write_line(&SnapshotLine::LoginStreak(streak.clone()))
+    }
+
+    fn visit_payout(&mut self, payout: &PayoutRequest) -> Result<()> {
+        self.summary.payouts += 1;
+        self.write_line(&SnapshotLine::Payout(payout.clone()))
+    }
+}
+
+/// Write a snapshot of every record in `storage`
+pub async fn export_snapshot<W: Write + Send>(storage: &dyn Storage, writer: W) -> Result<SnapshotSummary> {
+    let mut snapshot = SnapshotWriter {
+        writer,
+        summary: SnapshotSummary::default(),
+    };
+    snapshot.write_line(&SnapshotLine::Header {
+        format: SNAPSHOT_FORMAT.to_string(),
+        version: SNAPSHOT_VERSION,
+        created_at: Utc::now(),
+    })?;
+    storage.export(&mut snapshot).await?;
+
+    let summary = snapshot.summary;
+    snapshot.write_line(&SnapshotLine::Footer(summary))?;
+    snapshot.writer.flush()?;
+    Ok(summary)
+}
+
+/// Write a snapshot to a file, replacing it only once the export succeeded
+pub async fn export_snapshot_file(storage: &dyn Storage, path: impl AsRef<Path>) -> Result<SnapshotSummary> {
+    let path = path.as_ref();
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let file = File::create(&partial)?;
+
+    let summary = export_snapshot(storage, BufWriter::new(&file)).await?;
+    file.sync_all()?;
+    std::fs::rename(&partial, path)?;
+    Ok(summary)
+}
+
+/// Incremental snapshot validation
+#[derive(Default)]
+struct SnapshotReader {
+    line: u64,
+    header_seen: bool,
+    footer: Option<SnapshotSummary>,
+    summary: SnapshotSummary,
+    reward_ids: HashSet<Uuid>,
+    daily_stats_keys: HashSet<(String, NaiveDate)>,
+    streak_players: HashSet<String>,
+    payout_ids: HashSet<Uuid>,
+}
+
+impl SnapshotReader {
+    fn error(&self, message: impl std::fmt::Display) -> PokemonEngineError {
+        PokemonEngineError::InvalidSnapshot(format!("line {}: {}", self.line, message))
+    }
+
+    /// Parse and validate the next line, returning it if it holds a record
+    fn next(&mut self, text: &str) -> Result<Option<SnapshotLine>> {
+        self.line += 1;
+        if self.footer.is_some() {
+            return Err(self.error("data after footer"));
+        }
+        let line: SnapshotLine = serde_json::from_str(text).map_err(|e| self.error(e))?;
+
+        if !self.header_seen && !matches!(line, SnapshotLine::Header { .. }) {
+            return Err(self.error("missing header"));
+        }
+        match &line {
+            SnapshotLine::Header { format, version, .. } => {
+                if self.header_seen {
+                    return Err(self.error("duplicate header"));
+                }
+                if format != SNAPSHOT_FORMAT {
+                    return Err(self.error(format!("unknown format {:?}", format)));
+                }
+                if !(MIN_SNAPSHOT_VERSION..=SNAPSHOT_VERSION).contains(version) {
+                    return Err(self.error(format!(
+                        "unsupported version {} (expected {} to {})",
+                        version, MIN_SNAPSHOT_VERSION, SNAPSHOT_VERSION
+                    )));
+                }
+                self.header_seen = true;
+                return Ok(None);
+            }
+            SnapshotLine::Reward(reward) => {
+                if !self.reward_ids.insert(reward.id) {
+                    return Err(self.error(format!("duplicate reward {}", reward.id)));
+                }
+                self.summary.rewards += 1;
+            }
+            SnapshotLine::DailyStats(stats) => {
+                let total = stats.flypoke.checked_add(stats.battle).and_then(|t| t.checked_add(stats.login));
+                if total != Some(stats.total) {
+                    return Err(self.error(format!(
+                        "daily stats total {} for {} on {} does not match its parts",
+                        stats.total, stats.player_id, stats.date
+                    )));
+                }
+                if !self.daily_stats_keys.insert((stats.player_id.clone(), stats.date)) {
+                    return Err(self.error(format!(
+                        "duplicate daily stats for {} on {}",
+                        stats.player_id, stats.date
+                    )));
+                }
+                self.summary.daily_stats += 1;
+            }
+            SnapshotLine::LoginStreak(streak) => {
+                if streak.current_streak == 0 {
+                    return Err(self.error(format!("zero login streak for {}", streak.player_id)));
+                }
+                if !self.streak_players.insert(streak.player_id.clone()) {
+                    return Err(self.error(format!("duplicate login streak for {}", streak.player_id)));
+                }
+                self.summary.login_streaks += 1;
+            }
+            SnapshotLine::Payout(payout) => {
+                if let Some(missing) = payout.reward_ids.iter().find(|id| !self.reward_ids.contains(id)) {
+                    return Err(self.error(format!("payout {} pays unknown reward {}", payout.id, missing)));
+                }
+                if !self.payout_ids.insert(payout.id) {
+                    return Err(self.error(format!("duplicate payout {}", payout.id)));
+                }
+                self.summary.payouts += 1;
+            }
+            SnapshotLine::Footer(counts) => {
+                if *counts != self.summary {
+                    return Err(self.error(format!(
+                        "footer counts {:?} do not match records {:?}",
+                        counts, self.summary
+                    )));
+                }
+                self.footer = Some(*counts);
+                return Ok(None);
+            }
+        }
+        Ok(Some(line))
+    }
+
+    fn finish(self) -> Result<SnapshotSummary> {
+        match self.footer {
+            Some(summary) => Ok(summary),
+            None if !self.header_seen => Err(PokemonEngineError::InvalidSnapshot("empty snapshot".to_string())),
+            None => Err(PokemonEngineError::InvalidSnapshot(format!(
+                "truncated after line {}: missing footer",
+                self.line
+            ))),
+        }
+    }
+}
+
+/// Check a snapshot without loading it
+pub fn validate_snapshot<R: BufRead>(reader: R) -> Result<SnapshotSummary> {
+    let mut snapshot = SnapshotReader::default();
+    for line in reader.lines() {
+        snapshot.next(&line?)?;
+    }
+    snapshot.finish()
+}
+
+/// Load a snapshot into `storage`, validating it on the way
+///
+/// Records are written as they are read, so a snapshot that turns out to
+/// be invalid part-way leaves the records before the error in storage. Use
+/// `import_snapshot_file` (or `validate_snapshot` first) to avoid that.
+pub async fn import_snapshot<R: BufRead + Send>(reader: R, storage: &dyn Storage) -> Result<SnapshotSummary> {
+    let mut snapshot = SnapshotReader::default();
+    for line in reader.lines() {
+        match snapshot.next(&line?)? {
+            Some(SnapshotLine::Reward(reward)) => storage.create_reward(&reward).await?,
+            Some(SnapshotLine::DailyStats(stats)) => storage.update_daily_stats(&stats).await?,
+            Some(SnapshotLine::LoginStreak(streak)) => storage.update_login_streak(&streak).await?,
+            Some(SnapshotLine::Payout(payout)) => storage.restore_payout(&payout).await?,
+            _ => {}
+        }
+    }
+    snapshot.finish()
+}
+
+/// Validate a snapshot file, then load it into `storage`
+pub async fn import_snapshot_file(path: impl AsRef<Path>, storage: &dyn Storage) -> Result<SnapshotSummary> {
+    let path = path.as_ref();
+    validate_snapshot(BufReader::new(File::open(path)?))?;
+    import_snapshot(BufReader::new(File::open(path)?), storage).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::models::GameType;
+    use crate::storage::MemoryStorage;
+
+    fn reward(player_id: &str, lamports: u64) -> Reward {
+        Reward {
+            id: Uuid::new_v4(),
+            player_id: player_id.to_string(),
+            game: GameType::Battle,
+            amount: Amount::from_lamports(lamports),
+            timestamp: Utc::now(),
+            claimed: false,
+            game_data: serde_json::Value::Null,
+            transaction_signature: None,
+            config_version: None,
+        }
+    }
+
+    /// Storage with two rewards, one of them queued for payout
+    async fn populated() -> (MemoryStorage, PayoutRequest) {
+        let storage = MemoryStorage::new();
+        let queued = reward("player1", 20);
+        storage.create_reward(&reward("player1", 10)).await.unwrap();
+        storage.create_reward(&queued).await.unwrap();
+        let payout = PayoutRequest::new("player1", "wallet", &[queued], Utc::now()).unwrap();
+        storage.enqueue_payout(&payout).await.unwrap();
+        (storage, payout)
+    }
+
+    async fn export(storage: &MemoryStorage) -> String {
+        let mut buffer = Vec::new();
+        export_snapshot(storage, &mut buffer).await.unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    fn invalid_message(result: Result<SnapshotSummary>) -> String {
+        match result {
+            Err(PokemonEngineError::InvalidSnapshot(message)) => message,
+            other => panic!("expected an invalid snapshot, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip_keeps_rewards_and_payouts() {
+        let (storage, payout) = populated().await;
+        let text = export(&storage).await;
+        assert!(text.starts_with(r#"{"type":"header","format":"pokemon-engine-snapshot","version":2"#));
+
+        let restored = MemoryStorage::new();
+        let summary = import_snapshot(text.as_bytes(), &restored).await.unwrap();
+        assert_eq!(summary, SnapshotSummary { rewards: 2, payouts: 1, ..SnapshotSummary::default() });
+        assert_eq!(restored.get_pending_rewards("player1").await.unwrap().len(), 1);
+        let restored_payout = restored.get_payout(&payout.id).await.unwrap().unwrap();
+        assert_eq!(serde_json::to_value(restored_payout).unwrap(), serde_json::to_value(&payout).unwrap());
+        assert_eq!(restored.get_total_unpaid_amount().await.unwrap(), 30);
+        assert_eq!(export(&restored).await.lines().count(), text.lines().count());
+    }
+
+    #[tokio::test]
+    async fn truncated_snapshots_are_rejected() {
+        let (storage, _) = populated().await;
+        let text = export(&storage).await;
+        let lines: Vec<&str> = text.lines().collect();
+
+        let without_footer = lines[..lines.len() - 1].join("\n");
+        let message = invalid_message(validate_snapshot(without_footer.as_bytes()));
+        assert!(message.starts_with("truncated after line 4"), "{}", message);
+
+        let cut_mid_line = &text[..text.len() - 10];
+        assert!(invalid_message(validate_snapshot(cut_mid_line.as_bytes())).starts_with("line 5:"));
+
+        assert_eq!(invalid_message(validate_snapshot("".as_bytes())), "empty snapshot");
+    }
+
+    #[tokio::test]
+    async fn footer_must_match_the_records() {
+        let (storage, _) = populated().await;
+        let text = export(&storage).await;
+        assert!(text.contains(r#""payouts":1}"#));
+
+        let tampered = text.replace(r#""payouts":1}"#, r#""payouts":2}"#);
+        let message = invalid_message(validate_snapshot(tampered.as_bytes()));
+        assert!(message.starts_with("line 5: footer counts"), "{}", message);
+    }
+
+    #[test]
+    fn payouts_must_follow_their_rewards() {
+        let payout = PayoutRequest::new("player1", "wallet", &[reward("player1", 5)], Utc::now()).unwrap();
+        let lines = [
+            serde_json::to_string(&SnapshotLine::Header {
+                format: SNAPSHOT_FORMAT.to_string(),
+                version: SNAPSHOT_VERSION,
+                created_at: Utc::now(),
+            })
+            .unwrap(),
+            serde_json::to_string(&SnapshotLine::Payout(payout.clone())).unwrap(),
+        ];
+        let message = invalid_message(validate_snapshot(lines.join("\n").as_bytes()));
+        assert!(message.contains(&format!("pays unknown reward {}", payout.reward_ids[0])), "{}", message);
+    }
+
+    #[test]
+    fn version_1_snapshots_are_still_accepted() {
+        let reward = serde_json::to_string(&SnapshotLine::Reward(reward("player1", 5))).unwrap();
+        let text = format!(
+            "{}\n{}\n{}\n",
+            r#"{"type":"header","format":"pokemon-engine-snapshot","version":1,"created_at":"2025-01-01T00:00:00Z"}"#,
+            reward,
+            r#"{"type":"footer","rewards":1,"daily_stats":0,"login_streaks":0}"#,
+        );
+        let summary = validate_snapshot(text.as_bytes()).unwrap();
+        assert_eq!(summary, SnapshotSummary { rewards: 1, ..SnapshotSummary::default() });
+
+        let future = text.replace(r#""version":1"#, r#""version":3"#);
+        assert!(invalid_message(validate_snapshot(future.as_bytes())).contains("unsupported version 3"));
+    }
+}
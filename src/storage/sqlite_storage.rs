@@ -0,0 +1,774 @@
+use crate::amount::Amount;
+use crate::models::{Reward, DailyStats, LoginStreak, GameType, PayoutRequest, PayoutStatus};
+use crate::error::{Result, PokemonEngineError};
+use crate::storage::{RewardPage, RewardQuery, RewardStatus, SortOrder, Storage, StorageVisitor};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Params};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+/// Embedded schema migrations, applied in order
+///
+/// The schema version stored in `PRAGMA user_version` is the number of
+/// migrations applied. Never edit a released migration; append a new one.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE rewards (
+        id TEXT PRIMARY KEY NOT NULL,
+        player_id TEXT NOT NULL,
+        game TEXT NOT NULL,
+        amount INTEGER NOT NULL,
+        timestamp TEXT NOT NULL,
+        claimed INTEGER NOT NULL DEFAULT 0,
+        game_data TEXT NOT NULL,
+        transaction_signature TEXT
+    );
+    CREATE INDEX idx_rewards_player ON rewards (player_id, timestamp);
+    CREATE INDEX idx_rewards_status ON rewards (claimed, player_id);
+    CREATE INDEX idx_rewards_signature ON rewards (transaction_signature)
+        WHERE transaction_signature IS NOT NULL;
+
+    CREATE TABLE daily_stats (
+        player_id TEXT NOT NULL,
+        date TEXT NOT NULL,
+        flypoke INTEGER NOT NULL,
+        battle INTEGER NOT NULL,
+        login INTEGER NOT NULL,
+        total INTEGER NOT NULL,
+        PRIMARY KEY (player_id, date)
+    );
+    CREATE INDEX idx_daily_stats_date ON daily_stats (date);
+
+    CREATE TABLE login_streaks (
+        player_id TEXT PRIMARY KEY NOT NULL,
+        current_streak INTEGER NOT NULL,
+        last_login_date TEXT NOT NULL
+    );",
+    // 2: payout outbox
+    "CREATE TABLE payouts (
+        id TEXT PRIMARY KEY NOT NULL,
+        player_id TEXT NOT NULL,
+        wallet TEXT NOT NULL,
+        reward_ids TEXT NOT NULL,
+        amount INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        attempts INTEGER NOT NULL,
+        next_attempt_at TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        signature TEXT,
+        last_valid_block_height INTEGER,
+        last_error TEXT
+    );
+    CREATE INDEX idx_payouts_due ON payouts (status, next_attempt_at);",
+    // 3: config version stamped on rewards
+    "ALTER TABLE rewards ADD COLUMN config_version TEXT;",
+    // 4: time each payout's latest transaction was sent
+    "ALTER TABLE payouts ADD COLUMN sent_at TEXT;",
+];
+
+const REWARD_COLUMNS: &str =
+    "id, player_id, game, amount, timestamp, claimed, game_data, transaction_signature, config_version";
+
+const PAYOUT_COLUMNS: &str = "id, player_id, wallet, reward_ids, amount, status, attempts, \
+    next_attempt_at, created_at, signature, last_valid_block_height, last_error, sent_at";
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// SQLite storage implementation (requires the `sqlite` feature)
+///
+/// Works with a database file or an in-memory database. The schema is
+/// migrated to the latest version when the storage is opened.
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+type RewardRow = (String, String, String, i64, String, bool, String, Option<String>, Option<String>);
+
+impl SqliteStorage {
+    /// Open (or create) a database file and migrate it
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::from_connection(conn)
+    }
+
+    /// Open a private in-memory database
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Use an already opened connection, migrating it to the latest schema
+    pub fn from_connection(mut conn: Connection) -> Result<Self> {
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Current schema version of the database
+    pub fn schema_version(&self) -> Result<usize> {
+        Ok(schema_version(&self.conn())?)
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn select_rewards<P: Params>(&self, condition: &str, params: P) -> Result<Vec<Reward>> {
+        self.select_rewards_ordered(condition, "timestamp", params)
+    }
+
+    fn select_rewards_ordered<P: Params>(&self, condition: &str, order_by: &str, params: P) -> Result<Vec<Reward>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM rewards WHERE {} ORDER BY {}",
+            REWARD_COLUMNS, condition, order_by
+        ))?;
+        let rows = stmt.query_map(params, read_reward_row)?;
+        rows.map(|row| decode_reward(row?)).collect()
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn create_reward(&self, reward: &Reward) -> Result<()> {
+        insert_reward(&self.conn(), reward)
+    }
+
+    async fn get_rewards(&self, player_id: &str) -> Result<Vec<Reward>> {
+        self.select_rewards("player_id = ?1", params![player_id])
+    }
+
+    async fn get_pending_rewards(&self, player_id: &str) -> Result<Vec<Reward>> {
+        self.select_rewards("player_id = ?1 AND claimed = 0", params![player_id])
+    }
+
+    async fn query_rewards(&self, player_id: &str, query: &RewardQuery) -> Result<RewardPage> {
+        let mut conditions = vec!["player_id = ?".to_string()];
+        let mut values = vec![Value::from(player_id.to_string())];
+
+        if let Some(game) = query.game {
+            conditions.push("game = ?".to_string());
+            values.push(Value::from(game.to_string()));
+        }
+        match query.status {
+            Some(RewardStatus::Pending) => conditions.push("claimed = 0".to_string()),
+            Some(RewardStatus::Claimed) => conditions.push("claimed = 1".to_string()),
+            Some(RewardStatus::Paid) => conditions.push("transaction_signature IS NOT NULL".to_string()),
+            None => {}
+        }
+        if let Some(from) = query.from {
+            conditions.push("timestamp >= ?".to_string());
+            values.push(Value::from(from.to_rfc3339()));
+        }
+        if let Some(until) = query.until {
+            conditions.push("timestamp < ?".to_string());
+            values.push(Value::from(until.to_rfc3339()));
+        }
+        if let Some(min_amount) = query.min_amount {
+            // Every stored amount fits in i64, so larger minimums match nothing
+            let Ok(min_amount) = to_i64(min_amount.lamports()) else {
+                return query.finish_page(Vec::new());
+            };
+            conditions.push("amount >= ?".to_string());
+            values.push(Value::from(min_amount));
+        }
+
+        let (after, direction) = match query.order {
+            SortOrder::OldestFirst => (">", "ASC"),
+            SortOrder::NewestFirst => ("<", "DESC"),
+        };
+        if let Some(cursor) = query.decode_cursor()? {
+            // RFC 3339 timestamps in UTC sort lexically in time order
+            conditions.push(format!("(timestamp {after} ? OR (timestamp = ? AND id {after} ?))"));
+            let timestamp = cursor.timestamp.to_rfc3339();
+            values.push(Value::from(timestamp.clone()));
+            values.push(Value::from(timestamp));
+            values.push(Value::from(cursor.id.to_string()));
+        }
+
+        let rewards = self.select_rewards_ordered(
+            &conditions.join(" AND "),
+            &format!("timestamp {direction}, id {direction} LIMIT {}", query.page_size() + 1),
+            params_from_iter(values),
+        )?;
+        query.finish_page(rewards)
+    }
+
+    async fn get_paid_rewards(&self) -> Result<Vec<Reward>> {
+        self.select_rewards("transaction_signature IS NOT NULL", [])
+    }
+
+    async fn set_transaction_signature(&self, reward_id: &uuid::Uuid, signature: &str) -> Result<()> {
+        let updated = self.conn().execute(
+            "UPDATE rewards SET transaction_signature = ?2 WHERE id = ?1",
+            params![reward_id.to_string(), signature],
+        )?;
+        ensure_found(updated, reward_id)
+    }
+
+    async fn get_total_pending_amount(&self) -> Result<u64> {
+        let total: i64 = self.conn().query_row(
+            "SELECT COALESCE(SUM(amount), 0) FROM rewards WHERE claimed = 0",
+            [],
+            |row| row.get(0),
+        )?;
+        to_u64(total)
+    }
+
+    async fn get_total_unpaid_amount(&self) -> Result<u64> {
+        let total: i64 = self.conn().query_row(
+            "SELECT COALESCE(SUM(amount), 0) FROM rewards WHERE transaction_signature IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        to_u64(total)
+    }
+
+    async fn mark_reward_claimed(&self, reward_id: &uuid::Uuid) -> Result<()> {
+        let updated = self.conn().execute(
+            "UPDATE rewards SET claimed = 1 WHERE id = ?1",
+            params![reward_id.to_string()],
+        )?;
+        ensure_found(updated, reward_id)
+    }
+
+    async fn mark_all_rewards_claimed(&self, player_id: &str) -> Result<()> {
+        self.conn().execute(
+            "UPDATE rewards SET claimed = 1 WHERE player_id = ?1 AND claimed = 0",
+            params![player_id],
+        )?;
+        Ok(())
+    }
+
+    async fn get_daily_stats(&self, player_id: &str, date: NaiveDate) -> Result<Option<DailyStats>> {
+        let row: Option<(i64, i64, i64, i64)> = self
+            .conn()
+            .query_row(
+                "SELECT flypoke, battle, login, total FROM daily_stats WHERE player_id = ?1 AND date = ?2",
+                params![player_id, date.format(DATE_FORMAT).to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?;
+
+        row.map(|(flypoke, battle, login, total)| {
+            Ok(DailyStats {
+                player_id: player_id.to_string(),
+                date,
+                flypoke: Amount::from_lamports(to_u64(flypoke)?),
+                battle: Amount::from_lamports(to_u64(battle)?),
+                login: Amount::from_lamports(to_u64(login)?),
+                total: Amount::from_lamports(to_u64(total)?),
+            })
+        })
+        .transpose()
+    }
+
+    async fn update_daily_stats(&self, stats: &DailyStats) -> Result<()> {
+        upsert_daily_stats(&self.conn(), stats)
+    }
+
+    async fn get_login_streak(&self, player_id: &str) -> Result<Option<LoginStreak>> {
+        let row: Option<(u32, String)> = self
+            .conn()
+            .query_row(
+                "SELECT current_streak, last_login_date FROM login_streaks WHERE player_id = ?1",
+                params![player_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        row.map(|(current_streak, last_login_date)| {
+            Ok(LoginStreak {
+                player_id: player_id.to_string(),
+                current_streak,
+                last_login_date: parse_date(&last_login_date)?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn update_login_streak(&self, streak: &LoginStreak) -> Result<()> {
+        upsert_login_streak(&self.conn(), streak)
+    }
+
+    async fn has_welcome_bonus(&self, player_id: &str) -> Result<bool> {
+        Ok(self.conn().query_row(
+            "SELECT EXISTS (SELECT 1 FROM rewards WHERE player_id = ?1 AND game = ?2)",
+            params![player_id, GameType::Welcome.to_string()],
+            |row| row.get(0),
+        )?)
+    }
+
+    async fn enqueue_payout(&self, payout: &PayoutRequest) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS (SELECT 1 FROM payouts WHERE id = ?1)",
+            params![payout.id.to_string()],
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Err(PokemonEngineError::AlreadyExists(format!("Payout {}", payout.id)));
+        }
+        for reward_id in &payout.reward_ids {
+            let updated = tx.execute(
+                "UPDATE rewards SET claimed = 1 WHERE id = ?1 AND player_id = ?2 AND claimed = 0",
+                params![reward_id.to_string(), payout.player_id],
+            )?;
+            if updated == 0 {
+                let claimed: Option<bool> = tx
+                    .query_row(
+                        "SELECT claimed FROM rewards WHERE id = ?1 AND player_id = ?2",
+                        params![reward_id.to_string(), payout.player_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                return Err(match claimed {
+                    Some(true) => PokemonEngineError::RewardAlreadyClaimed(reward_id.to_string()),
+                    _ => PokemonEngineError::NotFound(format!("Reward {}", reward_id)),
+                });
+            }
+        }
+        insert_payout(&tx, payout)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    async fn get_payout(&self, payout_id: &uuid::Uuid) -> Result<Option<PayoutRequest>> {
+        let payouts = select_payouts(&self.conn(), "id = ?1", params![payout_id.to_string()])?;
+        Ok(payouts.into_iter().next())
+    }
+
+    async fn due_payouts(&self, now: DateTime<Utc>, limit: usize) -> Result<Vec<PayoutRequest>> {
+        select_payouts(
+            &self.conn(),
+            "status = ?1 AND next_attempt_at <= ?2 ORDER BY next_attempt_at, id LIMIT ?3",
+            params![status_name(PayoutStatus::Pending), now.to_rfc3339(), limit as i64],
+        )
+    }
+
+    async fn update_payout(&self, payout: &PayoutRequest) -> Result<()> {
+        let updated = self.conn().execute(
+            "UPDATE payouts SET status = ?2, attempts = ?3, next_attempt_at = ?4, signature = ?5,
+                 last_valid_block_height = ?6, last_error = ?7, sent_at = ?8
+             WHERE id = ?1",
+            params![
+                payout.id.to_string(),
+                status_name(payout.status),
+                payout.attempts,
+                payout.next_attempt_at.to_rfc3339(),
+                payout.signature,
+                payout.last_valid_block_height.map(to_i64).transpose()?,
+                payout.last_error,
+                payout.sent_at.map(|t| t.to_rfc3339()),
+            ],
+        )?;
+        if updated == 0 {
+            return Err(PokemonEngineError::NotFound(format!("Payout {}", payout.id)));
+        }
+        Ok(())
+    }
+
+    async fn restore_payout(&self, payout: &PayoutRequest) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM payouts WHERE id = ?1", params![payout.id.to_string()])?;
+        insert_payout(&tx, payout)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    async fn export(&self, visitor: &mut (dyn StorageVisitor + Send)) -> Result<()> {
+        let conn = self.conn();
+
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM rewards", REWARD_COLUMNS))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            visitor.visit_reward(&decode_reward(read_reward_row(row)?)?)?;
+        }
+
+        let mut stmt = conn.prepare("SELECT player_id, date, flypoke, battle, login, total FROM daily_stats")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let date: String = row.get(1)?;
+            visitor.visit_daily_stats(&DailyStats {
+                player_id: row.get(0)?,
+                date: parse_date(&date)?,
+                flypoke: Amount::from_lamports(to_u64(row.get(2)?)?),
+                battle: Amount::from_lamports(to_u64(row.get(3)?)?),
+                login: Amount::from_lamports(to_u64(row.get(4)?)?),
+                total: Amount::from_lamports(to_u64(row.get(5)?)?),
+            })?;
+        }
+
+        let mut stmt = conn.prepare("SELECT player_id, current_streak, last_login_date FROM login_streaks")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let last_login_date: String = row.get(2)?;
+            visitor.visit_login_streak(&LoginStreak {
+                player_id: row.get(0)?,
+                current_streak: row.get(1)?,
+                last_login_date: parse_date(&last_login_date)?,
+            })?;
+        }
+
+        for payout in select_payouts(&conn, "1 = 1", [])? {
+            visitor.visit_payout(&payout)?;
+        }
+        Ok(())
+    }
+
+    async fn record_reward(
+        &self,
+        reward: &Reward,
+        stats: Option<&DailyStats>,
+        streak: Option<&LoginStreak>,
+    ) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        if let Some(streak) = streak {
+            upsert_login_streak(&tx, streak)?;
+        }
+        insert_reward(&tx, reward)?;
+        if let Some(stats) = stats {
+            upsert_daily_stats(&tx, stats)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// Apply every migration newer than the database's schema version
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let current = schema_version(conn)?;
+    if current > MIGRATIONS.len() {
+        return Err(PokemonEngineError::Corrupt(format!(
+            "Database schema version {} is newer than supported version {}",
+            current,
+            MIGRATIONS.len()
+        )));
+    }
+
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", (index + 1) as i64)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn schema_version(conn: &Connection) -> rusqlite::Result<usize> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    Ok(version as usize)
+}
+
+/// Insert a reward, replacing (and possibly moving) one with the same id
+fn insert_reward(conn: &Connection, reward: &Reward) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO rewards ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT (id) DO UPDATE SET
+                 player_id = excluded.player_id,
+                 game = excluded.game,
+                 amount = excluded.amount,
+                 timestamp = excluded.timestamp,
+                 claimed = excluded.claimed,
+                 game_data = excluded.game_data,
+                 transaction_signature = excluded.transaction_signature,
+                 config_version = excluded.config_version",
+            REWARD_COLUMNS
+        ),
+        params![
+            reward.id.to_string(),
+            reward.player_id,
+            reward.game.to_string(),
+            to_i64(reward.amount.lamports())?,
+            reward.timestamp.to_rfc3339(),
+            reward.claimed,
+            serde_json::to_string(&reward.game_data)?,
+            reward.transaction_signature,
+            reward.config_version,
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_payout(conn: &Connection, payout: &PayoutRequest) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO payouts ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            PAYOUT_COLUMNS
+        ),
+        params![
+            payout.id.to_string(),
+            payout.player_id,
+            payout.wallet,
+            serde_json::to_string(&payout.reward_ids)?,
+            to_i64(payout.amount.lamports())?,
+            status_name(payout.status),
+            payout.attempts,
+            payout.next_attempt_at.to_rfc3339(),
+            payout.created_at.to_rfc3339(),
+            payout.signature,
+            payout.last_valid_block_height.map(to_i64).transpose()?,
+            payout.last_error,
+            payout.sent_at.map(|t| t.to_rfc3339()),
+        ],
+    )?;
+    Ok(())
+}
+
+fn upsert_daily_stats(conn: &Connection, stats: &DailyStats) -> Result<()> {
+    conn.execute(
+        "INSERT INTO daily_stats (player_id, date, flypoke, battle, login, total)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (player_id, date) DO UPDATE SET
+             flypoke = excluded.flypoke,
+             battle = excluded.battle,
+             login = excluded.login,
+             total = excluded.total",
+        params![
+            stats.player_id,
+            stats.date.format(DATE_FORMAT).to_string(),
+            to_i64(stats.flypoke.lamports())?,
+            to_i64(stats.battle.lamports())?,
+            to_i64(stats.login.lamports())?,
+            to_i64(stats.total.lamports())?,
+        ],
+    )?;
+    Ok(())
+}
+
+fn upsert_login_streak(conn: &Connection, streak: &LoginStreak) -> Result<()> {
+    conn.execute(
+        "INSERT INTO login_streaks (player_id, current_streak, last_login_date)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT (player_id) DO UPDATE SET
+             current_streak = excluded.current_streak,
+             last_login_date = excluded.last_login_date",
+        params![
+            streak.player_id,
+            streak.current_streak,
+            streak.last_login_date.format(DATE_FORMAT).to_string(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn read_reward_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RewardRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+        row.get(8)?,
+    ))
+}
+
+fn decode_reward(row: RewardRow) -> Result<Reward> {
+    let (id, player_id, game, amount, timestamp, claimed, game_data, transaction_signature, config_version) = row;
+    Ok(Reward {
+        id: uuid::Uuid::parse_str(&id)
+            .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid reward id {}: {}", id, e)))?,
+        player_id,
+        game: serde_json::from_value(serde_json::Value::String(game))?,
+        amount: Amount::from_lamports(to_u64(amount)?),
+        timestamp: parse_timestamp(&timestamp)?,
+        claimed,
+        game_data: serde_json::from_str(&game_data)?,
+        transaction_signature,
+        config_version,
+    })
+}
+
+fn select_payouts<P: Params>(conn: &Connection, condition: &str, params: P) -> Result<Vec<PayoutRequest>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM payouts WHERE {}", PAYOUT_COLUMNS, condition))?;
+    let mut rows = stmt.query(params)?;
+    let mut payouts = Vec::new();
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let reward_ids: String = row.get(3)?;
+        let status: String = row.get(5)?;
+        let next_attempt_at: String = row.get(7)?;
+        let created_at: String = row.get(8)?;
+        let last_valid_block_height: Option<i64> = row.get(10)?;
+        let sent_at: Option<String> = row.get(12)?;
+        payouts.push(PayoutRequest {
+            id: uuid::Uuid::parse_str(&id)
+                .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid payout id {}: {}", id, e)))?,
+            player_id: row.get(1)?,
+            wallet: row.get(2)?,
+            reward_ids: serde_json::from_str(&reward_ids)?,
+            amount: Amount::from_lamports(to_u64(row.get(4)?)?),
+            status: serde_json::from_value(serde_json::Value::String(status))?,
+            attempts: row.get(6)?,
+            next_attempt_at: parse_timestamp(&next_attempt_at)?,
+            created_at: parse_timestamp(&created_at)?,
+            signature: row.get(9)?,
+            last_valid_block_height: last_valid_block_height.map(to_u64).transpose()?,
+            last_error: row.get(11)?,
+            sent_at: sent_at.as_deref().map(parse_timestamp).transpose()?,
+        });
+    }
+    Ok(payouts)
+}
+
+fn status_name(status: PayoutStatus) -> &'static str {
+    match status {
+        PayoutStatus::Pending => "pending",
+        PayoutStatus::Sent => "sent",
+        PayoutStatus::Failed => "failed",
+    }
+}
+
+fn parse_timestamp(timestamp: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid timestamp {}: {}", timestamp, e)))
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT)
+        .map_err(|e| PokemonEngineError::Corrupt(format!("Invalid date {}: {}", date, e)))
+}
+
+fn ensure_found(updated: usize, reward_id: &uuid::Uuid) -> Result<()> {
+    if updated == 0 {
+        Err(PokemonEngineError::NotFound(format!("Reward {}", reward_id)))
+    } else {
+        Ok(())
+    }
+}
+
+/// SQLite integers are signed, so amounts above `i64::MAX` cannot be stored
+fn to_i64(amount: u64) -> Result<i64> {
+    i64::try_from(amount)
+        .map_err(|_| PokemonEngineError::AmountOverflow(format!("Amount too large to store: {}", amount)))
+}
+
+fn to_u64(amount: i64) -> Result<u64> {
+    u64::try_from(amount)
+        .map_err(|_| PokemonEngineError::Corrupt(format!("Negative amount in database: {}", amount)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("pokemon-engine-{}.sqlite", uuid::Uuid::new_v4()))
+    }
+
+    fn remove_db(path: &Path) {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut file = path.as_os_str().to_owned();
+            file.push(suffix);
+            let _ = std::fs::remove_file(file);
+        }
+    }
+
+    fn reward(player_id: &str) -> Reward {
+        Reward {
+            id: uuid::Uuid::new_v4(),
+            player_id: player_id.to_string(),
+            game: GameType::Battle,
+            amount: Amount::from_lamports(50_000_000_000),
+            timestamp: Utc::now(),
+            claimed: false,
+            game_data: serde_json::json!({ "won": true }),
+            transaction_signature: None,
+            config_version: Some("abc123".to_string()),
+        }
+    }
+
+    #[test]
+    fn new_databases_are_at_the_latest_version() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        assert_eq!(storage.schema_version().unwrap(), MIGRATIONS.len());
+    }
+
+    #[tokio::test]
+    async fn version_1_databases_are_upgraded_with_rows_intact() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        let id = uuid::Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO rewards (id, player_id, game, amount, timestamp, claimed, game_data)
+             VALUES (?1, 'player1', 'battle', 50, '2024-01-15T12:00:00+00:00', 0, '{}')",
+            params![id.to_string()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO login_streaks (player_id, current_streak, last_login_date) VALUES ('player1', 4, '2024-01-15')",
+            [],
+        )
+        .unwrap();
+
+        let storage = SqliteStorage::from_connection(conn).unwrap();
+        assert_eq!(storage.schema_version().unwrap(), MIGRATIONS.len());
+
+        let rewards = storage.get_rewards("player1").await.unwrap();
+        assert_eq!(rewards.len(), 1);
+        assert_eq!(rewards[0].id, id);
+        assert_eq!(rewards[0].amount, Amount::from_lamports(50));
+        assert_eq!(rewards[0].config_version, None);
+        let streak = storage.get_login_streak("player1").await.unwrap().unwrap();
+        assert_eq!(streak.current_streak, 4);
+        assert!(storage.due_payouts(Utc::now(), 10).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn newer_schema_versions_are_refused() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", (MIGRATIONS.len() + 1) as i64).unwrap();
+        assert!(matches!(
+            SqliteStorage::from_connection(conn),
+            Err(PokemonEngineError::Corrupt(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn database_files_survive_reopening() {
+        let path = temp_db_path();
+        let reward = reward("player1");
+        let date = reward.timestamp.date_naive();
+        {
+            let storage = SqliteStorage::open(&path).unwrap();
+            storage.create_reward(&reward).await.unwrap();
+            storage
+                .update_daily_stats(&DailyStats {
+                    player_id: "player1".to_string(),
+                    date,
+                    flypoke: Amount::ZERO,
+                    battle: reward.amount,
+                    login: Amount::ZERO,
+                    total: reward.amount,
+                })
+                .await
+                .unwrap();
+        }
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        assert_eq!(storage.schema_version().unwrap(), MIGRATIONS.len());
+        let rewards = storage.get_rewards("player1").await.unwrap();
+        assert_eq!(rewards.len(), 1);
+        assert_eq!(rewards[0].id, reward.id);
+        assert_eq!(rewards[0].amount, reward.amount);
+        assert_eq!(rewards[0].game_data, reward.game_data);
+        assert_eq!(rewards[0].config_version, reward.config_version);
+        let stats = storage.get_daily_stats("player1", date).await.unwrap().unwrap();
+        assert_eq!(stats.battle, reward.amount);
+        assert_eq!(stats.total, reward.amount);
+        drop(storage);
+        remove_db(&path);
+    }
+}
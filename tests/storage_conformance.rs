@@ -0,0 +1,20 @@
+//! Runs the `Storage` conformance suite against every bundled backend
+
+mod memory {
+    use pokemon_engine::storage::MemoryStorage;
+
+    pokemon_engine::storage_conformance_tests!(MemoryStorage::new);
+}
+
+mod sled {
+    use pokemon_engine::storage::SledStorage;
+
+    pokemon_engine::storage_conformance_tests!(|| SledStorage::temporary().unwrap());
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use pokemon_engine::storage::SqliteStorage;
+
+    pokemon_engine::storage_conformance_tests!(|| SqliteStorage::open_in_memory().unwrap());
+}